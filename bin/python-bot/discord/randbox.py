@@ -1,32 +1,34 @@
 from __future__ import annotations
 
-from nonebot.adapters import Message
+from nonebot.adapters import Bot, Event, Message
 from nonebot.params import CommandArg
 from nonebot.adapters.discord import MessageSegment
 
 import aiohttp
 from monad_std.prelude import siter
 
-from lootbox.utils import box_command
+from lootbox.utils import box_command, user_id
 from lootbox.randbox import (
     BOX_NON_PLAIN_TEXT_PARAM_TEXT,
     BOX_WRONG_PARAM_TEXT,
     BACKEND_URL,
+    INFO_URL,
     RandMessage,
 )
 
 
 rand_box = box_command(("",))
+box_info = box_command(("info",), priority=0, block=True)
 
 
 @rand_box.handle()
-async def rand_handle(args: Message = CommandArg()):
+async def rand_handle(bot: Bot, event: Event, args: Message = CommandArg()):
     if not siter(args).fold(True, lambda acc, x: acc and x.is_text()):
         await rand_box.finish(BOX_NON_PLAIN_TEXT_PARAM_TEXT)
 
     text = args.extract_plain_text()
     arg = text.split(" ")
-    if len(arg) != 2:
+    if len(arg) not in (2, 3):
         await rand_box.finish(BOX_WRONG_PARAM_TEXT)
 
     name_pat = str(arg[0])
@@ -39,6 +41,8 @@ async def rand_handle(args: Message = CommandArg()):
         "lang": "zh-sg",
         "box_name": name_pat,
         "amount": amount,
+        "user_id": user_id(bot, event),
+        "seed": arg[2] if len(arg) == 3 else None,
     }
 
     async with aiohttp.ClientSession() as session:
@@ -51,8 +55,35 @@ async def rand_handle(args: Message = CommandArg()):
                     .collect_list()
                 )
                 await rand_box.finish(data)
+            else:
+                await rand_box.finish(resp["brief"])
 
 
+
+@box_info.handle()
+async def info_handle(args: Message = CommandArg()):
+    box_name = args.extract_plain_text().strip()
+    if not box_name:
+        await box_info.finish(BOX_WRONG_PARAM_TEXT)
+
+    param = {
+        "lang": "zh-sg",
+        "box_name": box_name,
+    }
+
+    async with aiohttp.ClientSession() as session:
+        async with session.post(INFO_URL, json=param) as response:
+            resp = await response.json()
+            if resp["status"] == "ok":
+                data = (
+                    siter(resp["data"])
+                    .map(lambda t: message_to_local(RandMessage.parse(t)))
+                    .collect_list()
+                )
+                await box_info.finish(data)
+            else:
+                await box_info.finish(resp["brief"])
+
 def message_to_local(msg: RandMessage) -> MessageSegment | None:
     if msg.image:
         with open(msg.image, 'rb') as image:
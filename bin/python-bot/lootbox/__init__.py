@@ -1,4 +1,5 @@
 from . import help
 from . import randbox
+from . import state
 
 from . import utils
@@ -4,9 +4,12 @@ from .utils import box_command
 bot_help = box_command(("help",))
 
 BOT_HELP_TEXT = """使用方法：
-box <物品名称> <数量>
+box <物品名称> <数量> [种子]
+box info <物品名称> 查看箱子内容及概率
 示例：
 box 超级补给箱 100
+box state 查看保存的保底进度
+box reset [物品名称] 重置保底进度
 备注：
 有多个相似名称时会出错"""
 
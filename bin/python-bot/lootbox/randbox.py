@@ -2,7 +2,8 @@ from __future__ import annotations
 
 
 BOT_HELP_TEXT = """使用方法：
-box <物品名称> <数量>
+box <物品名称> <数量> [种子]
+box info <物品名称> 查看箱子内容及概率
 示例：
 box 超级补给箱 100
 备注：
@@ -15,6 +16,9 @@ BOX_NON_PLAIN_TEXT_PARAM_TEXT = (
 )
 
 BACKEND_URL = "http://localhost:8080/lootbox/rand"
+INFO_URL = "http://localhost:8080/lootbox/info"
+USER_URL = "http://localhost:8080/lootbox/user"
+USER_RESET_URL = "http://localhost:8080/lootbox/user/reset"
 
 class RandMessage:
     image: str | None
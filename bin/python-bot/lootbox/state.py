@@ -0,0 +1,49 @@
+from nonebot.adapters import Bot, Event, Message
+from nonebot.params import CommandArg
+
+import aiohttp
+
+from .utils import box_command, user_id
+from .randbox import USER_URL, USER_RESET_URL
+
+
+box_state = box_command(("state",), priority=0, block=True)
+box_reset = box_command(("reset",), priority=0, block=True)
+
+
+def text_messages(resp: dict) -> str | None:
+    if resp["status"] != "ok":
+        return None
+    return "\n".join(
+        v for t in resp["data"] for (ty, v) in t.items() if ty.lower() == "text"
+    )
+
+
+@box_state.handle()
+async def state_handle(bot: Bot, event: Event):
+    param = {
+        "lang": "zh-sg",
+        "user_id": user_id(bot, event),
+    }
+
+    async with aiohttp.ClientSession() as session:
+        async with session.get(USER_URL, params=param) as response:
+            text = text_messages(await response.json())
+            if text:
+                await box_state.finish(text)
+
+
+@box_reset.handle()
+async def reset_handle(bot: Bot, event: Event, args: Message = CommandArg()):
+    box_name = args.extract_plain_text().strip()
+    param = {
+        "lang": "zh-sg",
+        "user_id": user_id(bot, event),
+        "box_name": box_name or None,
+    }
+
+    async with aiohttp.ClientSession() as session:
+        async with session.post(USER_RESET_URL, json=param) as response:
+            text = text_messages(await response.json())
+            if text:
+                await box_reset.finish(text)
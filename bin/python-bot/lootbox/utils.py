@@ -1,4 +1,5 @@
 from nonebot import on_command
+from nonebot.adapters import Bot, Event
 from nonebot.internal.matcher import Matcher
 from monad_std.prelude import *
 
@@ -13,3 +14,7 @@ def box_command(args: tuple[str, ...], *aargs, **kwargs) -> type[Matcher]:
     first = base_it.next().unwrap()
     al = set(base_it.to_iter())
     return on_command(first, aliases=al, force_whitespace=False, *aargs, **kwargs)
+
+
+def user_id(bot: Bot, event: Event) -> str:
+    return f"{bot.adapter.get_name().lower()}:{event.get_user_id()}"
@@ -0,0 +1,21 @@
+/// Human-readable changes made by an update, printed once it is done.
+#[derive(Debug, Default)]
+pub struct Changelog {
+    entries: Vec<String>,
+}
+
+impl Changelog {
+    pub fn push(&mut self, entry: String) {
+        self.entries.push(entry);
+    }
+
+    pub fn print(&self, title: &str) {
+        println!("{title}:");
+        if self.entries.is_empty() {
+            println!("  (no change)");
+        }
+        for entry in self.entries.iter() {
+            println!("  {entry}");
+        }
+    }
+}
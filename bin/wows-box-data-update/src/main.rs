@@ -1,24 +1,27 @@
 use std::{
     env,
-    panic::{self, PanicInfo},
+    panic::{self, PanicHookInfo},
     time::Instant,
 };
 
+use anyhow::anyhow;
 use dotenvy::dotenv;
 use log::{error, info};
-use mongodb::{
-    bson::doc,
-    options::{ClientOptions, ServerApi, ServerApiVersion},
-    Client,
-};
+use wows_box::store::{AnyStore, FileStore};
+
+use changelog::Changelog;
 // use human_panic::setup_panic;
 
+mod changelog;
+mod prefetch_icons;
+mod snapshot;
 mod update_boxlist;
 mod update_currency;
 mod update_items;
 mod update_lang_list;
+mod update_prices;
 
-const LANGUAGE_LIST: &'static [&'static str] = &["zh-sg", "en"];
+const LANGUAGE_LIST: &[&str] = &["zh-sg", "en"];
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -27,47 +30,75 @@ async fn main() -> anyhow::Result<()> {
     // setup_panic!();
     dotenv().ok();
 
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
     info!("Starting update wows data...");
     let time_c = Instant::now();
 
-    info!("Started connecting to database...");
-    let time = Instant::now();
-    let mut client_options = ClientOptions::parse(env::var("MONGODB_CONN")?).await?;
+    match args[..] {
+        [] => {
+            let store = connect().await?;
+            update_lang_list::update_lang_list(LANGUAGE_LIST, &store).await?;
 
-    // Set the server_api field of the client_options object to Stable API version 1
-    let server_api = ServerApi::builder().version(ServerApiVersion::V1).build();
-    client_options.server_api = Some(server_api);
+            for lang in LANGUAGE_LIST {
+                let mut changelog = Changelog::default();
+                update_boxlist::update_boxlist(lang, &store, &mut changelog).await?;
+                update_items::update_items(lang, &store, &mut changelog).await?;
+                update_currency::update_currency(lang, &store).await?;
+                update_prices::update_prices(lang, &store).await?;
+                changelog.print(&format!("Changes [lang {lang}]"));
+            }
+        }
+        ["prefetch-icons"] => {
+            let store = connect().await?;
+            for lang in LANGUAGE_LIST {
+                prefetch_icons::prefetch_icons(lang, &store).await?;
+            }
+        }
+        ["export-snapshot", path] => {
+            // A document left out would be missing from every import.
+            let store = connect().await?.strict();
+            snapshot::export_snapshot(path, LANGUAGE_LIST, &store).await?;
+        }
+        ["import-snapshot", path] => {
+            let store = connect().await?;
+            snapshot::import_snapshot(path, &store).await?;
+        }
+        // Into a directory of JSON files, regardless of the environment.
+        ["import-snapshot", path, dir] => {
+            snapshot::import_snapshot(path, &FileStore::new(dir)).await?;
+        }
+        _ => return Err(anyhow!("Unknown command: {}", args.join(" "))),
+    }
+
+    info!(
+        "Successfully finished update in {:.2}s.",
+        time_c.elapsed().as_secs_f64()
+    );
+    Ok(())
+}
 
-    // Create a new client and connect to the server
-    let client = Client::with_options(client_options)?;
+/// The store configured by the environment, see [`AnyStore::from_env`].
+async fn connect() -> anyhow::Result<AnyStore> {
+    info!("Started connecting to database...");
+    let time = Instant::now();
+    let store = AnyStore::from_env().await?;
     info!(
         "Successfully build connection to database in {:.2}s.",
         time.elapsed().as_secs_f64()
     );
 
     // Send a ping to confirm a successful connection
-    client
-        .database("admin")
-        .run_command(doc! { "ping": 1 })
-        .await?;
-    info!("Pinged database, connection verified.");
-
-    update_lang_list::update_lang_list(LANGUAGE_LIST, &client).await?;
-
-    for lang in LANGUAGE_LIST {
-        update_boxlist::update_boxlist(lang, &client).await?;
-        update_items::update_items(lang, &client).await?;
-        update_currency::update_currency(lang, &client).await?;
+    if let AnyStore::Mongo(mongo) = &store {
+        mongo.ping().await?;
+        info!("Pinged database, connection verified.");
     }
 
-    info!(
-        "Successfully finished update in {:.2}s.",
-        time_c.elapsed().as_secs_f64()
-    );
-    Ok(())
+    Ok(store)
 }
 
-fn panic_handler(panic_info: &PanicInfo) {
+fn panic_handler(panic_info: &PanicHookInfo) {
     error!("Panic occurred: {}", panic_info);
     std::process::exit(1);
 }
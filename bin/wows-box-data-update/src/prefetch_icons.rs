@@ -0,0 +1,21 @@
+use std::time::Instant;
+
+use log::info;
+use wows_box::store::LootboxStore;
+use wows_box_render::icon::ICON_CACHE;
+
+pub async fn prefetch_icons(lang: &str, store: &impl LootboxStore) -> anyhow::Result<()> {
+    info!("Started prefetching wows icons [lang {}]...", lang);
+    let time_c = Instant::now();
+
+    let stored = ICON_CACHE.prefetch(lang, store).await?;
+
+    info!(
+        "Stored {} icons in {} in {:.2}s",
+        stored,
+        ICON_CACHE.dir().display(),
+        time_c.elapsed().as_secs_f64()
+    );
+
+    Ok(())
+}
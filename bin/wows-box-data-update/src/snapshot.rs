@@ -0,0 +1,51 @@
+use std::{env, time::Instant};
+
+use log::info;
+
+use wows_box::{snapshot::Snapshot, store::LootboxStore};
+
+pub async fn export_snapshot(
+    path: &str,
+    langs: &[&str],
+    store: &impl LootboxStore,
+) -> anyhow::Result<()> {
+    info!("Started exporting snapshot to {}...", path);
+    let time_c = Instant::now();
+
+    let snapshot = Snapshot::export(store, langs, env::var("WOWS_WEB_VERSION").ok()).await?;
+    snapshot.save(path)?;
+
+    info!(
+        "Exported {} records in {:.2}s",
+        snapshot.manifest.records,
+        time_c.elapsed().as_secs_f64()
+    );
+
+    Ok(())
+}
+
+pub async fn import_snapshot(path: &str, store: &impl LootboxStore) -> anyhow::Result<()> {
+    info!("Started importing snapshot from {}...", path);
+    let time_c = Instant::now();
+
+    let snapshot = Snapshot::load(path)?;
+    info!(
+        "Loaded snapshot of {:?}, fetched at {} with web version {}",
+        snapshot.manifest.langs,
+        snapshot.manifest.fetched_at,
+        snapshot
+            .manifest
+            .wows_web_version
+            .as_deref()
+            .unwrap_or("unknown")
+    );
+    snapshot.import(store).await?;
+
+    info!(
+        "Imported {} records in {:.2}s",
+        snapshot.manifest.records,
+        time_c.elapsed().as_secs_f64()
+    );
+
+    Ok(())
+}
@@ -1,44 +1,139 @@
-use std::time::Instant;
+use std::{collections::HashSet, time::Instant};
 
 use anyhow::anyhow;
-use bson::doc;
-use log::{debug, info};
-use mongodb::Client;
+use log::{debug, error, info, warn};
+use wows_box::{
+    history::{self, unix_now},
+    lootbox::LootBox,
+    store::LootboxStore,
+    validate::LootBoxIssue,
+};
 
-pub async fn update_boxlist(lang: &str, client: &Client) -> anyhow::Result<()> {
+use wows_box_fetch::{client::FETCH_CLIENT, lootbox::fetch_lootbox};
+
+use crate::changelog::Changelog;
+
+/// Store the lootboxes which are new or changed, and retire the ones no longer
+/// listed. Every version is kept in the history.
+pub async fn update_boxlist(
+    lang: &str,
+    store: &impl LootboxStore,
+    changelog: &mut Changelog,
+) -> anyhow::Result<()> {
     info!("Started updating lootbox list [lang {}]...", lang);
     let time_c = Instant::now();
 
-    let box_db = client.database(&format!("wowslootbox-{lang}"));
-    let box_list_collection = box_db.collection("list");
-
     debug!("Started fetching box list...");
     let time = Instant::now();
     let box_list = wows_box_fetch::list::fetch_list(lang).await?;
     debug!("Fetched box list in {:.2}s", time.elapsed().as_secs_f64());
 
-    for item in box_list {
-        let id = item.id;
-        debug!("Fetching item: {}", id);
-        let res = box_list_collection
-            .find_one(doc! { "id": id as u32 })
-            .await?;
-        if res.is_some() {
-            debug!("Duplicate item: {}", id);
+    let now = unix_now();
+    let listed: HashSet<_> = box_list.iter().map(|t| t.id).collect();
+
+    debug!("Started fetching {} box details...", listed.len());
+    let time = Instant::now();
+    let report = FETCH_CLIENT
+        .fetch_all(box_list.iter().map(|t| t.id), |id| async move {
+            fetch_lootbox(lang, id)
+                .await?
+                .ok()
+                .ok_or(anyhow!("Unable to fetch box detail"))
+        })
+        .await;
+    debug!(
+        "Fetched box details in {:.2}s",
+        time.elapsed().as_secs_f64()
+    );
+    for (id, e) in report.failed.iter() {
+        error!("Cannot fetch lootbox {}: {:?}", id, e);
+    }
+
+    let mut rejected = vec![];
+    // Written once at the end, as a file store rewrites a whole collection on
+    // every write. Unchanged lootboxes are recorded too, which starts the
+    // history of the ones stored before it was kept.
+    let mut recorded = vec![];
+    let mut changed = vec![];
+    for (id, box_detail) in report.fetched {
+        let box_detail = box_detail.into_standrad();
+        let issues = box_detail.validate();
+        report_issues(&box_detail, &issues);
+        if issues.iter().any(LootBoxIssue::is_fatal) {
+            rejected.push(id);
             continue;
         }
-        let time = Instant::now();
-        let box_detail = wows_box_fetch::lootbox::fetch_lootbox(lang, id)
-            .await?
-            .ok()
-            .ok_or(anyhow!("Unable to fetch box detail"))?;
-        debug!("Fetched box detail in {:.2}s", time.elapsed().as_secs_f64());
-        box_list_collection
-            .insert_one(box_detail.into_standrad())
-            .await?;
+
+        match store.get_box(lang, id).await? {
+            Some(stored) if stored == box_detail => {
+                debug!("Unchanged lootbox: {}", id);
+                recorded.push(box_detail);
+                continue;
+            }
+            Some(stored) => {
+                for change in stored.diff(&box_detail) {
+                    changelog.push(format!("Lootbox {} ({}): {}", id, box_detail.name, change));
+                }
+            }
+            None => changelog.push(format!("Lootbox {} ({}): added", id, box_detail.name)),
+        }
+        changed.push(box_detail.clone());
+        recorded.push(box_detail);
     }
 
+    // Kept, as seeds and histories may still refer to them.
+    for stored in store.list_boxes(lang).await? {
+        if stored.retired || listed.contains(&stored.id) {
+            continue;
+        }
+        let retired = LootBox {
+            retired: true,
+            ..stored
+        };
+        changelog.push(format!(
+            "Lootbox {} ({}): retired",
+            retired.id, retired.name
+        ));
+        recorded.push(retired.clone());
+        changed.push(retired);
+    }
+
+    history::record_all(store, lang, &recorded, now).await?;
+    store.upsert_boxes(lang, &changed).await?;
+
+    if !report.failed.is_empty() {
+        let failed: Vec<_> = report.failed.iter().map(|(id, _)| *id).collect();
+        warn!(
+            "Failed to fetch {} lootboxes, kept as stored [lang {}]: {:?}",
+            failed.len(),
+            lang,
+            failed
+        );
+    }
+    if !rejected.is_empty() {
+        warn!(
+            "Refused to publish {} broken lootboxes [lang {}]: {:?}",
+            rejected.len(),
+            lang,
+            rejected
+        );
+    }
     info!("Updated box list in {:.2}s", time_c.elapsed().as_secs_f64());
 
     Ok(())
 }
+
+/// Print the validation result of a single lootbox.
+fn report_issues(lootbox: &LootBox, issues: &[LootBoxIssue]) {
+    if issues.is_empty() {
+        info!("Lootbox {} ({}): ok", lootbox.id, lootbox.name);
+        return;
+    }
+    for issue in issues {
+        if issue.is_fatal() {
+            error!("Lootbox {} ({}): {}", lootbox.id, lootbox.name, issue);
+        } else {
+            warn!("Lootbox {} ({}): {}", lootbox.id, lootbox.name, issue);
+        }
+    }
+}
@@ -1,88 +1,83 @@
-use std::time::Instant;
+use std::{collections::HashSet, time::Instant};
 
-use bson::doc;
-use log::{debug, info};
-use mongodb::{Client, Collection};
-use serde::{Deserialize, Serialize};
+use log::{debug, error, info, warn};
 
-use wows_box::{
-    item::ItemData,
-    lootbox::{LootBox, LootBoxReward},
-};
-use wows_box_fetch::{album::fetch_album, item::fetch_item};
+use wows_box::{item::ItemData, store::LootboxStore};
+use wows_box_fetch::{album::fetch_album, client::FETCH_CLIENT, item::fetch_item};
 
-pub async fn update_items(lang: &str, client: &Client) -> anyhow::Result<()> {
+use crate::changelog::Changelog;
+
+/// Store the rewards which are new or changed.
+pub async fn update_items(
+    lang: &str,
+    store: &impl LootboxStore,
+    changelog: &mut Changelog,
+) -> anyhow::Result<()> {
     info!("Started updating item static data [lang {}]...", lang);
     let time_c = Instant::now();
 
-    let box_db = client.database(&format!("wowslootbox-{lang}"));
-    let lootbox_collection: Collection<LootBox> = box_db.collection("list");
-    let items_collection: Collection<ItemData> = box_db.collection("items");
-
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    struct ConcatResponse {
-        rewards: Vec<Vec<Vec<LootBoxReward>>>,
-    }
-
     debug!("Started processing box list...");
     let time = Instant::now();
-    let mut rewards = lootbox_collection
-        .aggregate(vec![doc! {
-            "$project": {
-                "rewards": {
-                    "$concatArrays": [
-                        "$slots.common.rewards",
-                        "$slots.valuable.rewards",
-                    ]
-                }
-            }
-        }])
-        .allow_disk_use(true)
-        .await?
-        .with_type::<ConcatResponse>();
+    let boxes = store.list_boxes(lang).await?;
     debug!("Fetched box list in {:.2}s", time.elapsed().as_secs_f64());
 
     use wows_box::lootbox::LootBoxRewardType::*;
 
-    while rewards.advance().await? {
-        let curr: ConcatResponse = rewards.deserialize_current()?;
-        for reward in curr.rewards.into_iter().flatten().flatten() {
-            match reward.reward {
-                CamoBoost { id } | Signal { id, .. } => {
-                    debug!("Fetch reward item {}", id);
-                    let res = items_collection.find_one(doc! { "id": id as u32 }).await?;
-                    if res.is_some() {
-                        debug!("Duplicate item: {}", id);
-                        continue;
-                    }
-                    let time = Instant::now();
-                    let fetched = fetch_item(lang, id).await?.into_standard();
-                    debug!(
-                        "Fetched item detail in {:.2}s",
-                        time.elapsed().as_secs_f64()
-                    );
-                    items_collection.insert_one(fetched).await?;
-                }
-                CollectionAlbum { id } => {
-                    debug!("Fetch reward item(album) {}", id);
-                    let res = items_collection.find_one(doc! { "id": id as u32 }).await?;
-                    if res.is_some() {
-                        debug!("Duplicate item(album): {}", id);
-                        continue;
-                    }
-                    let time = Instant::now();
-                    let fetched = fetch_album(lang, id).await?.into_standard().into_item();
-                    debug!(
-                        "Fetched item(album) detail in {:.2}s",
-                        time.elapsed().as_secs_f64()
-                    );
-                    items_collection.insert_one(fetched).await?;
-                }
-                _ => {}
-            }
+    let rewards = boxes
+        .iter()
+        .flat_map(|t| t.slots.iter())
+        .flat_map(|t| t.common.iter().chain(t.valuable.iter()))
+        .flat_map(|t| t.rewards.iter());
+    let mut visited = HashSet::new();
+    let mut keys = vec![];
+    for reward in rewards {
+        let key = match reward.reward {
+            CamoBoost { id } | Signal { id, .. } => ItemKey::Item(id),
+            CollectionAlbum { id } => ItemKey::Album(id),
+            _ => continue,
+        };
+        if visited.insert(key.id()) {
+            keys.push(key);
         }
     }
 
+    debug!("Started fetching {} item details...", keys.len());
+    let time = Instant::now();
+    let report = FETCH_CLIENT
+        .fetch_all(keys, |key| async move {
+            anyhow::Ok(match key {
+                ItemKey::Item(id) => fetch_item(lang, id).await?.into_standard(),
+                ItemKey::Album(id) => fetch_album(lang, id).await?.into_standard().into_item(),
+            })
+        })
+        .await;
+    debug!(
+        "Fetched item details in {:.2}s",
+        time.elapsed().as_secs_f64()
+    );
+    for (key, e) in report.failed.iter() {
+        error!("Cannot fetch {} {}: {:?}", key.kind(), key.id(), e);
+    }
+
+    let mut changed = vec![];
+    for (_, fetched) in report.fetched {
+        if let Some(entry) = diff_item(store.get_item(lang, fetched.id).await?, &fetched) {
+            changelog.push(entry);
+            changed.push(fetched);
+        }
+    }
+    store.upsert_items(lang, &changed).await?;
+
+    if !report.failed.is_empty() {
+        let failed: Vec<_> = report.failed.iter().map(|(key, _)| key.id()).collect();
+        warn!(
+            "Failed to fetch {} items, kept as stored [lang {}]: {:?}",
+            failed.len(),
+            lang,
+            failed
+        );
+    }
+
     info!(
         "Updated item data in {:.2}s",
         time_c.elapsed().as_secs_f64()
@@ -90,3 +85,41 @@ pub async fn update_items(lang: &str, client: &Client) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// A reward stored as an item, fetched from where the website keeps it.
+#[derive(Debug, Clone, Copy)]
+enum ItemKey {
+    Item(u64),
+    Album(u64),
+}
+
+impl ItemKey {
+    fn id(self) -> u64 {
+        match self {
+            ItemKey::Item(id) | ItemKey::Album(id) => id,
+        }
+    }
+
+    fn kind(self) -> &'static str {
+        match self {
+            ItemKey::Item(_) => "item",
+            ItemKey::Album(_) => "album",
+        }
+    }
+}
+
+/// Changelog entry if `fetched` differs from the stored item.
+fn diff_item(stored: Option<ItemData>, fetched: &ItemData) -> Option<String> {
+    match stored {
+        Some(stored) if stored == *fetched => None,
+        Some(stored) if stored.name != fetched.name => Some(format!(
+            "Item {}: renamed from `{}` to `{}`",
+            fetched.id, stored.name, fetched.name
+        )),
+        Some(_) => Some(format!(
+            "Item {} ({}): details changed",
+            fetched.id, fetched.name
+        )),
+        None => Some(format!("Item {} ({}): added", fetched.id, fetched.name)),
+    }
+}
@@ -0,0 +1,29 @@
+use std::{env, fs, time::Instant};
+
+use log::{debug, info};
+use wows_box::{price::PriceData, store::LootboxStore};
+
+/// Import the price catalogue from the JSON file at `PRICE_FILE`, if set.
+pub async fn update_prices(lang: &str, store: &impl LootboxStore) -> anyhow::Result<()> {
+    let Ok(path) = env::var("PRICE_FILE") else {
+        debug!("PRICE_FILE is not set, skip updating prices.");
+        return Ok(());
+    };
+    info!("Started updating prices [lang {}]...", lang);
+    let time_c = Instant::now();
+
+    let prices: Vec<PriceData> = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+    for price in prices.iter() {
+        debug!("Updating price of lootbox {}", price.box_id);
+        store.upsert_price(lang, price).await?;
+    }
+
+    info!(
+        "Updated {} prices in {:.2}s",
+        prices.len(),
+        time_c.elapsed().as_secs_f64()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use axum::Json;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use wows_box::store::AnyStore;
+use wows_box_rand::error::RandError;
+use wows_box_render::compare::render_compare_to_file;
+
+use crate::rand_handler::{
+    find_box, BoxMatch, Message, BROKEN_BOX, MAX_AMOUNT, TOO_MANY_OPENINGS, UNKNOWN_ERROR,
+};
+use crate::{valuation, AppResponse, AppState, APP_STATE};
+
+const TOO_FEW_BOXES: &str = r#"请至少输入两个箱子。"#;
+
+/// Upper bound of compared lootboxes, to keep the image readable.
+const MAX_BOXES: usize = 6;
+
+pub async fn compare_handler(Json(param): Json<CompareParam>) -> Json<AppResponse<Vec<Message>>> {
+    info!("Connected with client.");
+
+    debug!("Received: {:?}", param);
+
+    let resp = handle_req(param, APP_STATE.get().await).await;
+
+    println!("End connection.");
+
+    Json(resp.into())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompareParam {
+    pub lang: String,
+    pub box_names: Vec<String>,
+    /// Openings of every lootbox.
+    pub amount: u32,
+}
+
+pub async fn handle_req(param: CompareParam, state: &AppState) -> anyhow::Result<Vec<Message>> {
+    debug!("Receive request: {:?}", param);
+
+    state.check_lang(&param.lang).await?;
+    handle(param, &state.store).await
+}
+
+async fn handle(param: CompareParam, store: &AnyStore) -> anyhow::Result<Vec<Message>> {
+    if param.box_names.len() < 2 {
+        return Ok(vec![Message::Text(TOO_FEW_BOXES.to_owned())]);
+    }
+    if param.amount > MAX_AMOUNT {
+        return Ok(vec![Message::Text(TOO_MANY_OPENINGS.to_owned())]);
+    }
+
+    let mut ids = vec![];
+    for box_name in param.box_names.iter().take(MAX_BOXES) {
+        match find_box(&param.lang, box_name, store).await? {
+            BoxMatch::Found(id) => ids.push(id),
+            other => return Ok(other.into_messages()),
+        }
+    }
+
+    match render_compare_to_file(&param.lang, store, &ids, param.amount, valuation()).await {
+        Ok(path) => Ok(vec![Message::Image(path)]),
+        Err(e) if e.is::<RandError>() => Err(e.context(BROKEN_BOX)),
+        Err(e) => {
+            warn!("{}", e);
+            Ok(vec![Message::Text(UNKNOWN_ERROR.to_owned())])
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use axum::Json;
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use wows_box::{
+    history::{date_text, parse_date, revision_at},
+    store::{AnyStore, LootboxStore},
+};
+
+use crate::rand_handler::{find_box, BoxMatch, Message, INVALID_DATE, NO_REVISION};
+use crate::{AppResponse, AppState, APP_STATE};
+
+const NO_HISTORY: &str = r#"暂无此箱子的历史记录。"#;
+const NO_CHANGE: &str = r#"两个日期之间箱子没有变化。"#;
+const NOW: &str = r#"至今"#;
+
+pub async fn history_handler(Json(param): Json<HistoryParam>) -> Json<AppResponse<Vec<Message>>> {
+    info!("Connected with client.");
+
+    debug!("Received: {:?}", param);
+
+    let resp = handle_req(param, APP_STATE.get().await).await;
+
+    println!("End connection.");
+
+    Json(resp.into())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryParam {
+    pub lang: String,
+    pub box_name: String,
+    /// Earliest revision if not given.
+    #[serde(default)]
+    pub from: Option<String>,
+    /// Latest revision if not given.
+    #[serde(default)]
+    pub to: Option<String>,
+}
+
+pub async fn handle_req(param: HistoryParam, state: &AppState) -> anyhow::Result<Vec<Message>> {
+    debug!("Receive request: {:?}", param);
+
+    state.check_lang(&param.lang).await?;
+    handle(param, &state.store).await
+}
+
+/// Lists the revisions without dates, otherwise what changed between them.
+async fn handle(param: HistoryParam, store: &AnyStore) -> anyhow::Result<Vec<Message>> {
+    let id = match find_box(&param.lang, &param.box_name, store).await? {
+        BoxMatch::Found(id) => id,
+        other => return Ok(other.into_messages()),
+    };
+    let history = store.list_history(&param.lang, id).await?;
+    let (Some(first), Some(last)) = (history.first(), history.last()) else {
+        return Ok(vec![Message::Text(NO_HISTORY.to_owned())]);
+    };
+
+    if param.from.is_none() && param.to.is_none() {
+        let lines: Vec<_> = history
+            .iter()
+            .map(|t| {
+                let until = t.valid_until.map(date_text);
+                format!(
+                    "{} ~ {}",
+                    date_text(t.valid_from),
+                    until.as_deref().unwrap_or(NOW)
+                )
+            })
+            .collect();
+        return Ok(vec![Message::Text(lines.join("\n"))]);
+    }
+
+    let mut ends = vec![];
+    for (date, default) in [(&param.from, first), (&param.to, last)] {
+        let revision = match date.as_deref().map(parse_date) {
+            Some(Some(at)) => revision_at(&history, at),
+            Some(None) => return Ok(vec![Message::Text(INVALID_DATE.to_owned())]),
+            None => Some(default),
+        };
+        match revision {
+            Some(revision) => ends.push(revision),
+            None => return Ok(vec![Message::Text(NO_REVISION.to_owned())]),
+        }
+    }
+
+    let changes = ends[0].data.diff(&ends[1].data);
+    if changes.is_empty() {
+        return Ok(vec![Message::Text(NO_CHANGE.to_owned())]);
+    }
+    let lines: Vec<_> = changes.iter().map(ToString::to_string).collect();
+    Ok(vec![Message::Text(lines.join("\n"))])
+}
@@ -0,0 +1,48 @@
+use axum::Json;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use wows_box::store::AnyStore;
+use wows_box_render::sheet::render_probability_sheet;
+
+use crate::rand_handler::{find_box, BoxMatch, Message, UNKNOWN_ERROR};
+use crate::{AppResponse, AppState, APP_STATE};
+
+pub async fn info_handler(Json(param): Json<InfoParam>) -> Json<AppResponse<Vec<Message>>> {
+    info!("Connected with client.");
+
+    debug!("Received: {:?}", param);
+
+    let resp = handle_req(param, APP_STATE.get().await).await;
+
+    println!("End connection.");
+
+    Json(resp.into())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InfoParam {
+    pub lang: String,
+    pub box_name: String,
+}
+
+pub async fn handle_req(param: InfoParam, state: &AppState) -> anyhow::Result<Vec<Message>> {
+    debug!("Receive request: {:?}", param);
+
+    state.check_lang(&param.lang).await?;
+    handle(param, &state.store).await
+}
+
+async fn handle(param: InfoParam, store: &AnyStore) -> anyhow::Result<Vec<Message>> {
+    let id = match find_box(&param.lang, &param.box_name, store).await? {
+        BoxMatch::Found(id) => id,
+        other => return Ok(other.into_messages()),
+    };
+
+    match render_probability_sheet(&param.lang, store, id).await {
+        Ok(path) => Ok(vec![Message::Image(path)]),
+        Err(e) => {
+            warn!("{}", e);
+            Ok(vec![Message::Text(UNKNOWN_ERROR.to_owned())])
+        }
+    }
+}
@@ -1,27 +1,44 @@
 use std::process::exit;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+#[cfg(all(feature = "chrome", not(feature = "raster")))]
+use std::thread;
+use std::time::Duration;
 use std::{env, panic};
-use std::{net::SocketAddr, panic::PanicInfo};
+use std::{net::SocketAddr, panic::PanicHookInfo};
 
+use anyhow::{anyhow, Context};
 use async_once::AsyncOnce;
 use axum::routing::{get, post};
 use axum::Router;
 use dotenvy::dotenv;
 use lazy_static::lazy_static;
-use log::error;
-use mongodb::options::{ClientOptions, ServerApi, ServerApiVersion};
-use mongodb::Client;
+use log::{error, info};
 use serde::{Deserialize, Serialize};
 use tokio::{
     // io::AsyncWriteExt,
     net::TcpListener,
 };
+use wows_box::{
+    store::{AnyStore, LootboxStore},
+    valuation::ValuationTable,
+};
+use wows_box_render::cache::{CacheManager, CacheOptions};
 
+use compare_handler::compare_handler;
+use history_handler::history_handler;
+use info_handler::info_handler;
 use rand_handler::rand_handler;
 use search_handler::search_handler;
+use until_handler::until_handler;
+use user_handler::{reset_handler, user_handler};
 
+mod compare_handler;
+mod history_handler;
+mod info_handler;
 mod rand_handler;
 mod search_handler;
+mod until_handler;
+mod user_handler;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "status", rename_all = "kebab-case")]
@@ -45,22 +62,42 @@ impl<T> From<anyhow::Result<T>> for AppResponse<T> {
 lazy_static! {
     pub static ref APP_STATE: AsyncOnce<AppState> = AsyncOnce::new(async {
         AppState {
-            conn: Arc::new({
-                let mut client_options = ClientOptions::parse(env::var("MONGODB_CONN").unwrap())
-                    .await
-                    .unwrap();
-                let server_api = ServerApi::builder().version(ServerApiVersion::V1).build();
-                client_options.server_api = Some(server_api);
-                let client = Client::with_options(client_options).unwrap();
-                client
-            }),
+            store: Arc::new(AnyStore::from_env().await.unwrap()),
         }
     });
 }
 
+/// Set by [`main`] from `VALUATION_FILE` before serving.
+static VALUATION: OnceLock<ValuationTable> = OnceLock::new();
+
+/// The valuation table, the built-in one if [`main`] has not loaded any.
+pub fn valuation() -> &'static ValuationTable {
+    VALUATION.get_or_init(ValuationTable::default)
+}
+
+/// Load `VALUATION_FILE` if set, so a broken file stops the server at startup.
+fn load_valuation() -> anyhow::Result<ValuationTable> {
+    match env::var("VALUATION_FILE") {
+        Ok(path) => ValuationTable::load(&path)
+            .with_context(|| format!("Cannot load valuation file {path}")),
+        Err(_) => Ok(ValuationTable::default()),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AppState {
-    pub conn: Arc<Client>,
+    pub store: Arc<AnyStore>,
+}
+
+impl AppState {
+    /// Refuse a language the store has no data of, before it reaches the store.
+    pub async fn check_lang(&self, lang: &str) -> anyhow::Result<()> {
+        let langs = self.store.list_languages().await?;
+        if !langs.iter().any(|t| t == lang) {
+            return Err(anyhow!("Unknown language {lang}"));
+        }
+        Ok(())
+    }
 }
 
 #[tokio::main]
@@ -72,9 +109,24 @@ async fn main() -> anyhow::Result<()> {
         Default::default(),
     )?;
 
+    let _ = VALUATION.set(load_valuation()?);
+
+    #[cfg(all(feature = "chrome", not(feature = "raster")))]
+    start_browser_pool();
+    start_cache_sweep(CacheManager::new(
+        env::var("CACHE_DIR")?,
+        CacheOptions::from_env(),
+    ));
+
     let lootbox = Router::new()
         .route("/rand", post(rand_handler))
-        .route("/search", get(search_handler));
+        .route("/compare", post(compare_handler))
+        .route("/info", post(info_handler))
+        .route("/history", post(history_handler))
+        .route("/search", get(search_handler))
+        .route("/until", post(until_handler))
+        .route("/user", get(user_handler))
+        .route("/user/reset", post(reset_handler));
     let app = Router::new().nest("/lootbox", lootbox);
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
@@ -84,7 +136,52 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn panic_handler(panic_info: &PanicInfo) {
+/// How often idle browsers are checked.
+#[cfg(all(feature = "chrome", not(feature = "raster")))]
+const BROWSER_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Launch the browsers before the first request, and keep them healthy.
+///
+/// Only needed when Chrome is the `DefaultRenderer`, which `raster` replaces.
+#[cfg(all(feature = "chrome", not(feature = "raster")))]
+fn start_browser_pool() {
+    use wows_box_render::pool::BROWSER_POOL;
+
+    thread::spawn(|| {
+        if let Err(e) = BROWSER_POOL.warm_up() {
+            error!("Cannot launch browsers: {e:?}");
+        }
+        loop {
+            thread::sleep(BROWSER_CHECK_INTERVAL);
+            BROWSER_POOL.check_health();
+        }
+    });
+}
+
+/// How often expired images are removed.
+const CACHE_SWEEP_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Remove rendered images once they expire, see [`CacheOptions`].
+fn start_cache_sweep(cache: CacheManager) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CACHE_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            let cache = cache.clone();
+            match tokio::task::spawn_blocking(move || cache.sweep()).await {
+                Ok(Ok(report)) if report.removed > 0 => info!(
+                    "Removed {} cached files, {} bytes kept",
+                    report.removed, report.kept
+                ),
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => error!("Cannot sweep cache: {e:?}"),
+                Err(e) => error!("Cache sweep panicked: {e}"),
+            }
+        }
+    });
+}
+
+fn panic_handler(panic_info: &PanicHookInfo) {
     error!("{}", panic_info);
     exit(1);
 }
@@ -1,18 +1,24 @@
 use std::{cmp::Reverse, collections::HashMap};
 
 use axum::Json;
-use bson::doc;
 use itertools::Itertools;
-use log::{debug, info};
-use mongodb::{Client, Collection};
+use log::{debug, info, warn};
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use strsim::sorensen_dice;
 use utils::{tri, triany};
-use wows_box::lootbox::LootBox;
-use wows_box_render::process::render_to_file;
+use wows_box::{
+    history::{box_at, parse_date},
+    store::{AnyStore, LootboxStore},
+};
+use wows_box_rand::{error::RandError, seed::SeedCode};
+use wows_box_render::{
+    format::{OutputFormat, Rendered},
+    process::{render_to_file, RandOptions},
+};
 
-use crate::{AppResponse, AppState, APP_STATE};
+use crate::user_handler::{load_user, save_user, MAX_SAVE_ATTEMPTS};
+use crate::{valuation, AppResponse, AppState, APP_STATE};
 
 // const USAGE: &str = r#"使用方法：
 // box <物品名称> <数量>
@@ -23,7 +29,18 @@ use crate::{AppResponse, AppState, APP_STATE};
 const NO_ITEM_FOUND: &str = r#"未找到对应物品。"#;
 // const INT_ERROR: &str = r#"数字输入错误"#;
 const MULTIPLE_ITEM_FOUND: &str = r#"找到过多匹配项：\n"#;
-const UNKNOWN_ERROR: &str = r#"机器人出错了！"#;
+pub(crate) const UNKNOWN_ERROR: &str = r#"机器人出错了！"#;
+pub(crate) const BROKEN_BOX: &str = r#"箱子数据有误，无法开箱。"#;
+const INVALID_SEED: &str = r#"种子格式错误。"#;
+pub(crate) const INVALID_DATE: &str = r#"日期格式错误，应为 YYYY、YYYY-MM 或 YYYY-MM-DD。"#;
+pub(crate) const NO_REVISION: &str = r#"该日期没有此箱子的记录。"#;
+const SEED_CODE: &str = r#"种子："#;
+pub(crate) const TOO_MANY_OPENINGS: &str = r#"开箱数量过多，最多 100000 个。"#;
+/// Appended to the names of lootboxes no longer listed.
+pub(crate) const RETIRED_MARK: &str = r#"（已下架）"#;
+
+/// Upper bound of openings in a request, every opening is kept in the log.
+pub(crate) const MAX_AMOUNT: u32 = 100000;
 
 pub async fn rand_handler(Json(param): Json<BoxParam>) -> Json<AppResponse<Vec<Message>>> {
     info!("Connected with client.");
@@ -41,6 +58,7 @@ pub async fn rand_handler(Json(param): Json<BoxParam>) -> Json<AppResponse<Vec<M
 pub enum Message {
     Text(String),
     Image(String),
+    Json(serde_json::Value),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -48,12 +66,35 @@ pub struct BoxParam {
     pub lang: String,
     pub box_name: String,
     pub amount: u32,
+    /// Continue from the saved state of this user if given.
+    #[serde(default)]
+    pub user_id: Option<String>,
+    /// Seed code to replay a previous result.
+    #[serde(default)]
+    pub seed: Option<String>,
+    /// Also show the rewards with every inner lootbox opened.
+    #[serde(default)]
+    pub open_nested: bool,
+    /// Ids of ships and camouflages owned in game.
+    #[serde(default)]
+    pub owned_items: Vec<u64>,
+    /// `png` if not given.
+    #[serde(default)]
+    pub format: OutputFormat,
+    /// Built-in look if not given or unknown.
+    #[serde(default)]
+    pub theme: Option<String>,
+    /// Open the lootbox as it was by the end of this date, see
+    /// [`wows_box::history::parse_date`]. The user state is left alone.
+    #[serde(default)]
+    pub at: Option<String>,
 }
 
 pub async fn handle_req(param: BoxParam, state: &AppState) -> anyhow::Result<Vec<Message>> {
     debug!("Receive request: {:?}", param);
 
-    handle(param, &state.conn).await
+    state.check_lang(&param.lang).await?;
+    handle(param, &state.store).await
 }
 
 #[tokio::test]
@@ -61,7 +102,7 @@ async fn test_msg() {
     use std::env;
 
     use dotenvy::dotenv;
-    use mongodb::options::{ClientOptions, ServerApi, ServerApiVersion};
+    use wows_box::store::MongoStore;
 
     dotenv().unwrap();
 
@@ -69,62 +110,186 @@ async fn test_msg() {
         lang: "en".to_owned(),
         box_name: "Mini No.5".to_owned(),
         amount: 250,
+        user_id: None,
+        seed: None,
+        open_nested: false,
+        owned_items: vec![],
+        format: OutputFormat::Png,
+        theme: None,
+        at: None,
     };
 
-    let mut client_options = ClientOptions::parse(env::var("MONGODB_CONN").unwrap())
-        .await
-        .unwrap();
-    let server_api = ServerApi::builder().version(ServerApiVersion::V1).build();
-    client_options.server_api = Some(server_api);
-    let client = Client::with_options(client_options).unwrap();
+    let store = AnyStore::Mongo(
+        MongoStore::connect(&env::var("MONGODB_CONN").unwrap())
+            .await
+            .unwrap(),
+    );
 
-    let h = handle(p, &client).await;
+    let h = handle(p, &store).await;
     println!("{:#?}", h);
 }
 
-async fn handle(param: BoxParam, client: &Client) -> anyhow::Result<Vec<Message>> {
-    let db = client.database(&format!("wowslootbox-{}", param.lang));
-    let box_list: Collection<LootBox> = db.collection("list");
-    let resp = box_list.find(doc! {}).allow_disk_use(true).await;
-
-    let mut resp = triany!(warn resp);
-    let mut map = HashMap::new();
-    let mut keys = Vec::new();
-    while triany!(warn resp.advance().await) {
-        let next = resp.deserialize_current();
-        let next = tri!(continue; warn next);
-        keys.push((
-            next.name.clone(),
-            sorensen_dice(&next.name, &param.box_name),
-        ));
-        map.insert(next.name, next.id);
+async fn handle(param: BoxParam, store: &AnyStore) -> anyhow::Result<Vec<Message>> {
+    match find_box(&param.lang, &param.box_name, store).await? {
+        BoxMatch::Found(id) => build_img(&param, store, id).await,
+        other => Ok(other.into_messages()),
     }
-    keys.sort_unstable_by_key(|&(_, n)| Reverse(OrderedFloat(n)));
+}
+
+/// Result of resolving a lootbox by a fuzzy name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BoxMatch {
+    Found(u64),
+    Multiple(Vec<String>),
+    NotFound,
+}
 
-    if let Some((first_key, first_rate)) = keys.first() {
+impl BoxMatch {
+    /// Messages explaining why no single lootbox was selected.
+    pub fn into_messages(self) -> Vec<Message> {
+        match self {
+            BoxMatch::Found(_) => vec![],
+            BoxMatch::Multiple(names) => vec![
+                Message::Text(MULTIPLE_ITEM_FOUND.to_owned()),
+                Message::Text(names.join("\n")),
+            ],
+            BoxMatch::NotFound => vec![Message::Text(NO_ITEM_FOUND.to_owned())],
+        }
+    }
+}
+
+pub async fn find_box(
+    lang: &str,
+    box_name: &str,
+    store: &impl LootboxStore,
+) -> anyhow::Result<BoxMatch> {
+    let boxes = triany!(warn store.list_boxes(lang).await);
+
+    // A listed lootbox wins over a retired one of the same name.
+    let mut map: HashMap<String, (u64, bool)> = HashMap::new();
+    for next in boxes {
+        if map.get(&next.name).is_some_and(|t| !t.1) && next.retired {
+            continue;
+        }
+        map.insert(next.name, (next.id, next.retired));
+    }
+    let mut keys = map
+        .iter()
+        .map(|(name, &(_, retired))| (name.clone(), sorensen_dice(name, box_name), retired))
+        .collect_vec();
+    keys.sort_unstable_by_key(|&(_, n, retired)| (Reverse(OrderedFloat(n)), retired));
+
+    if let Some((first_key, first_rate, _)) = keys.first() {
         if (first_rate - 1.0) < 0.01 {
-            let id = map.get(first_key).unwrap();
-            Ok(build_img(&param.lang, client, *id, param.amount).await)
+            let (id, _) = map.get(first_key).unwrap();
+            Ok(BoxMatch::Found(*id))
         } else {
-            let filtered = keys.iter().filter(|(_, n)| *n > 0.5).collect_vec();
+            let filtered = keys.iter().filter(|(_, n, _)| *n > 0.5).collect_vec();
             if filtered.get(1).is_some() {
-                Ok(vec![
-                    Message::Text(MULTIPLE_ITEM_FOUND.to_owned()),
-                    Message::Text(filtered.into_iter().map(|(s, _)| s).join("\n")),
-                ])
+                Ok(BoxMatch::Multiple(
+                    filtered
+                        .into_iter()
+                        .map(|(s, _, retired)| match retired {
+                            true => format!("{s}{RETIRED_MARK}"),
+                            false => s.clone(),
+                        })
+                        .collect(),
+                ))
             } else {
-                let id = map.get(first_key).unwrap();
+                let (id, _) = map.get(first_key).unwrap();
                 debug!("Select lootbox {}", first_key);
-                Ok(build_img(&param.lang, client, *id, param.amount).await)
+                Ok(BoxMatch::Found(*id))
             }
         }
     } else {
-        Ok(vec![Message::Text(NO_ITEM_FOUND.to_owned())])
+        Ok(BoxMatch::NotFound)
     }
 }
 
-async fn build_img(lang: &str, client: &Client, key: u64, times: u32) -> Vec<Message> {
-    let path = render_to_file(lang, client, key, times).await;
-    let path = tri!(return vec![Message::Text(UNKNOWN_ERROR.to_owned())]; warn path);
-    vec![Message::Image(path)]
+async fn build_img(param: &BoxParam, store: &AnyStore, key: u64) -> anyhow::Result<Vec<Message>> {
+    if param.amount > MAX_AMOUNT {
+        return Ok(vec![Message::Text(TOO_MANY_OPENINGS.to_owned())]);
+    }
+    let seed = match param
+        .seed
+        .as_deref()
+        .map(str::parse::<SeedCode>)
+        .transpose()
+    {
+        Ok(seed) => seed,
+        Err(_) => return Ok(vec![Message::Text(INVALID_SEED.to_owned())]),
+    };
+    let at = match param.at.as_deref().map(parse_date) {
+        Some(Some(at)) => {
+            if box_at(store, &param.lang, key, at).await?.is_none() {
+                return Ok(vec![Message::Text(NO_REVISION.to_owned())]);
+            }
+            Some(at)
+        }
+        Some(None) => return Ok(vec![Message::Text(INVALID_DATE.to_owned())]),
+        None => None,
+    };
+
+    // Redone on top of the other request if it saved the user meanwhile.
+    let mut attempt = 0;
+    let output = loop {
+        attempt += 1;
+        let mut user = match &param.user_id {
+            Some(user_id) if at.is_none() => {
+                let user = load_user(store, user_id).await;
+                Some(tri!(return Ok(vec![Message::Text(UNKNOWN_ERROR.to_owned())]); warn user))
+            }
+            _ => None,
+        };
+        let (unique_rewards_list, current_try) = match &user {
+            Some(user) => (user.owned.clone(), user.current_try(key)),
+            None => (vec![], 0),
+        };
+        let options = RandOptions {
+            times: param.amount,
+            unique_rewards_list,
+            current_try,
+            seed,
+            open_nested: param.open_nested,
+            owned_items: param.owned_items.clone(),
+            valuation: valuation().clone(),
+            format: param.format,
+            theme: param.theme.clone(),
+            at,
+            ..Default::default()
+        };
+
+        let output = match render_to_file(&param.lang, store, key, &options).await {
+            Ok(output) => output,
+            // Broken lootbox data is reported in the response instead of hidden.
+            Err(e) if e.is::<RandError>() => return Err(e.context(BROKEN_BOX)),
+            Err(e) => {
+                warn!("{}", e);
+                return Ok(vec![Message::Text(UNKNOWN_ERROR.to_owned())]);
+            }
+        };
+
+        let Some(user) = &mut user else {
+            break output;
+        };
+        user.owned = output.unique_rewards_list.clone();
+        user.set_current_try(key, output.current_try);
+        match save_user(store, user).await {
+            Ok(false) if attempt < MAX_SAVE_ATTEMPTS => continue,
+            Ok(false) => warn!("Gave up saving user {}", user.user_id),
+            Ok(true) => {}
+            Err(e) => warn!("{}", e),
+        }
+        break output;
+    };
+
+    Ok(match output.rendered {
+        Rendered::Image(path) => vec![
+            Message::Image(path),
+            Message::Text(format!("{SEED_CODE}{}", output.seed)),
+        ],
+        // The seed is the last line already.
+        Rendered::Text(text) => vec![Message::Text(text)],
+        Rendered::Json(prop) => vec![Message::Json(serde_json::to_value(prop)?)],
+    })
 }
@@ -1,14 +1,12 @@
 use std::cmp::Reverse;
 
 use axum::{extract::Query, Json};
-use bson::doc;
 use log::{debug, info};
-use mongodb::Collection;
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use strsim::sorensen_dice;
-use utils::{tri, triany};
-use wows_box::lootbox::LootBox;
+use utils::triany;
+use wows_box::store::LootboxStore;
 
 use crate::{AppResponse, APP_STATE};
 
@@ -16,6 +14,8 @@ use crate::{AppResponse, APP_STATE};
 pub struct SearchItem {
     name: String,
     score: f64,
+    /// No longer listed, only kept for seeds and the history.
+    retired: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -38,22 +38,20 @@ pub async fn search_handler(Query(q): Query<SearchQueryArg>) -> Json<AppResponse
 }
 
 async fn handler(pat: &str, lang: &str, lim: u32) -> anyhow::Result<Vec<SearchItem>> {
-    let client = &APP_STATE.get().await.conn;
-    let db = client.database(&format!("wowslootbox-{lang}"));
-    let box_list: Collection<LootBox> = db.collection("list");
-    let resp = box_list.find(doc! {}).allow_disk_use(true).await;
-    let mut resp = triany!(warn resp);
+    let state = APP_STATE.get().await;
+    state.check_lang(lang).await?;
+    let store = &state.store;
+    let boxes = triany!(warn store.list_boxes(lang).await);
     let mut items = Vec::new();
-    while triany!(warn resp.advance().await) {
-        let next = resp.deserialize_current();
-        let next = tri!(continue; warn next);
+    for next in boxes {
         items.push(SearchItem {
             name: next.name.clone(),
-            score: sorensen_dice(&next.name, &pat),
+            score: sorensen_dice(&next.name, pat),
+            retired: next.retired,
         });
     }
 
-    items.sort_unstable_by_key(|t| Reverse(OrderedFloat(t.score)));
+    items.sort_unstable_by_key(|t| (Reverse(OrderedFloat(t.score)), t.retired));
 
     if items.len() > lim as usize {
         items.drain((lim as usize + 1)..);
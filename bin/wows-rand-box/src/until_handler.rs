@@ -0,0 +1,166 @@
+use std::cmp::Reverse;
+
+use anyhow::Context;
+use axum::Json;
+use itertools::Itertools;
+use log::{debug, info};
+use ordered_float::OrderedFloat;
+use rand::{rngs::SmallRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use strsim::sorensen_dice;
+use utils::tri;
+use wows_box::{
+    lootbox::{LootBox, LootBoxRewardType},
+    store::{AnyStore, LootboxStore},
+};
+use wows_box_rand::rand::{rand_until_distribution, worst_case_until};
+use wows_box_render::until::{render_until_to_file, until_summary};
+
+use crate::rand_handler::{find_box, BoxMatch, Message, BROKEN_BOX, UNKNOWN_ERROR};
+use crate::{AppResponse, AppState, APP_STATE};
+
+const NO_TARGET_FOUND: &str = r#"该箱子中没有对应物品。"#;
+const TARGET_OWNED: &str = r#"已拥有该物品。"#;
+const TARGET_UNREACHABLE: &str = r#"该物品无法从此箱子中开出。"#;
+
+/// Default amount of simulated runs.
+const DEFAULT_RUNS: u32 = 10000;
+/// Upper bound of simulated runs.
+const MAX_RUNS: u32 = 100000;
+/// Give up a single run after this many openings.
+const MAX_OPENINGS: u32 = 100000;
+/// Upper bound of openings over all runs of a request.
+const MAX_TOTAL_OPENINGS: u32 = 20_000_000;
+
+pub async fn until_handler(Json(param): Json<UntilParam>) -> Json<AppResponse<Vec<Message>>> {
+    info!("Connected with client.");
+
+    debug!("Received: {:?}", param);
+
+    let resp = handle_req(param, APP_STATE.get().await).await;
+
+    println!("End connection.");
+
+    Json(resp.into())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UntilParam {
+    pub lang: String,
+    pub box_name: String,
+    /// Reward id or name.
+    pub target: String,
+    /// Unique reward ids already owned.
+    #[serde(default)]
+    pub owned: Vec<u64>,
+    /// Guarantee counter before the first opening.
+    #[serde(default)]
+    pub current_try: u32,
+    pub runs: Option<u32>,
+}
+
+pub async fn handle_req(param: UntilParam, state: &AppState) -> anyhow::Result<Vec<Message>> {
+    debug!("Receive request: {:?}", param);
+
+    state.check_lang(&param.lang).await?;
+    handle(param, &state.store).await
+}
+
+async fn handle(param: UntilParam, store: &AnyStore) -> anyhow::Result<Vec<Message>> {
+    let id = match find_box(&param.lang, &param.box_name, store).await? {
+        BoxMatch::Found(id) => id,
+        other => return Ok(other.into_messages()),
+    };
+
+    let Some(lootbox) = store.get_box(&param.lang, id).await? else {
+        return Ok(vec![Message::Text(UNKNOWN_ERROR.to_owned())]);
+    };
+
+    let Some(target) = find_target(&lootbox, &param.target) else {
+        return Ok(vec![Message::Text(NO_TARGET_FOUND.to_owned())]);
+    };
+    debug!("Select target {:?}", target);
+    if target.get_id().is_some_and(|t| param.owned.contains(&t)) {
+        return Ok(vec![Message::Text(TARGET_OWNED.to_owned())]);
+    }
+    if !can_drop(&lootbox, &target) {
+        return Ok(vec![Message::Text(TARGET_UNREACHABLE.to_owned())]);
+    }
+
+    // No run needs more openings than the guarantee takes to hand it out.
+    let worst_case = worst_case_until(&lootbox, |t| *t == target, &param.owned, param.current_try);
+    let runs = param.runs.unwrap_or(DEFAULT_RUNS).clamp(1, MAX_RUNS);
+    let limit = worst_case
+        .unwrap_or(MAX_TOTAL_OPENINGS / runs)
+        .clamp(1, MAX_OPENINGS);
+    let runs = runs.min(MAX_TOTAL_OPENINGS / limit);
+
+    let data = lootbox.clone();
+    let dist_target = target.clone();
+    let UntilParam {
+        owned, current_try, ..
+    } = param.clone();
+    let dist = tokio::task::spawn_blocking(move || {
+        let mut rng = SmallRng::from_entropy();
+        rand_until_distribution(
+            &mut rng,
+            &data,
+            |t| *t == dist_target,
+            &owned,
+            current_try,
+            runs,
+            limit,
+        )
+    })
+    .await?
+    .context(BROKEN_BOX)?;
+
+    let summary = until_summary(&param.lang, &dist)
+        .into_iter()
+        .map(|t| format!("{}: {}", t.label, t.value))
+        .join("\n");
+
+    let path = render_until_to_file(&param.lang, store, id, target, &dist).await;
+    let path = tri!(return Ok(vec![Message::Text(summary)]); warn path);
+
+    Ok(vec![Message::Text(summary), Message::Image(path)])
+}
+
+/// Whether `target` is in a list which can be picked, and has a chance in it.
+///
+/// Unique lists pick uniformly, regardless of the chances of their rewards.
+fn can_drop(lootbox: &LootBox, target: &LootBoxRewardType) -> bool {
+    lootbox
+        .slots
+        .iter()
+        .flat_map(|t| t.common.iter().chain(t.valuable.iter()))
+        .filter(|t| t.probability > 0.0)
+        .any(|list| {
+            list.rewards
+                .iter()
+                .any(|t| t.reward == *target && (list.has_unique_rewards || t.probability > 0.0))
+        })
+}
+
+/// Find the reward in `lootbox` by id, or by the closest name.
+fn find_target(lootbox: &LootBox, target: &str) -> Option<LootBoxRewardType> {
+    let rewards = lootbox
+        .slots
+        .iter()
+        .flat_map(|t| t.common.iter().chain(t.valuable.iter()))
+        .flat_map(|t| t.rewards.iter())
+        .map(|t| &t.reward);
+
+    if let Ok(id) = target.trim().parse::<u64>() {
+        return rewards
+            .into_iter()
+            .find(|t| t.get_id() == Some(id))
+            .cloned();
+    }
+
+    rewards
+        .filter_map(|t| t.get_name().map(|n| (t, sorensen_dice(n, target))))
+        .filter(|&(_, score)| score > 0.5)
+        .min_by_key(|&(_, score)| Reverse(OrderedFloat(score)))
+        .map(|(t, _)| t.clone())
+}
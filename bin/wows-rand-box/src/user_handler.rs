@@ -0,0 +1,140 @@
+use anyhow::anyhow;
+use axum::{extract::Query, Json};
+use log::{debug, info};
+use serde::{Deserialize, Serialize};
+use wows_box::{store::LootboxStore, user::UserData};
+
+use crate::rand_handler::{find_box, BoxMatch, Message};
+use crate::{AppResponse, AppState, APP_STATE};
+
+const NO_STATE: &str = r#"当前没有保存的开箱记录。"#;
+const RESET_ALL: &str = r#"已重置所有开箱记录。"#;
+const RESET_BOX: &str = r#"已重置开箱记录："#;
+const OWNED_UNIQUE: &str = r#"已获得的唯一物品数量："#;
+const PITY_HEADER: &str = r#"保底进度："#;
+
+pub async fn load_user(store: &impl LootboxStore, user_id: &str) -> anyhow::Result<UserData> {
+    let user = store.get_user(user_id).await?;
+    Ok(user.unwrap_or_else(|| UserData::new(user_id)))
+}
+
+/// Returns `false` if the user was saved by another request since it was
+/// loaded, see [`LootboxStore::save_user`].
+pub async fn save_user(store: &impl LootboxStore, user: &UserData) -> anyhow::Result<bool> {
+    store.save_user(user).await
+}
+
+/// Attempts to save a user before giving up to concurrent requests.
+pub const MAX_SAVE_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserQueryArg {
+    pub lang: String,
+    pub user_id: String,
+}
+
+pub async fn user_handler(Query(q): Query<UserQueryArg>) -> Json<AppResponse<Vec<Message>>> {
+    info!("Connected with client.");
+
+    debug!("Received: {:?}", q);
+
+    let resp = handle_view(q, APP_STATE.get().await).await;
+
+    println!("End connection.");
+
+    Json(resp.into())
+}
+
+async fn handle_view(q: UserQueryArg, state: &AppState) -> anyhow::Result<Vec<Message>> {
+    state.check_lang(&q.lang).await?;
+    let store = &*state.store;
+    let user = load_user(store, &q.user_id).await?;
+    if user.owned.is_empty() && user.pity.iter().all(|t| t.current_try == 0) {
+        return Ok(vec![Message::Text(NO_STATE.to_owned())]);
+    }
+
+    let boxes = store.list_boxes(&q.lang).await?;
+
+    let mut lines = vec![format!("{OWNED_UNIQUE}{}", user.owned.len())];
+    let pity: Vec<_> = user
+        .pity
+        .iter()
+        .filter(|t| t.current_try > 0)
+        .filter_map(|t| {
+            let lootbox = boxes.iter().find(|b| b.id == t.box_id)?;
+            Some(match lootbox.save_point {
+                Some(save_point) => {
+                    format!("{}: {}/{}", lootbox.name, t.current_try, save_point)
+                }
+                None => format!("{}: {}", lootbox.name, t.current_try),
+            })
+        })
+        .collect();
+    if !pity.is_empty() {
+        lines.push(PITY_HEADER.to_owned());
+        lines.extend(pity);
+    }
+
+    Ok(vec![Message::Text(lines.join("\n"))])
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResetParam {
+    pub lang: String,
+    pub user_id: String,
+    /// Only reset this lootbox if given.
+    pub box_name: Option<String>,
+}
+
+pub async fn reset_handler(Json(param): Json<ResetParam>) -> Json<AppResponse<Vec<Message>>> {
+    info!("Connected with client.");
+
+    debug!("Received: {:?}", param);
+
+    let resp = handle_reset(param, APP_STATE.get().await).await;
+
+    println!("End connection.");
+
+    Json(resp.into())
+}
+
+async fn handle_reset(param: ResetParam, state: &AppState) -> anyhow::Result<Vec<Message>> {
+    state.check_lang(&param.lang).await?;
+    let store = &*state.store;
+
+    let Some(box_name) = param.box_name else {
+        store.delete_user(&param.user_id).await?;
+        return Ok(vec![Message::Text(RESET_ALL.to_owned())]);
+    };
+
+    let id = match find_box(&param.lang, &box_name, store).await? {
+        BoxMatch::Found(id) => id,
+        other => return Ok(other.into_messages()),
+    };
+    let Some(lootbox) = store.get_box(&param.lang, id).await? else {
+        return Ok(BoxMatch::NotFound.into_messages());
+    };
+
+    // Forget the pity of this box and every unique reward it can drop.
+    let unique_ids: Vec<_> = lootbox
+        .slots
+        .iter()
+        .flat_map(|t| t.common.iter().chain(t.valuable.iter()))
+        .filter(|t| t.has_unique_rewards)
+        .flat_map(|t| t.rewards.iter())
+        .filter_map(|t| t.reward.get_id())
+        .collect();
+    for _ in 0..MAX_SAVE_ATTEMPTS {
+        let mut user = load_user(store, &param.user_id).await?;
+        user.pity.retain(|t| t.box_id != id);
+        user.owned.retain(|t| !unique_ids.contains(t));
+        if save_user(store, &user).await? {
+            return Ok(vec![Message::Text(format!("{RESET_BOX}{}", lootbox.name))]);
+        }
+    }
+
+    Err(anyhow!(
+        "User {} is being changed by other requests",
+        param.user_id
+    ))
+}
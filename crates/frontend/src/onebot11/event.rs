@@ -12,6 +12,7 @@ pub struct Event {
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
 #[serde(tag = "post_type", rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum EventContent {
     Message(MessageEvent),
     MetaEvent(MetaEvent),
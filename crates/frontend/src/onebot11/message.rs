@@ -40,9 +40,9 @@ macro_rules! __impl_msg {
         $(__impl_msg!(@$ident $(as $tag)?);)+
     };
     (@$ident:ident) => {
-        impl Into<Message> for $ident {
-            fn into(self) -> Message {
-                Message::$ident { data: self }
+        impl From<$ident> for Message {
+            fn from(data: $ident) -> Message {
+                Message::$ident { data }
             }
         }
 
@@ -57,9 +57,9 @@ macro_rules! __impl_msg {
         }
     };
     (@$ident:ident as $tag:ident) => {
-        impl Into<Message> for $ident {
-            fn into(self) -> Message {
-                Message::$tag { data: self }
+        impl From<$ident> for Message {
+            fn from(data: $ident) -> Message {
+                Message::$tag { data }
             }
         }
 
@@ -214,7 +214,7 @@ pub enum ForwardMessageNode {
     /// > **注意**
     /// >
     /// > 接收时，此消息段不会直接出现在消息事件的 `message` 中，
-    /// 需通过 [`get_forward_msg` API](../api/public.md#get_forward_msg-获取合并转发消息) 获取。
+    /// > 需通过 [`get_forward_msg` API](../api/public.md#get_forward_msg-获取合并转发消息) 获取。
     ///
     /// | 参数名 | 收 | 发 | 可能的值 | 说明 |
     /// | --- | --- | --- | --- | --- |
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::item::ItemIcon;
+use crate::{client::FETCH_CLIENT, item::ItemIcon};
 
 const ALBUM_QUERY: &str = r#"query CollectibleAlbum ($albumId: String, $languageCode: String) {
     collectibleAlbum(albumId:$albumId, lang: $languageCode) {
@@ -59,25 +59,22 @@ pub async fn fetch_album(lang: &str, album_id: u64) -> anyhow::Result<AlbumData>
         collectible_album: Vec<AlbumData>,
     }
 
-    let resp = reqwest::Client::new()
-        .post("https://vortex.worldofwarships.asia/api/graphql/glossary/")
-        .json(&body)
-        .send()
+    let content: serde_json::Value = FETCH_CLIENT
+        .json(|client| {
+            client
+                .post("https://vortex.worldofwarships.asia/api/graphql/glossary/")
+                .json(&body)
+        })
         .await
         .map_err(|e| anyhow::anyhow!("Failed to fetch album data: {:?}", e))?;
 
-    let content = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse from json: {:?}", e))?;
-
     // dbg!(&content);
 
     let content: Vec<AlbumData__InternalBase> = serde_json::from_value(content)?;
 
     let item = content
-        .get(0)
-        .and_then(|t| t.data.collectible_album.get(0))
+        .first()
+        .and_then(|t| t.data.collectible_album.first())
         .ok_or(anyhow::anyhow!("Failed to get a single result"))?;
 
     Ok(item.clone())
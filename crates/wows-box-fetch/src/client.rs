@@ -0,0 +1,365 @@
+//! The HTTP client every fetch goes through, so a whole update stays within
+//! the limits of the official website.
+
+use std::{
+    env,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::anyhow;
+use futures::{stream, StreamExt};
+use lazy_static::lazy_static;
+use log::warn;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use tokio::sync::Semaphore;
+
+/// Retries never wait longer than this.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchOptions {
+    /// Requests in flight at the same time.
+    pub concurrency: usize,
+    /// Requests started per second, unlimited if not positive. Up to a
+    /// second of requests may start at once.
+    pub rate: f64,
+    /// Extra attempts of a request failed by a transient error.
+    pub retries: u32,
+    /// Wait before the first retry, doubled for every next one.
+    pub backoff: Duration,
+    /// Longest a single attempt may take, body included.
+    pub timeout: Duration,
+    /// Longest a single attempt may wait to connect.
+    pub connect_timeout: Duration,
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        FetchOptions {
+            concurrency: 4,
+            rate: 5.0,
+            retries: 3,
+            backoff: Duration::from_millis(500),
+            timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl FetchOptions {
+    /// Read `FETCH_CONCURRENCY`, `FETCH_RATE` (per second), `FETCH_RETRIES`,
+    /// `FETCH_BACKOFF` (in milliseconds), `FETCH_TIMEOUT` and
+    /// `FETCH_CONNECT_TIMEOUT` (in seconds), with the defaults for unset ones.
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(key: &str) -> Option<T> {
+            env::var(key).ok().and_then(|t| t.parse().ok())
+        }
+        let default = FetchOptions::default();
+        FetchOptions {
+            concurrency: var("FETCH_CONCURRENCY").map_or(default.concurrency, |t: usize| t.max(1)),
+            rate: var("FETCH_RATE").unwrap_or(default.rate),
+            retries: var("FETCH_RETRIES").unwrap_or(default.retries),
+            backoff: var("FETCH_BACKOFF").map_or(default.backoff, Duration::from_millis),
+            timeout: var("FETCH_TIMEOUT").map_or(default.timeout, Duration::from_secs),
+            connect_timeout: var("FETCH_CONNECT_TIMEOUT")
+                .map_or(default.connect_timeout, Duration::from_secs),
+        }
+    }
+
+    /// Wait before retry `attempt`, counted from 0.
+    pub fn retry_delay(&self, attempt: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(MAX_BACKOFF)
+    }
+}
+
+/// Refilled by `rate` tokens per second, holding at most `capacity`.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    fn new(rate: f64) -> Self {
+        let capacity = rate.max(1.0);
+        TokenBucket {
+            rate,
+            capacity,
+            state: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    /// Wait until a token is taken.
+    async fn acquire(&self) {
+        if self.rate <= 0.0 {
+            return;
+        }
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let (tokens, last) = &mut *state;
+                let now = Instant::now();
+                *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * self.rate)
+                    .min(self.capacity);
+                *last = now;
+                if *tokens >= 1.0 {
+                    *tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - *tokens) / self.rate)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Worth another try, unlike a response the website meant.
+fn is_transient(e: &reqwest::Error) -> bool {
+    e.is_timeout() || e.is_connect()
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Why an attempt of a request failed.
+enum Failure {
+    /// Worth another try.
+    Transient(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+/// Results of [`FetchClient::fetch_all`], in the order of the keys.
+#[derive(Debug)]
+pub struct FetchReport<K, T> {
+    pub fetched: Vec<(K, T)>,
+    pub failed: Vec<(K, anyhow::Error)>,
+}
+
+#[derive(Debug)]
+pub struct FetchClient {
+    client: reqwest::Client,
+    permits: Semaphore,
+    bucket: TokenBucket,
+    options: FetchOptions,
+}
+
+impl FetchClient {
+    pub fn new(options: FetchOptions) -> Self {
+        FetchClient {
+            client: reqwest::Client::builder()
+                .timeout(options.timeout)
+                .connect_timeout(options.connect_timeout)
+                .build()
+                .unwrap_or_default(),
+            permits: Semaphore::new(options.concurrency.max(1)),
+            bucket: TokenBucket::new(options.rate),
+            options,
+        }
+    }
+
+    pub fn options(&self) -> &FetchOptions {
+        &self.options
+    }
+
+    /// Send the request built by `request`, retrying timeouts, refused
+    /// connections, `429` and `5xx` responses.
+    ///
+    /// Other error statuses are returned as they are, the website explains
+    /// them in the body. Use [`FetchClient::json`] to retry a body which
+    /// cannot be read as well.
+    pub async fn send(
+        &self,
+        request: impl Fn(&reqwest::Client) -> RequestBuilder,
+    ) -> anyhow::Result<Response> {
+        let request = &request;
+        self.retry(move || self.attempt(request)).await
+    }
+
+    /// Same as [`FetchClient::send`], then read the body as JSON, also
+    /// retrying when the body is cut off or a successful response is not
+    /// valid JSON.
+    pub async fn json<T: DeserializeOwned>(
+        &self,
+        request: impl Fn(&reqwest::Client) -> RequestBuilder,
+    ) -> anyhow::Result<T> {
+        let request = &request;
+        self.retry(move || async move {
+            let resp = self.attempt(request).await?;
+            let status = resp.status();
+            let url = resp.url().clone();
+            let body = resp
+                .bytes()
+                .await
+                .map_err(|e| Failure::Transient(anyhow!("Cannot read {url}: {e}")))?;
+            serde_json::from_slice(&body).map_err(|e| {
+                let e = anyhow!("Invalid JSON from {url}: {e}");
+                // A failed response is not expected to change.
+                if status.is_success() {
+                    Failure::Transient(e)
+                } else {
+                    Failure::Fatal(e.context(status))
+                }
+            })
+        })
+        .await
+    }
+
+    /// Send the request once.
+    async fn attempt(
+        &self,
+        request: &impl Fn(&reqwest::Client) -> RequestBuilder,
+    ) -> Result<Response, Failure> {
+        self.bucket.acquire().await;
+        match request(&self.client).send().await {
+            Ok(resp) if !is_transient_status(resp.status()) => Ok(resp),
+            Ok(resp) => Err(Failure::Transient(anyhow!(
+                "{} from {}",
+                resp.status(),
+                resp.url()
+            ))),
+            Err(e) if is_transient(&e) => Err(Failure::Transient(e.into())),
+            Err(e) => Err(Failure::Fatal(e.into())),
+        }
+    }
+
+    /// Run `attempt` until it succeeds or fails for good, holding a permit
+    /// while it runs.
+    async fn retry<T, Fut>(&self, attempt: impl Fn() -> Fut) -> anyhow::Result<T>
+    where
+        Fut: Future<Output = Result<T, Failure>>,
+    {
+        let mut tries = 0;
+        loop {
+            let error = {
+                let _permit = self.permits.acquire().await?;
+                match attempt().await {
+                    Ok(t) => return Ok(t),
+                    Err(Failure::Fatal(e)) => return Err(e),
+                    Err(Failure::Transient(e)) => e,
+                }
+            };
+            if tries >= self.options.retries {
+                return Err(error.context(format!("Gave up after {} attempts", tries + 1)));
+            }
+            let delay = self.options.retry_delay(tries);
+            warn!("{error}, retrying in {:.1}s...", delay.as_secs_f64());
+            tokio::time::sleep(delay).await;
+            tries += 1;
+        }
+    }
+
+    /// Run `fetch` for every key, `concurrency` at a time, collecting the
+    /// failures instead of stopping at the first one.
+    pub async fn fetch_all<K, T, F, Fut>(
+        &self,
+        keys: impl IntoIterator<Item = K>,
+        fetch: F,
+    ) -> FetchReport<K, T>
+    where
+        K: Clone,
+        F: Fn(K) -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let results: Vec<_> = stream::iter(keys)
+            .map(|key| {
+                let res = fetch(key.clone());
+                async move { (key, res.await) }
+            })
+            .buffered(self.options.concurrency.max(1))
+            .collect()
+            .await;
+
+        let mut report = FetchReport {
+            fetched: vec![],
+            failed: vec![],
+        };
+        for (key, res) in results {
+            match res {
+                Ok(t) => report.fetched.push((key, t)),
+                Err(e) => report.failed.push((key, e)),
+            }
+        }
+        report
+    }
+}
+
+lazy_static! {
+    /// Configured by [`FetchOptions::from_env`].
+    pub static ref FETCH_CLIENT: FetchClient = FetchClient::new(FetchOptions::from_env());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_fetch_all() {
+    let client = FetchClient::new(FetchOptions {
+        rate: 100.0,
+        ..Default::default()
+    });
+    let report = client
+        .fetch_all(1..=6u64, |id| async move {
+            match id % 3 {
+                0 => Err(anyhow!("broken {id}")),
+                _ => Ok(id * 10),
+            }
+        })
+        .await;
+    assert_eq!(report.fetched, vec![(1, 10), (2, 20), (4, 40), (5, 50)]);
+    let failed: Vec<_> = report.failed.iter().map(|(id, _)| *id).collect();
+    assert_eq!(failed, vec![3, 6]);
+
+    let options = FetchOptions::default();
+    assert_eq!(options.retry_delay(0), Duration::from_millis(500));
+    assert_eq!(options.retry_delay(2), Duration::from_secs(2));
+    assert_eq!(options.retry_delay(40), MAX_BACKOFF);
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_token_bucket() {
+    // A burst of 20, then 20 per second.
+    let bucket = TokenBucket::new(20.0);
+    let time = Instant::now();
+    for _ in 0..24 {
+        bucket.acquire().await;
+    }
+    assert!(time.elapsed() >= Duration::from_millis(150));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_json_retries() {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    // A cut off body first, then the whole one.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        for body in [r#"{"id": 1"#, r#"{"id": 1}"#] {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = stream.read(&mut [0; 1024]).await;
+            let resp = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(resp.as_bytes()).await.unwrap();
+        }
+    });
+
+    let client = FetchClient::new(FetchOptions {
+        backoff: Duration::from_millis(1),
+        ..Default::default()
+    });
+    let url = format!("http://{addr}/");
+    let value: serde_json::Value = client.json(|client| client.get(&url)).await.unwrap();
+    assert_eq!(value["id"], 1);
+}
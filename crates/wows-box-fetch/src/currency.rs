@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::item::ItemIcon;
+use crate::{client::FETCH_CLIENT, item::ItemIcon};
 
 const QUERY_CURRENCY: &str = r#"query Currencies($languageCode: String) {
     currencies(lang: $languageCode) {
@@ -41,7 +41,7 @@ pub enum CurrencyType {
     /// 军团代币
     #[serde(rename = "molybdenum")]
     Molybdenum,
-    ///  
+    /// (unnamed)
     #[serde(rename = "brass")]
     Brass,
     /// 网站筹码
@@ -107,7 +107,7 @@ pub enum CurrencyType {
     /// 战斗代币
     #[serde(rename = "clientum_1")]
     Clientum1,
-    ///  
+    /// (unnamed)
     #[serde(rename = "clientum_2")]
     Clientum2,
     /// 石油
@@ -218,18 +218,15 @@ pub async fn fetch_currency_symbol(lang: &str) -> anyhow::Result<Vec<CurrencyDat
         currencies: Vec<CurrencyData>,
     }
 
-    let resp = reqwest::Client::new()
-        .post("https://vortex.worldofwarships.asia/api/graphql/glossary/")
-        .json(&body)
-        .send()
+    let content: serde_json::Value = FETCH_CLIENT
+        .json(|client| {
+            client
+                .post("https://vortex.worldofwarships.asia/api/graphql/glossary/")
+                .json(&body)
+        })
         .await
         .map_err(|e| anyhow::anyhow!("Failed to fetch currency data: {:?}", e))?;
 
-    let content = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse from json: {:?}", e))?;
-
     // dbg!(&content);
 
     let mut content: Vec<CurrencyData__InternalBase> = serde_json::from_value(content)?;
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::client::FETCH_CLIENT;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ItemIcon {
@@ -106,25 +108,22 @@ pub async fn fetch_item(lang: &str, item_id: u64) -> anyhow::Result<ItemData> {
         items: Vec<ItemData>,
     }
 
-    let resp = reqwest::Client::new()
-        .post("https://vortex.worldofwarships.asia/api/graphql/glossary/")
-        .json(&body)
-        .send()
+    let content: serde_json::Value = FETCH_CLIENT
+        .json(|client| {
+            client
+                .post("https://vortex.worldofwarships.asia/api/graphql/glossary/")
+                .json(&body)
+        })
         .await
         .map_err(|e| anyhow::anyhow!("Failed to fetch item data: {:?}", e))?;
 
-    let content = resp
-        .json()
-        .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse from json: {:?}", e))?;
-
     // dbg!(&content);
 
     let content: Vec<ItemType__InternalBase> = serde_json::from_value(content)?;
 
     let item = content
-        .get(0)
-        .and_then(|t| t.data.items.get(0))
+        .first()
+        .and_then(|t| t.data.items.first())
         .ok_or(anyhow::anyhow!("Failed to get a single result"))?;
 
     Ok(item.clone())
@@ -1,4 +1,5 @@
 pub mod album;
+pub mod client;
 pub mod currency;
 pub mod item;
 pub mod list;
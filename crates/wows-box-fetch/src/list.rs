@@ -1,6 +1,8 @@
 //! Fetch wows lootbox list.
 
-use serde::{Serialize, Deserialize};
+use serde::{Deserialize, Serialize};
+
+use crate::client::FETCH_CLIENT;
 
 const QUERY_LOOTBOX_LIST: &str = r#"query Lootbox($languageCode: String!) {
     lootbox(lang: $languageCode) {
@@ -52,16 +54,15 @@ pub async fn fetch_list(lang: &str) -> anyhow::Result<Vec<LootboxListItem>> {
         lootbox: Vec<LootboxListItem>,
     }
 
-    
-    let resp = reqwest::Client::new()
-        .post("https://vortex.worldofwarships.asia/api/graphql/glossary/")
-        .json(&body)
-        .send()
+    let content: serde_json::Value = FETCH_CLIENT
+        .json(|client| {
+            client
+                .post("https://vortex.worldofwarships.asia/api/graphql/glossary/")
+                .json(&body)
+        })
         .await
         .map_err(|e| anyhow::anyhow!("Failed to fetch lootbox list data: {:?}", e))?;
 
-    let content = resp.json().await.map_err(|e| anyhow::anyhow!("Failed to parse from json: {:?}", e))?;
-
     let mut content: Vec<LootboxList__InternalBase> = serde_json::from_value(content)?;
 
     let item = content
@@ -2,7 +2,7 @@ use std::{collections::HashMap, num::NonZeroU8};
 
 use serde::{Deserialize, Serialize};
 
-use crate::item::ItemIcon;
+use crate::{client::FETCH_CLIENT, item::ItemIcon};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -20,11 +20,10 @@ pub struct LootBox {
 
 impl LootBox {
     pub fn into_standrad(self) -> wows_box::lootbox::LootBox {
-        let filler = if let Some(filler) = self.filler.clone() {
-            Some(filler.into_standard(&self))
-        } else {
-            None
-        };
+        let filler = self
+            .filler
+            .clone()
+            .map(|filler| filler.into_standard(&self));
         let save_point = self
             .slots
             .iter()
@@ -45,6 +44,7 @@ impl LootBox {
                 .collect(),
             filler,
             save_point,
+            retired: false,
         }
     }
 }
@@ -123,10 +123,19 @@ impl LootBoxFiller {
                             .get_id()
                             .is_some_and(|i| i == id)
                     })
-                    .unwrap()
-                    .reward
-                    .clone()
-                    .into_standard(),
+                    .map(|t| t.reward.clone().into_standard())
+                    // Left for `LootBox::validate` to report as unresolved.
+                    .unwrap_or(match self.filler {
+                        LootBoxFillerType::Signal { .. } => Signal {
+                            id,
+                            name: String::new(),
+                        },
+                        _ => Camouflage {
+                            id,
+                            name: String::new(),
+                            icon: String::new(),
+                        },
+                    }),
                 LootBoxFillerType::Style { id } => Style { id },
             },
             amount: self.amount,
@@ -508,11 +517,7 @@ impl LootBoxRewardType {
                 only_silver,
                 ship: additional_data.ship.into_standard(),
             },
-            Self::Style {
-                id,
-            } => Style {
-                id,
-            },
+            Self::Style { id } => Style { id },
             Self::Crew {
                 id,
                 ship_id,
@@ -672,6 +677,7 @@ const fn bool_false() -> bool {
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "status", rename_all = "camelCase")]
+#[allow(clippy::large_enum_variant)]
 pub enum LootBoxFetchResponse {
     #[serde(rename = "ok")]
     Ok { data: LootBox },
@@ -681,10 +687,7 @@ pub enum LootBoxFetchResponse {
 
 impl LootBoxFetchResponse {
     pub fn is_ok(&self) -> bool {
-        match self {
-            LootBoxFetchResponse::Ok { .. } => true,
-            _ => false,
-        }
+        matches!(self, LootBoxFetchResponse::Ok { .. })
     }
 
     pub fn is_err(&self) -> bool {
@@ -700,16 +703,11 @@ impl LootBoxFetchResponse {
 }
 
 pub async fn fetch_lootbox(lang: &str, id: u64) -> anyhow::Result<LootBoxFetchResponse> {
-    let resp = reqwest::get(format!(
-        "https://vortex.worldofwarships.asia/api/get_lootbox/{lang}/{id}/"
-    ))
-    .await
-    .map_err(|e| anyhow::anyhow!("Failed to fetch lootbox detail: {:?}", e))?;
-
-    let lootbox = resp
-        .json()
+    let url = format!("https://vortex.worldofwarships.asia/api/get_lootbox/{lang}/{id}/");
+    let lootbox = FETCH_CLIENT
+        .json(|client| client.get(&url))
         .await
-        .map_err(|e| anyhow::anyhow!("Failed to parse json: {:?}", e))?;
+        .map_err(|e| anyhow::anyhow!("Failed to fetch lootbox detail: {:?}", e))?;
 
     // dbg!(&lootbox);
     // let lootbox = serde_json::from_value(lootbox)
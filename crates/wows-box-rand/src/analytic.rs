@@ -0,0 +1,457 @@
+//! Expectation of multiple lootbox openings.
+//!
+//! The sampler in [`crate::rand`] only tells what *one* run looks like.
+//! This module walks the same rules analytically:
+//!
+//! - Lists without unique rewards are drawn independently of any state,
+//!   so every slot of every opening contributes an independent term.
+//! - Unique lists pick uniformly among rewards that have not dropped yet,
+//!   so the only state that matters is how many rewards of each unique list
+//!   have already dropped. The chain runs over these counters.
+//! - The guarantee counter does not depend on what dropped (the sampler
+//!   resets it only when the guarantee fires), so it is replayed
+//!   deterministically.
+//!
+//! Unique lists are assumed not to share reward ids with each other. Every
+//! unique list then only depends on its own counter, so what one slot drops
+//! never changes what another slot of the same opening can draw, which
+//! matches the sampler adding the drops of an opening once all its slots are
+//! drawn.
+//!
+//! The chain holds up to 2 × Π(n + 1) states, n being the rewards with an id
+//! of each unique list, and is stepped once for every slot of every opening.
+//! The work therefore grows with `times` times the number of states, so
+//! [`expect_multi`] refuses more than [`MAX_EXPECT_TIMES`] openings.
+//!
+//! Rewards without an id in a unique list are not part of the chain. Their
+//! chance in an opening is averaged over the chain states, and openings are
+//! then treated as independent, so their expectation is exact but their
+//! variance and [`RewardExpectation::at_least_one`] are approximations.
+
+use std::collections::HashMap;
+
+use wows_box::{
+    item::ItemData,
+    lootbox::{LootBox, LootBoxRewardList, LootBoxRewardType, LootBoxSlot},
+    valuation::ValuationTable,
+};
+
+use crate::error::RandError;
+
+/// Statistics of the total amount of a single reward after all openings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RewardExpectation {
+    pub reward_type: LootBoxRewardType,
+    pub is_unique: bool,
+    /// Expected total amount.
+    pub expectation: f64,
+    /// Variance of the total amount.
+    pub variance: f64,
+    /// Probability of getting the reward at least once.
+    pub at_least_one: f64,
+}
+
+impl RewardExpectation {
+    pub fn std_deviation(&self) -> f64 {
+        self.variance.max(0.0).sqrt()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LootBoxExpectation {
+    pub times: u32,
+    /// How many of the openings are guaranteed ones.
+    pub guaranteed_openings: u32,
+    /// Every reward except the filler, ordered by [`LootBoxRewardType::as_precedence`].
+    pub rewards: Vec<RewardExpectation>,
+    /// Rewards given out when a unique list has been exhausted.
+    pub filler: Option<RewardExpectation>,
+    /// Probability of getting at least one unique reward, an approximation
+    /// when some of them have no id.
+    pub unique_at_least_one: f64,
+}
+
+impl LootBoxExpectation {
+    pub fn get(&self, reward_type: &LootBoxRewardType) -> Option<&RewardExpectation> {
+        self.rewards
+            .iter()
+            .chain(self.filler.iter())
+            .find(|t| &t.reward_type == reward_type)
+    }
+
+    /// Expected value of all openings in doubloons, and the number of rewards
+    /// without a value. See [`ValuationTable::total`].
+    pub fn value(&self, valuation: &ValuationTable, items: &HashMap<u64, ItemData>) -> (f64, u32) {
+        let mut value = 0.0;
+        let mut unvalued = 0;
+        for reward in self.rewards.iter().chain(self.filler.iter()) {
+            let item = reward.reward_type.get_id().and_then(|t| items.get(&t));
+            match valuation.value(&reward.reward_type, item) {
+                Some(t) => value += t * reward.expectation,
+                None => unvalued += 1,
+            }
+        }
+        (value, unvalued)
+    }
+}
+
+/// Moments of independent per-draw contributions of one reward.
+#[derive(Debug, Clone, Copy, Default)]
+struct Moments {
+    mean: f64,
+    variance: f64,
+    /// Probability of never getting the reward.
+    never: f64,
+}
+
+impl Moments {
+    const NONE: Moments = Moments {
+        mean: 0.0,
+        variance: 0.0,
+        never: 1.0,
+    };
+
+    /// Add one draw yielding `amount` with `prob` for each of the `(prob, amount)` pairs.
+    fn add_draw(&mut self, outcomes: &[(f64, u32)]) {
+        let mean: f64 = outcomes.iter().map(|&(p, a)| p * a as f64).sum();
+        let square: f64 = outcomes.iter().map(|&(p, a)| p * (a as f64).powi(2)).sum();
+        let hit: f64 = outcomes.iter().map(|&(p, _)| p).sum();
+        self.mean += mean;
+        self.variance += square - mean * mean;
+        self.never *= (1.0 - hit).clamp(0.0, 1.0);
+    }
+}
+
+/// A unique list of a slot.
+#[derive(Debug, Clone)]
+struct UniquePool<'a> {
+    /// Rewards with an id, not owned before the first opening.
+    remaining: Vec<(u32, &'a LootBoxRewardType)>,
+    /// Rewards without an id never run out. Their moments assume
+    /// independent openings.
+    persistent: Vec<(u32, &'a LootBoxRewardType)>,
+}
+
+impl UniquePool<'_> {
+    fn available(&self, dropped: u32) -> u32 {
+        self.remaining.len() as u32 - dropped + self.persistent.len() as u32
+    }
+}
+
+/// Probability mass of a chain state, with the first two moments of the filler amount.
+#[derive(Debug, Clone, Copy, Default)]
+struct StateMass {
+    prob: f64,
+    filler_m1: f64,
+    filler_m2: f64,
+}
+
+impl StateMass {
+    fn scaled(self, p: f64) -> StateMass {
+        StateMass {
+            prob: self.prob * p,
+            filler_m1: self.filler_m1 * p,
+            filler_m2: self.filler_m2 * p,
+        }
+    }
+
+    fn with_filler(self, p: f64, amount: u32) -> StateMass {
+        let a = amount as f64;
+        StateMass {
+            prob: self.prob * p,
+            filler_m1: (self.filler_m1 + a * self.prob) * p,
+            filler_m2: (self.filler_m2 + 2.0 * a * self.filler_m1 + a * a * self.prob) * p,
+        }
+    }
+
+    fn merge(&mut self, other: StateMass) {
+        self.prob += other.prob;
+        self.filler_m1 += other.filler_m1;
+        self.filler_m2 += other.filler_m2;
+    }
+}
+
+/// `(dropped count of each unique pool, whether a filler was given)`
+type ChainState = (Vec<u32>, bool);
+
+/// Draw model of one slot, either in a normal or a guaranteed opening.
+struct SlotModel<'a> {
+    /// Independent part: `(reward, amount, probability)`.
+    regular: Vec<(&'a LootBoxRewardType, u32, f64)>,
+    /// `(pool index, probability of hitting the pool)`
+    unique: Vec<(usize, f64)>,
+}
+
+fn slot_lists(slot: &LootBoxSlot) -> impl Iterator<Item = &LootBoxRewardList> {
+    slot.common.iter().chain(slot.valuable.iter())
+}
+
+/// `pools` maps the position of a unique list in [`slot_lists`] to its pool index.
+fn slot_model<'a>(
+    slot: &'a LootBoxSlot,
+    pools: &HashMap<usize, usize>,
+    guaranteed: bool,
+) -> SlotModel<'a> {
+    let unique_valuable: Vec<_> = slot_lists(slot)
+        .enumerate()
+        .skip(slot.common.len())
+        .filter(|(_, t)| t.has_unique_rewards)
+        .collect();
+
+    // Same rule as `rand_unique`: only valuable unique lists are drawn,
+    // weighted by their own probabilities.
+    if guaranteed && !unique_valuable.is_empty() {
+        let total: f64 = unique_valuable.iter().map(|(_, t)| t.probability).sum();
+        return SlotModel {
+            regular: vec![],
+            unique: unique_valuable
+                .into_iter()
+                .map(|(idx, t)| (pools[&idx], t.probability / total))
+                .collect(),
+        };
+    }
+
+    let mut model = SlotModel {
+        regular: vec![],
+        unique: vec![],
+    };
+    for (idx, list) in slot_lists(slot).enumerate() {
+        if list.has_unique_rewards {
+            model.unique.push((pools[&idx], list.probability));
+        } else {
+            model.regular.extend(
+                list.rewards
+                    .iter()
+                    .map(|t| (&t.reward, t.amount, t.probability)),
+            );
+        }
+    }
+    model
+}
+
+/// Upper bound of `times` in [`expect_multi`].
+pub const MAX_EXPECT_TIMES: u32 = 100000;
+
+/// Compute the statistics of opening `data` for `times` times, exact except
+/// for rewards without an id in unique lists, see the module docs.
+///
+/// The arguments mirror [`crate::rand::rand_multi`]: `unique_rewards_list`
+/// holds unique reward ids the player already owns, and `current_try` is
+/// the guarantee counter before the first opening. Takes time proportional
+/// to `times` and the chain states described in the module docs, and fails
+/// with [`RandError::TooManyOpenings`] over [`MAX_EXPECT_TIMES`].
+pub fn expect_multi(
+    data: &LootBox,
+    times: u32,
+    unique_rewards_list: &[u64],
+    mut current_try: u32,
+) -> Result<LootBoxExpectation, RandError> {
+    if times > MAX_EXPECT_TIMES {
+        return Err(RandError::TooManyOpenings {
+            times,
+            max: MAX_EXPECT_TIMES,
+        });
+    }
+
+    let mut pools: Vec<UniquePool> = vec![];
+    let mut normal = vec![];
+    let mut guaranteed = vec![];
+    for slot in data.slots.iter() {
+        let mut slot_pools = HashMap::new();
+        for (idx, list) in slot_lists(slot).enumerate() {
+            if !list.has_unique_rewards {
+                continue;
+            }
+            let (remaining, persistent) = list
+                .rewards
+                .iter()
+                .filter(|t| {
+                    !t.reward
+                        .get_id()
+                        .is_some_and(|i| unique_rewards_list.contains(&i))
+                })
+                .map(|t| (t.amount, &t.reward))
+                .partition(|(_, t)| t.get_id().is_some());
+            slot_pools.insert(idx, pools.len());
+            pools.push(UniquePool {
+                remaining,
+                persistent,
+            });
+        }
+        normal.push(slot_model(slot, &slot_pools, false));
+        guaranteed.push(slot_model(slot, &slot_pools, true));
+    }
+
+    let guarantee = data.save_point.unwrap_or(u32::MAX);
+    let filler = data.filler.as_ref();
+
+    let mut regular: HashMap<(&LootBoxRewardType, bool), Moments> = HashMap::new();
+    let mut chain: HashMap<ChainState, StateMass> = HashMap::new();
+    chain.insert(
+        (vec![0; pools.len()], false),
+        StateMass {
+            prob: 1.0,
+            ..Default::default()
+        },
+    );
+    let mut guaranteed_openings = 0;
+
+    for _ in 0..times {
+        current_try += 1;
+        let models = if current_try >= guarantee {
+            current_try = 0;
+            guaranteed_openings += 1;
+            &guaranteed
+        } else {
+            &normal
+        };
+
+        for model in models {
+            let mut outcomes: HashMap<&LootBoxRewardType, Vec<(f64, u32)>> = HashMap::new();
+            for &(reward, amount, prob) in model.regular.iter() {
+                outcomes.entry(reward).or_default().push((prob, amount));
+            }
+            for (reward, outcomes) in outcomes {
+                regular
+                    .entry((reward, false))
+                    .or_insert(Moments::NONE)
+                    .add_draw(&outcomes);
+            }
+
+            if model.unique.is_empty() {
+                continue;
+            }
+
+            // Rewards without an id in unique lists, keyed by pool and position.
+            let mut persistent: HashMap<(usize, usize), f64> = HashMap::new();
+            let mut next: HashMap<ChainState, StateMass> = HashMap::new();
+            let stay: f64 = 1.0 - model.unique.iter().map(|&(_, p)| p).sum::<f64>();
+            for ((dropped, filled), mass) in chain.into_iter() {
+                if stay > 0.0 {
+                    next.entry((dropped.clone(), filled))
+                        .or_default()
+                        .merge(mass.scaled(stay));
+                }
+                for &(pool, hit) in model.unique.iter() {
+                    let available = pools[pool].available(dropped[pool]);
+                    if available == 0 {
+                        match filler {
+                            Some(filler) => next
+                                .entry((dropped.clone(), true))
+                                .or_default()
+                                .merge(mass.with_filler(hit, filler.amount)),
+                            None => next
+                                .entry((dropped.clone(), filled))
+                                .or_default()
+                                .merge(mass.scaled(hit)),
+                        }
+                        continue;
+                    }
+                    let new_unique = pools[pool].remaining.len() as u32 - dropped[pool];
+                    if new_unique > 0 {
+                        let mut state = dropped.clone();
+                        state[pool] += 1;
+                        next.entry((state, filled))
+                            .or_default()
+                            .merge(mass.scaled(hit * new_unique as f64 / available as f64));
+                    }
+                    if !pools[pool].persistent.is_empty() {
+                        for idx in 0..pools[pool].persistent.len() {
+                            *persistent.entry((pool, idx)).or_default() +=
+                                mass.prob * hit / available as f64;
+                        }
+                        next.entry((dropped.clone(), filled)).or_default().merge(
+                            mass.scaled(
+                                hit * pools[pool].persistent.len() as f64 / available as f64,
+                            ),
+                        );
+                    }
+                }
+            }
+            chain = next;
+
+            for ((pool, idx), prob) in persistent {
+                let (amount, reward) = pools[pool].persistent[idx];
+                regular
+                    .entry((reward, true))
+                    .or_insert(Moments::NONE)
+                    .add_draw(&[(prob, amount)]);
+            }
+        }
+    }
+
+    // Chain states where no unique reward with an id has dropped, then no
+    // reward without one either.
+    let no_unique = chain
+        .iter()
+        .filter(|((d, _), _)| d.iter().all(|&t| t == 0))
+        .map(|(_, m)| m.prob)
+        .sum::<f64>()
+        * regular
+            .iter()
+            .filter(|((_, is_unique), _)| *is_unique)
+            .map(|(_, m)| m.never)
+            .product::<f64>();
+
+    let mut rewards: Vec<RewardExpectation> = regular
+        .into_iter()
+        .map(|((reward, is_unique), m)| RewardExpectation {
+            reward_type: reward.clone(),
+            is_unique,
+            expectation: m.mean,
+            variance: m.variance,
+            at_least_one: 1.0 - m.never,
+        })
+        .collect();
+
+    for (idx, pool) in pools.iter().enumerate() {
+        if pool.remaining.is_empty() {
+            continue;
+        }
+        let expected_dropped: f64 = chain.iter().map(|((d, _), m)| d[idx] as f64 * m.prob).sum();
+        let q = expected_dropped / pool.remaining.len() as f64;
+        for &(amount, reward) in pool.remaining.iter() {
+            let a = amount as f64;
+            rewards.push(RewardExpectation {
+                reward_type: reward.clone(),
+                is_unique: true,
+                expectation: a * q,
+                variance: a * a * q * (1.0 - q),
+                at_least_one: q,
+            });
+        }
+    }
+
+    rewards.sort_by_key(|t| {
+        let (class, order) = t.reward_type.as_precedence();
+        (
+            (class as u128).checked_shl(64).unwrap_or(0) + order.unwrap_or(0) as u128,
+            !t.is_unique,
+        )
+    });
+
+    let filler = filler.map(|filler| {
+        let (m1, m2, filled) = chain.iter().fold((0.0, 0.0, 0.0), |acc, ((_, f), m)| {
+            (
+                acc.0 + m.filler_m1,
+                acc.1 + m.filler_m2,
+                acc.2 + if *f { m.prob } else { 0.0 },
+            )
+        });
+        RewardExpectation {
+            reward_type: filler.filler.clone(),
+            is_unique: false,
+            expectation: m1,
+            variance: m2 - m1 * m1,
+            at_least_one: filled,
+        }
+    });
+
+    Ok(LootBoxExpectation {
+        times,
+        guaranteed_openings,
+        rewards,
+        filler,
+        unique_at_least_one: 1.0 - no_unique,
+    })
+}
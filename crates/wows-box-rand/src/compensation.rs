@@ -0,0 +1,151 @@
+//! Rewards the player already owns are converted into compensation.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wows_box::lootbox::{LootBoxRewardType, SkinShipBaseData};
+
+use crate::rand::{OpeningRecord, RandResult};
+
+/// Reward kinds which are compensated when already owned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompensationKind {
+    Ship,
+    Permoflage,
+    Mskin,
+    Skin,
+}
+
+impl CompensationKind {
+    /// Kind and ship level of `reward`, if it can be compensated.
+    pub fn of(reward: &LootBoxRewardType) -> Option<(Self, u8)> {
+        let level = |ship: &SkinShipBaseData| ship.ship_level;
+        match reward {
+            LootBoxRewardType::Ship { ship_level, .. } => Some((Self::Ship, *ship_level)),
+            LootBoxRewardType::Permoflage { ship, .. } => Some((Self::Permoflage, level(ship))),
+            LootBoxRewardType::Mskin { ship, .. } => Some((Self::Mskin, level(ship))),
+            LootBoxRewardType::Skin { ship, .. } => Some((Self::Skin, level(ship))),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompensationRule {
+    /// Given instead of the owned reward.
+    pub reward_type: LootBoxRewardType,
+    /// Amount by ship level, starting from level I. The last one is used
+    /// for every higher level.
+    pub amounts: Vec<u32>,
+}
+
+impl CompensationRule {
+    pub fn amount(&self, level: u8) -> u32 {
+        let idx = (level.max(1) as usize - 1).min(self.amounts.len().saturating_sub(1));
+        self.amounts.get(idx).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompensationTable(pub HashMap<CompensationKind, CompensationRule>);
+
+impl Default for CompensationTable {
+    /// Doubloon values close to the in-game ones.
+    fn default() -> Self {
+        let gold = |amounts: &[u32]| CompensationRule {
+            reward_type: LootBoxRewardType::Gold,
+            amounts: amounts.to_owned(),
+        };
+        CompensationTable(HashMap::from([
+            (
+                CompensationKind::Ship,
+                gold(&[
+                    250, 500, 750, 1000, 1500, 2000, 3500, 5000, 7500, 10000, 15000,
+                ]),
+            ),
+            (
+                CompensationKind::Permoflage,
+                gold(&[100, 100, 200, 200, 500, 500, 1000, 1000, 1500, 2000, 2000]),
+            ),
+            (CompensationKind::Mskin, gold(&[1000])),
+            (CompensationKind::Skin, gold(&[500])),
+        ]))
+    }
+}
+
+impl CompensationTable {
+    /// Compensation of `amount` of `reward` when it is already owned.
+    pub fn compensation(&self, reward: &LootBoxRewardType, amount: u32) -> Option<RandResult> {
+        let (kind, level) = CompensationKind::of(reward)?;
+        let rule = self.0.get(&kind)?;
+        Some(RandResult::new(
+            rule.amount(level) * amount,
+            rule.reward_type.clone(),
+            false,
+        ))
+    }
+}
+
+/// Mark every reward of `log` the player already owns with its compensation.
+///
+/// `owned_items` holds ids owned before the first opening, rewards dropped
+/// in `log` are owned from then on. Returns the owned ids after `log`.
+pub fn compensate(
+    log: &mut [OpeningRecord],
+    table: &CompensationTable,
+    owned_items: &[u64],
+) -> Vec<u64> {
+    let mut owned = owned_items.to_owned();
+    for slot in log.iter_mut().flat_map(|t| t.slots.iter_mut()) {
+        let reward = &slot.reward;
+        let Some(id) = reward
+            .reward_type
+            .get_id()
+            .filter(|_| CompensationKind::of(&reward.reward_type).is_some())
+        else {
+            continue;
+        };
+        if owned.contains(&id) {
+            slot.compensation = table.compensation(&reward.reward_type, reward.amount);
+        } else {
+            owned.push(id);
+        }
+    }
+    owned
+}
+
+/// Sum up the compensation of an opening log.
+///
+/// Returns: (Type, is_guarantee): total compensation
+pub fn compensations(log: &[OpeningRecord]) -> HashMap<(LootBoxRewardType, bool), RandResult> {
+    let mut map: HashMap<_, RandResult> = HashMap::new();
+    for opening in log {
+        for slot in opening.slots.iter() {
+            let Some(compensation) = &slot.compensation else {
+                continue;
+            };
+            map.entry((slot.reward.reward_type.clone(), opening.is_guaranteed))
+                .and_modify(|t| t.amount += compensation.amount)
+                .or_insert_with(|| compensation.clone());
+        }
+    }
+    map
+}
+
+/// Sum up the rewards of an opening log as received, with every compensated
+/// reward replaced by its compensation.
+///
+/// Returns: (Type, is_guarantee): amount
+pub fn received(log: &[OpeningRecord]) -> HashMap<(LootBoxRewardType, bool), u32> {
+    let mut map = HashMap::new();
+    for opening in log {
+        for slot in opening.slots.iter() {
+            let reward = slot.compensation.as_ref().unwrap_or(&slot.reward);
+            *map.entry((reward.reward_type.clone(), opening.is_guaranteed))
+                .or_insert(0) += reward.amount;
+        }
+    }
+    map
+}
@@ -0,0 +1,72 @@
+use thiserror::Error;
+use wows_box::validate::LootBoxIssue;
+
+pub use wows_box::validate::PROBABILITY_TOLERANCE;
+
+/// Malformed lootbox data found by [`validate`](crate::rand::validate) or
+/// while sampling, or a request too large to compute.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum RandError {
+    #[error("slot {slot} has no reward list")]
+    EmptySlot { slot: usize },
+    #[error("reward list `{list}` in slot {slot} has no reward")]
+    EmptyList { slot: usize, list: String },
+    #[error("reward list `{list}` in slot {slot} has invalid probability {probability}")]
+    InvalidProbability {
+        slot: usize,
+        list: String,
+        probability: f64,
+    },
+    #[error("reward lists in slot {slot} sum up to {sum} instead of 1")]
+    SlotProbability { slot: usize, sum: f64 },
+    #[error("rewards in list `{list}` of slot {slot} sum up to {sum} instead of {expected}")]
+    ListProbability {
+        slot: usize,
+        list: String,
+        sum: f64,
+        expected: f64,
+    },
+    #[error(
+        "reward list `{list}` in slot {slot} has unique rewards but the lootbox has no filler"
+    )]
+    MissingFiller { slot: usize, list: String },
+    #[error("every unique reward of list `{list}` in slot {slot} is owned and there is no filler")]
+    Exhausted { slot: usize, list: String },
+    #[error("{times} openings is over the limit of {max}")]
+    TooManyOpenings { times: u32, max: u32 },
+}
+
+impl TryFrom<LootBoxIssue> for RandError {
+    type Error = LootBoxIssue;
+
+    /// Issues the sampler cannot work with, the others are given back.
+    fn try_from(issue: LootBoxIssue) -> Result<Self, Self::Error> {
+        Ok(match issue {
+            LootBoxIssue::EmptySlot { slot } => RandError::EmptySlot { slot },
+            LootBoxIssue::EmptyList { slot, list } => RandError::EmptyList { slot, list },
+            LootBoxIssue::InvalidProbability {
+                slot,
+                list,
+                probability,
+            } => RandError::InvalidProbability {
+                slot,
+                list,
+                probability,
+            },
+            LootBoxIssue::SlotProbability { slot, sum } => RandError::SlotProbability { slot, sum },
+            LootBoxIssue::ListProbability {
+                slot,
+                list,
+                sum,
+                expected,
+            } => RandError::ListProbability {
+                slot,
+                list,
+                sum,
+                expected,
+            },
+            LootBoxIssue::MissingFiller { slot, list } => RandError::MissingFiller { slot, list },
+            other => return Err(other),
+        })
+    }
+}
@@ -1 +1,6 @@
+pub mod analytic;
+pub mod compensation;
+pub mod error;
+pub mod nested;
 pub mod rand;
+pub mod seed;
@@ -0,0 +1,175 @@
+//! Open lootboxes dropped by other lootboxes.
+//!
+//! The sampler does not access the database, so every inner lootbox is
+//! resolved by the caller beforehand, see [`nested_box_ids`].
+
+use std::collections::HashMap;
+
+use rand::Rng;
+use wows_box::lootbox::{LootBox, LootBoxRewardType};
+
+use crate::{
+    compensation::{compensate, CompensationTable},
+    rand::{rand_multi_log, state_after, OpeningRecord, RandResult, SlotRecord},
+};
+
+/// Result of fully opening the rewards of an opening log.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NestedOpening {
+    /// Same as [`crate::rand::aggregate`], with every openable lootbox
+    /// replaced by its content: (Type, is_guarantee): amount
+    pub totals: HashMap<(LootBoxRewardType, bool), u32>,
+    /// Same as [`crate::compensation::compensations`], for the rewards in
+    /// `totals`.
+    pub compensations: HashMap<(LootBoxRewardType, bool), RandResult>,
+    /// Same as [`crate::compensation::received`], for the rewards in `totals`.
+    pub received: HashMap<(LootBoxRewardType, bool), u32>,
+    /// Inner lootboxes left closed, because they are unknown, broken, too
+    /// deep, contain themselves or are over [`MAX_NESTED_OPENINGS`].
+    pub unopened: u32,
+}
+
+/// Upper bound of inner lootboxes opened for one opening log, as amounts
+/// multiply with every level.
+pub const MAX_NESTED_OPENINGS: u32 = 100000;
+
+/// Ids of lootboxes `data` can drop.
+pub fn nested_box_ids(data: &LootBox) -> Vec<u64> {
+    let mut ids: Vec<_> = data
+        .slots
+        .iter()
+        .flat_map(|t| t.common.iter().chain(t.valuable.iter()))
+        .flat_map(|t| t.rewards.iter())
+        .filter_map(|t| match t.reward {
+            LootBoxRewardType::Lootbox { id, .. } => Some(id),
+            _ => None,
+        })
+        .collect();
+    ids.sort_unstable();
+    ids.dedup();
+    ids
+}
+
+struct Opener<'a, R: Rng> {
+    rng: &'a mut R,
+    boxes: &'a HashMap<u64, LootBox>,
+    max_depth: usize,
+    table: &'a CompensationTable,
+    /// Ids of ships and camouflages owned so far, see [`compensate`].
+    owned: Vec<u64>,
+    /// Unique reward ids dropped by every inner lootbox opened so far.
+    unique: HashMap<u64, Vec<u64>>,
+    /// Guarantee counter of every inner lootbox opened so far.
+    pity: HashMap<u64, u32>,
+    totals: HashMap<(LootBoxRewardType, bool), u32>,
+    compensations: HashMap<(LootBoxRewardType, bool), RandResult>,
+    received: HashMap<(LootBoxRewardType, bool), u32>,
+    unopened: u32,
+    /// Inner lootboxes which may still be opened.
+    budget: u32,
+}
+
+impl<R: Rng> Opener<'_, R> {
+    /// `stack` holds the lootboxes being opened, outermost first.
+    fn add(&mut self, slot: &SlotRecord, is_guaranteed: bool, stack: &mut Vec<u64>) {
+        let reward = &slot.reward;
+        let inner = match &reward.reward_type {
+            LootBoxRewardType::Lootbox { id, .. } => self.boxes.get(id).filter(|_| {
+                !stack.contains(id) && stack.len() <= self.max_depth && reward.amount <= self.budget
+            }),
+            _ => None,
+        };
+        // A broken inner lootbox is kept closed instead of failing the others.
+        let opened = inner.and_then(|inner| {
+            let unique = self.unique.get(&inner.id).map_or(&[][..], Vec::as_slice);
+            let current_try = self.pity.get(&inner.id).copied().unwrap_or(0);
+            rand_multi_log(self.rng, inner, reward.amount, unique, current_try)
+                .ok()
+                .map(|log| (inner, current_try, log))
+        });
+        let Some((inner, current_try, mut log)) = opened else {
+            if matches!(reward.reward_type, LootBoxRewardType::Lootbox { .. }) {
+                self.unopened += reward.amount;
+            }
+            *self
+                .totals
+                .entry((reward.reward_type.clone(), is_guaranteed))
+                .or_insert(0) += reward.amount;
+            if let Some(compensation) = &slot.compensation {
+                self.compensations
+                    .entry((reward.reward_type.clone(), is_guaranteed))
+                    .and_modify(|t| t.amount += compensation.amount)
+                    .or_insert_with(|| compensation.clone());
+            }
+            let received = slot.compensation.as_ref().unwrap_or(reward);
+            *self
+                .received
+                .entry((received.reward_type.clone(), is_guaranteed))
+                .or_insert(0) += received.amount;
+            return;
+        };
+
+        self.budget -= reward.amount;
+        self.owned = compensate(&mut log, self.table, &self.owned);
+        let unique = self.unique.get(&inner.id).map_or(&[][..], Vec::as_slice);
+        let (unique, current_try) = state_after(&log, unique, current_try);
+        self.unique.insert(inner.id, unique);
+        self.pity.insert(inner.id, current_try);
+
+        stack.push(inner.id);
+        for opening in log.iter() {
+            for slot in opening.slots.iter() {
+                self.add(slot, is_guaranteed || opening.is_guaranteed, stack);
+            }
+        }
+        stack.pop();
+    }
+}
+
+/// Open every lootbox in the rewards of `log`, recursively.
+///
+/// `log` comes from opening `data`, already passed through [`compensate`],
+/// and `owned_items` are the ids it returned. Rewards of the inner lootboxes
+/// are compensated with `table` the same way. Every inner lootbox starts from
+/// an empty guarantee counter and no unique rewards, kept apart from the ones
+/// of `data` and of the other inner lootboxes. Lootboxes missing in `boxes`, nested deeper than
+/// `max_depth` or already being opened are kept as they are, and so are
+/// broken ones and the ones over [`MAX_NESTED_OPENINGS`].
+pub fn open_nested(
+    rng: &mut impl Rng,
+    data: &LootBox,
+    log: &[OpeningRecord],
+    boxes: &HashMap<u64, LootBox>,
+    table: &CompensationTable,
+    owned_items: &[u64],
+    max_depth: usize,
+) -> NestedOpening {
+    let mut opener = Opener {
+        rng,
+        boxes,
+        max_depth,
+        table,
+        owned: owned_items.to_owned(),
+        unique: HashMap::new(),
+        pity: HashMap::new(),
+        totals: HashMap::new(),
+        compensations: HashMap::new(),
+        received: HashMap::new(),
+        unopened: 0,
+        budget: MAX_NESTED_OPENINGS,
+    };
+
+    let mut stack = vec![data.id];
+    for opening in log {
+        for slot in opening.slots.iter() {
+            opener.add(slot, opening.is_guaranteed, &mut stack);
+        }
+    }
+
+    NestedOpening {
+        totals: opener.totals,
+        compensations: opener.compensations,
+        received: opener.received,
+        unopened: opener.unopened,
+    }
+}
@@ -1,9 +1,12 @@
 use std::collections::HashMap;
 
-use rand::{rngs::SmallRng, Rng};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use wows_box::lootbox::{LootBox, LootBoxFiller, LootBoxRewardList, LootBoxRewardType};
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+use crate::error::RandError;
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct RandResult {
     pub amount: u32,
     pub reward_type: LootBoxRewardType,
@@ -20,193 +23,560 @@ impl RandResult {
     }
 }
 
-/// Return type: amount, type, is-unique
+/// What a single slot produced in one opening.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SlotRecord {
+    pub slot_index: usize,
+    pub slot_name: String,
+    /// Name of the reward list the reward was drawn from.
+    pub list_name: String,
+    pub reward: RandResult,
+    /// The list's unique rewards were exhausted and the filler was given instead.
+    pub is_filler: bool,
+    /// `reward` was already owned and converted into this, see
+    /// [`crate::compensation::compensate`].
+    #[serde(default)]
+    pub compensation: Option<RandResult>,
+}
+
+/// What one opening produced.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpeningRecord {
+    /// Starts from 1.
+    pub index: u32,
+    pub is_guaranteed: bool,
+    /// Guarantee counter before this opening.
+    pub pity_before: u32,
+    /// Guarantee counter after this opening.
+    pub pity_after: u32,
+    pub slots: Vec<SlotRecord>,
+}
+
+/// Return type: result, the list drawn from, whether the filler was given
 fn rand_slot<'a>(
-    rng: &mut SmallRng,
+    rng: &mut impl Rng,
     filler: Option<&LootBoxFiller>,
-    // slot: &LootBoxSlot,
-    lists: impl Iterator<Item = &'a LootBoxRewardList>,
+    slot_index: usize,
+    lists: &[&'a LootBoxRewardList],
     max_prob: f64,
     unique_rewards_list: &[u64],
-) -> RandResult {
+) -> Result<(RandResult, &'a LootBoxRewardList, bool), RandError> {
     let mut seed: f64 = rng.gen_range(0.0..max_prob);
 
-    'list: for list in lists {
-        debug_assert!(
-            (list.probability - list.rewards.iter().map(|t| t.probability).sum::<f64>()).abs()
-                <= 0.01
-        );
-        if seed < list.probability {
-            if list.has_unique_rewards {
-                let possible_amount = list
-                    .rewards
-                    .iter()
-                    .filter(|t| {
-                        !t.reward
-                            .get_id()
-                            .is_some_and(|i| unique_rewards_list.contains(&i))
-                    })
-                    .count();
-                if possible_amount == 0 {
-                    if let Some(filler) = filler {
-                        return RandResult::new(
-                            filler.amount,
-                            filler.filler.clone(),
-                            list.has_unique_rewards,
-                        );
-                    }
-                }
-                let target = rng.gen_range(0..possible_amount);
-                let reward = list
-                    .rewards
-                    .iter()
-                    .filter(|t| {
-                        !t.reward
-                            .get_id()
-                            .is_some_and(|i| unique_rewards_list.contains(&i))
-                    })
-                    .nth(target)
-                    .unwrap();
-                return RandResult::new(
-                    reward.amount,
-                    reward.reward.clone(),
+    // Rounding may leave `seed` past the last list, which then takes it.
+    let Some(&list) = lists
+        .iter()
+        .find(|t| {
+            let hit = seed < t.probability;
+            seed -= t.probability;
+            hit
+        })
+        .or(lists.last())
+    else {
+        return Err(RandError::EmptySlot { slot: slot_index });
+    };
+
+    if list.has_unique_rewards {
+        let possible: Vec<_> = list
+            .rewards
+            .iter()
+            .filter(|t| {
+                !t.reward
+                    .get_id()
+                    .is_some_and(|i| unique_rewards_list.contains(&i))
+            })
+            .collect();
+        if possible.is_empty() {
+            let Some(filler) = filler else {
+                return Err(RandError::Exhausted {
+                    slot: slot_index,
+                    list: list.name.clone(),
+                });
+            };
+            return Ok((
+                RandResult::new(
+                    filler.amount,
+                    filler.filler.clone(),
                     list.has_unique_rewards,
-                );
-            } else {
-                let mut seed: f64 = rng.gen_range(0.0..list.probability);
-                'no_unique: for reward in list.rewards.iter() {
-                    if seed < reward.probability {
-                        return RandResult::new(
-                            reward.amount,
-                            reward.reward.clone(),
-                            list.has_unique_rewards,
-                        );
-                    } else {
-                        seed -= reward.probability;
-                        continue 'no_unique;
-                    }
-                }
-            }
-        } else {
-            seed -= list.probability;
-            continue 'list;
+                ),
+                list,
+                true,
+            ));
         }
+        let reward = possible[rng.gen_range(0..possible.len())];
+        return Ok((
+            RandResult::new(
+                reward.amount,
+                reward.reward.clone(),
+                list.has_unique_rewards,
+            ),
+            list,
+            false,
+        ));
     }
 
-    unreachable!()
+    let mut seed: f64 = rng.gen_range(0.0..list.probability);
+    let reward = list
+        .rewards
+        .iter()
+        .find(|t| {
+            let hit = seed < t.probability;
+            seed -= t.probability;
+            hit
+        })
+        .or(list.rewards.last())
+        .ok_or_else(|| RandError::EmptyList {
+            slot: slot_index,
+            list: list.name.clone(),
+        })?;
+    Ok((
+        RandResult::new(
+            reward.amount,
+            reward.reward.clone(),
+            list.has_unique_rewards,
+        ),
+        list,
+        false,
+    ))
 }
 
-pub fn rand_single(
-    rng: &mut SmallRng,
+/// Check that `data` can be sampled.
+///
+/// The sampling functions only fail on data rejected here, so checking once
+/// up front gives a precise error instead of one in the middle of a run.
+///
+/// The rules are the ones of [`LootBox::validate`], of which the issues
+/// that stop sampling are reported here.
+pub fn validate(data: &LootBox) -> Result<(), RandError> {
+    match data
+        .validate()
+        .into_iter()
+        .find_map(|t| RandError::try_from(t).ok())
+    {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+fn slot_record(
     data: &LootBox,
+    slot_index: usize,
+    (reward, list, is_filler): (RandResult, &LootBoxRewardList, bool),
+) -> SlotRecord {
+    SlotRecord {
+        slot_index,
+        slot_name: data.slots[slot_index].name.clone(),
+        list_name: list.name.clone(),
+        reward,
+        is_filler,
+        compensation: None,
+    }
+}
+
+/// A reward, the list it was drawn from and whether the filler was given,
+/// for every slot in order.
+type Draws<'a> = Vec<(RandResult, &'a LootBoxRewardList, bool)>;
+
+fn into_records(data: &LootBox, draws: Draws<'_>) -> Vec<SlotRecord> {
+    draws
+        .into_iter()
+        .enumerate()
+        .map(|(idx, draw)| slot_record(data, idx, draw))
+        .collect()
+}
+
+fn draw_single<'a>(
+    rng: &mut impl Rng,
+    data: &'a LootBox,
     unique_rewards_list: &[u64],
-) -> Vec<RandResult> {
-    let mut rewards: Vec<RandResult> = Vec::new();
+) -> Result<Draws<'a>, RandError> {
+    let mut rewards = Vec::with_capacity(data.slots.len());
 
-    for slot in data.slots.iter() {
-        let resp = rand_slot(
+    for (idx, slot) in data.slots.iter().enumerate() {
+        let lists: Vec<_> = slot.common.iter().chain(slot.valuable.iter()).collect();
+        rewards.push(rand_slot(
             rng,
             data.filler.as_ref(),
-            slot.common.iter().chain(slot.valuable.iter()),
+            idx,
+            &lists,
             1.0,
             unique_rewards_list,
-        );
-        rewards.push(resp);
+        )?);
     }
 
-    assert_eq!(
-        rewards.len(),
-        data.slots.len(),
-        "one slot produces one reward"
-    );
-
-    rewards
+    Ok(rewards)
 }
 
-pub fn rand_unique(
-    rng: &mut SmallRng,
-    data: &LootBox,
+fn draw_unique<'a>(
+    rng: &mut impl Rng,
+    data: &'a LootBox,
     unique_rewards_list: &[u64],
-) -> Vec<RandResult> {
-    let mut rewards: Vec<RandResult> = Vec::new();
+) -> Result<Draws<'a>, RandError> {
+    let mut rewards = Vec::with_capacity(data.slots.len());
 
-    'slot: for slot in data.slots.iter() {
+    for (idx, slot) in data.slots.iter().enumerate() {
         let unique_rewards: Vec<_> = slot
             .valuable
             .iter()
             .filter(|t| t.has_unique_rewards)
             .collect();
 
-        if unique_rewards.len() == 0 {
-            let resp = rand_slot(
+        let resp = if unique_rewards.is_empty() {
+            let lists: Vec<_> = slot.common.iter().chain(slot.valuable.iter()).collect();
+            rand_slot(
                 rng,
                 data.filler.as_ref(),
-                slot.common.iter().chain(slot.valuable.iter()),
+                idx,
+                &lists,
                 1.0,
                 unique_rewards_list,
-            );
-            rewards.push(resp);
-            continue 'slot;
-        }
-
-        let prob = unique_rewards.iter().map(|t| t.probability).sum();
-        let resp = rand_slot(
-            rng,
-            data.filler.as_ref(),
-            unique_rewards.into_iter(),
-            prob,
-            unique_rewards_list,
-        );
+            )?
+        } else {
+            let prob = unique_rewards.iter().map(|t| t.probability).sum();
+            rand_slot(
+                rng,
+                data.filler.as_ref(),
+                idx,
+                &unique_rewards,
+                prob,
+                unique_rewards_list,
+            )?
+        };
         rewards.push(resp);
     }
 
-    assert_eq!(
-        rewards.len(),
-        data.slots.len(),
-        "one slot produces one reward"
-    );
+    Ok(rewards)
+}
 
-    rewards
+pub fn rand_single(
+    rng: &mut impl Rng,
+    data: &LootBox,
+    unique_rewards_list: &[u64],
+) -> Result<Vec<RandResult>, RandError> {
+    Ok(draw_single(rng, data, unique_rewards_list)?
+        .into_iter()
+        .map(|t| t.0)
+        .collect())
 }
 
-/// Returns: (Type, is_guarantee): amount
-pub fn rand_multi(
-    rng: &mut SmallRng,
+/// Same as [`rand_single`], but keeps where every reward comes from.
+pub fn rand_single_log(
+    rng: &mut impl Rng,
+    data: &LootBox,
+    unique_rewards_list: &[u64],
+) -> Result<Vec<SlotRecord>, RandError> {
+    Ok(into_records(
+        data,
+        draw_single(rng, data, unique_rewards_list)?,
+    ))
+}
+
+pub fn rand_unique(
+    rng: &mut impl Rng,
+    data: &LootBox,
+    unique_rewards_list: &[u64],
+) -> Result<Vec<RandResult>, RandError> {
+    Ok(draw_unique(rng, data, unique_rewards_list)?
+        .into_iter()
+        .map(|t| t.0)
+        .collect())
+}
+
+/// Same as [`rand_unique`], but keeps where every reward comes from.
+pub fn rand_unique_log(
+    rng: &mut impl Rng,
     data: &LootBox,
+    unique_rewards_list: &[u64],
+) -> Result<Vec<SlotRecord>, RandError> {
+    Ok(into_records(
+        data,
+        draw_unique(rng, data, unique_rewards_list)?,
+    ))
+}
+
+/// Open `data` for `times` times, handing every opening to `visit` as
+/// `(is_guaranteed, pity_before, pity_after, draws)`.
+fn draw_multi<'a>(
+    rng: &mut impl Rng,
+    data: &'a LootBox,
     times: u32,
     unique_rewards_list: &[u64],
     mut current_try: u32,
-) -> HashMap<(LootBoxRewardType, bool), u32> {
-    let mut map = HashMap::new();
+    mut visit: impl FnMut(bool, u32, u32, Draws<'a>),
+) -> Result<(), RandError> {
+    validate(data)?;
+
     let mut unique_rewards_list = unique_rewards_list.to_owned();
     let guarantee = data.save_point.unwrap_or(u32::MAX);
 
     for _ in 0..times {
+        let pity_before = current_try;
         current_try += 1;
-        // dbg!(current_try);
-        if current_try >= guarantee {
-            let resp = rand_unique(rng, data, &unique_rewards_list);
-            // dbg!(&resp);
-            for reward in resp {
-                let unique_id = reward.reward_type.get_id().filter(|_| reward.is_unique);
-                *map.entry((reward.reward_type, true)).or_insert(0) += reward.amount;
-                if let Some(unique_id) = unique_id {
-                    unique_rewards_list.push(unique_id);
-                }
-            }
+        let is_guaranteed = current_try >= guarantee;
+        let draws = if is_guaranteed {
             current_try = 0;
+            draw_unique(rng, data, &unique_rewards_list)?
         } else {
-            let resp = rand_single(rng, data, &unique_rewards_list);
-            // dbg!(&resp);
-            for reward in resp {
-                let unique_id = reward.reward_type.get_id().filter(|_| reward.is_unique);
-                *map.entry((reward.reward_type, false)).or_insert(0) += reward.amount;
-                if let Some(unique_id) = unique_id {
-                    unique_rewards_list.push(unique_id);
-                }
+            draw_single(rng, data, &unique_rewards_list)?
+        };
+        for (reward, _, _) in draws.iter() {
+            if let Some(unique_id) = reward.reward_type.get_id().filter(|_| reward.is_unique) {
+                unique_rewards_list.push(unique_id);
             }
         }
+        visit(is_guaranteed, pity_before, current_try, draws);
     }
 
+    Ok(())
+}
+
+/// Returns: (Type, is_guarantee): amount
+///
+/// Only the totals are kept, use [`rand_multi_log`] for every opening.
+pub fn rand_multi(
+    rng: &mut impl Rng,
+    data: &LootBox,
+    times: u32,
+    unique_rewards_list: &[u64],
+    current_try: u32,
+) -> Result<HashMap<(LootBoxRewardType, bool), u32>, RandError> {
+    let mut map = HashMap::new();
+    draw_multi(
+        rng,
+        data,
+        times,
+        unique_rewards_list,
+        current_try,
+        |is_guaranteed, _, _, draws| {
+            for (reward, _, _) in draws {
+                *map.entry((reward.reward_type, is_guaranteed)).or_insert(0) += reward.amount;
+            }
+        },
+    )?;
+    Ok(map)
+}
+
+/// Same as [`rand_multi`], but returns every opening in order.
+///
+/// The log grows with `times`, callers should bound it.
+pub fn rand_multi_log(
+    rng: &mut impl Rng,
+    data: &LootBox,
+    times: u32,
+    unique_rewards_list: &[u64],
+    current_try: u32,
+) -> Result<Vec<OpeningRecord>, RandError> {
+    let mut log = vec![];
+    draw_multi(
+        rng,
+        data,
+        times,
+        unique_rewards_list,
+        current_try,
+        |is_guaranteed, pity_before, pity_after, draws| {
+            log.push(OpeningRecord {
+                index: log.len() as u32 + 1,
+                is_guaranteed,
+                pity_before,
+                pity_after,
+                slots: into_records(data, draws),
+            })
+        },
+    )?;
+
+    Ok(log)
+}
+
+/// Sum up the rewards of an opening log.
+///
+/// Rewards are counted as dropped, their compensation is summed up by
+/// [`crate::compensation::compensations`].
+///
+/// Returns: (Type, is_guarantee): amount
+pub fn aggregate(log: &[OpeningRecord]) -> HashMap<(LootBoxRewardType, bool), u32> {
+    let mut map = HashMap::new();
+    for opening in log {
+        for slot in opening.slots.iter() {
+            *map.entry((slot.reward.reward_type.clone(), opening.is_guaranteed))
+                .or_insert(0) += slot.reward.amount;
+        }
+    }
     map
 }
+
+/// Owned unique ids and guarantee counter after the openings in `log`.
+///
+/// `unique_rewards_list` and `current_try` are the ones the log started from.
+pub fn state_after(
+    log: &[OpeningRecord],
+    unique_rewards_list: &[u64],
+    current_try: u32,
+) -> (Vec<u64>, u32) {
+    let mut unique_rewards_list = unique_rewards_list.to_owned();
+    for slot in log.iter().flat_map(|t| t.slots.iter()) {
+        let reward = &slot.reward;
+        if let Some(unique_id) = reward.reward_type.get_id().filter(|_| reward.is_unique) {
+            if !unique_rewards_list.contains(&unique_id) {
+                unique_rewards_list.push(unique_id);
+            }
+        }
+    }
+    let current_try = log.last().map(|t| t.pity_after).unwrap_or(current_try);
+    (unique_rewards_list, current_try)
+}
+
+/// Open `data` until a reward matching `target` drops.
+///
+/// Returns the number of openings needed, or `None` if it did not drop
+/// within `limit` openings. [`validate`] is not called here, so that
+/// [`rand_until_distribution`] only checks once.
+pub fn rand_until(
+    rng: &mut impl Rng,
+    data: &LootBox,
+    target: impl Fn(&LootBoxRewardType) -> bool,
+    unique_rewards_list: &[u64],
+    mut current_try: u32,
+    limit: u32,
+) -> Result<Option<u32>, RandError> {
+    let mut unique_rewards_list = unique_rewards_list.to_owned();
+    let guarantee = data.save_point.unwrap_or(u32::MAX);
+
+    for opening in 1..=limit {
+        current_try += 1;
+        let resp = if current_try >= guarantee {
+            current_try = 0;
+            rand_unique(rng, data, &unique_rewards_list)?
+        } else {
+            rand_single(rng, data, &unique_rewards_list)?
+        };
+        for reward in resp {
+            if target(&reward.reward_type) {
+                return Ok(Some(opening));
+            }
+            if let Some(unique_id) = reward.reward_type.get_id().filter(|_| reward.is_unique) {
+                unique_rewards_list.push(unique_id);
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Openings needed until the target drops, over many simulated runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UntilDistribution {
+    pub runs: u32,
+    /// Sorted openings needed of every run that got the target.
+    pub openings: Vec<u32>,
+    /// Runs which did not get the target within the limit.
+    pub failed: u32,
+    /// Openings after which the guarantee always gives the target, if it can.
+    pub worst_case: Option<u32>,
+}
+
+impl UntilDistribution {
+    pub fn mean(&self) -> Option<f64> {
+        if self.openings.is_empty() {
+            return None;
+        }
+        Some(self.openings.iter().map(|&t| t as f64).sum::<f64>() / self.openings.len() as f64)
+    }
+
+    /// Nearest-rank percentile over successful runs, `p` in `0.0..=1.0`.
+    pub fn percentile(&self, p: f64) -> Option<u32> {
+        if self.openings.is_empty() {
+            return None;
+        }
+        let rank = (p * self.openings.len() as f64).ceil() as usize;
+        Some(self.openings[rank.clamp(1, self.openings.len()) - 1])
+    }
+
+    pub fn median(&self) -> Option<u32> {
+        self.percentile(0.5)
+    }
+
+    /// `(bucket start, bucket end, run count)`, with at most `buckets` buckets.
+    pub fn histogram(&self, buckets: u32) -> Vec<(u32, u32, u32)> {
+        let (Some(&first), Some(&last)) = (self.openings.first(), self.openings.last()) else {
+            return vec![];
+        };
+        let width = (last - first) / buckets.max(1) + 1;
+        let mut histogram: Vec<(u32, u32, u32)> = vec![];
+        for &t in self.openings.iter() {
+            let start = first + (t - first) / width * width;
+            match histogram.last_mut() {
+                Some(bucket) if bucket.0 == start => bucket.2 += 1,
+                _ => histogram.push((start, start + width - 1, 1)),
+            }
+        }
+        histogram
+    }
+}
+
+/// Openings until the guarantee has handed out every unique reward of a
+/// list that contains the target.
+///
+/// Only slots where the guarantee can only pick that list are considered.
+pub fn worst_case_until(
+    data: &LootBox,
+    target: impl Fn(&LootBoxRewardType) -> bool,
+    unique_rewards_list: &[u64],
+    current_try: u32,
+) -> Option<u32> {
+    let guarantee = data.save_point?;
+    let remaining = data
+        .slots
+        .iter()
+        .filter_map(|slot| {
+            let mut unique = slot.valuable.iter().filter(|t| t.has_unique_rewards);
+            match (unique.next(), unique.next()) {
+                (Some(list), None) => Some(list),
+                _ => None,
+            }
+        })
+        .filter(|list| list.rewards.iter().any(|t| target(&t.reward)))
+        .map(|list| {
+            list.rewards
+                .iter()
+                .filter(|t| {
+                    !t.reward
+                        .get_id()
+                        .is_some_and(|i| unique_rewards_list.contains(&i))
+                })
+                .count() as u32
+        })
+        .min()?;
+
+    let first = guarantee.saturating_sub(current_try).max(1);
+    Some(first + remaining.saturating_sub(1) * guarantee)
+}
+
+/// Simulate [`rand_until`] `runs` times.
+pub fn rand_until_distribution(
+    rng: &mut impl Rng,
+    data: &LootBox,
+    target: impl Fn(&LootBoxRewardType) -> bool,
+    unique_rewards_list: &[u64],
+    current_try: u32,
+    runs: u32,
+    limit: u32,
+) -> Result<UntilDistribution, RandError> {
+    validate(data)?;
+
+    let mut openings = Vec::with_capacity(runs as usize);
+    let mut failed = 0;
+    for _ in 0..runs {
+        match rand_until(rng, data, &target, unique_rewards_list, current_try, limit)? {
+            Some(t) => openings.push(t),
+            None => failed += 1,
+        }
+    }
+    openings.sort_unstable();
+
+    Ok(UntilDistribution {
+        runs,
+        openings,
+        failed,
+        worst_case: worst_case_until(data, &target, unique_rewards_list, current_try),
+    })
+}
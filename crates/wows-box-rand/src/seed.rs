@@ -0,0 +1,40 @@
+//! Shareable seed codes.
+//!
+//! A simulation seeded with the same code, on the same lootbox data,
+//! with the same amount and starting state always gives the same result.
+//!
+//! Codes drive [`ChaCha8Rng`], whose output is fixed across platforms and
+//! versions, so they reproduce on any build.
+
+use std::{fmt, str::FromStr};
+
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SeedCode(pub u64);
+
+impl SeedCode {
+    pub fn random() -> SeedCode {
+        SeedCode(thread_rng().gen())
+    }
+
+    pub fn rng(self) -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(self.0)
+    }
+}
+
+impl fmt::Display for SeedCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016X}", self.0)
+    }
+}
+
+impl FromStr for SeedCode {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s.trim(), 16).map(SeedCode)
+    }
+}
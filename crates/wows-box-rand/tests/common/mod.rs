@@ -0,0 +1,217 @@
+#![allow(dead_code)]
+
+use wows_box::lootbox::{
+    LootBox, LootBoxFiller, LootBoxReward, LootBoxRewardList, LootBoxRewardType, LootBoxSlot,
+};
+
+pub fn ship(id: u64) -> LootBoxRewardType {
+    LootBoxRewardType::Ship {
+        crew_level: None,
+        ship_level: 8,
+        id,
+        name: format!("Ship {id}"),
+        is_premium: true,
+        is_special: false,
+        icon: String::new(),
+    }
+}
+
+pub fn reward_list(
+    probability: f64,
+    rewards: Vec<LootBoxReward>,
+    unique: bool,
+) -> LootBoxRewardList {
+    LootBoxRewardList {
+        name: String::new(),
+        short_name: String::new(),
+        probability,
+        rewards,
+        has_unique_rewards: unique,
+    }
+}
+
+pub fn reward(probability: f64, amount: u32, reward: LootBoxRewardType) -> LootBoxReward {
+    LootBoxReward {
+        probability,
+        amount,
+        reward,
+    }
+}
+
+pub fn fixture() -> LootBox {
+    LootBox {
+        name: "Test".to_owned(),
+        short_name: String::new(),
+        wows_name_id: String::new(),
+        id: 1,
+        is_premium: true,
+        icon: String::new(),
+        slots: vec![LootBoxSlot {
+            common: vec![reward_list(
+                0.9,
+                vec![
+                    reward(0.6, 1000, LootBoxRewardType::Credits),
+                    reward(0.3, 100, LootBoxRewardType::Gold),
+                ],
+                false,
+            )],
+            valuable: vec![reward_list(
+                0.1,
+                vec![reward(0.05, 1, ship(11)), reward(0.05, 1, ship(12))],
+                true,
+            )],
+            name: String::new(),
+            continuous_rewards: false,
+        }],
+        filler: Some(LootBoxFiller {
+            filler: LootBoxRewardType::Gold,
+            amount: 500,
+        }),
+        save_point: Some(10),
+        retired: false,
+    }
+}
+
+/// Two slots, with plain lists, two unique lists and a filler.
+pub fn conformance_fixture() -> LootBox {
+    let mut list_a = reward_list(
+        0.7,
+        vec![
+            reward(0.4, 1000, LootBoxRewardType::Credits),
+            reward(0.3, 500, LootBoxRewardType::FreeXp),
+        ],
+        false,
+    );
+    list_a.name = "A".to_owned();
+    let mut list_b = reward_list(
+        0.2,
+        vec![
+            reward(
+                0.15,
+                2,
+                LootBoxRewardType::Signal {
+                    id: 100,
+                    name: "Signal".to_owned(),
+                },
+            ),
+            reward(0.05, 1, LootBoxRewardType::CamoBoost { id: 200 }),
+        ],
+        false,
+    );
+    list_b.name = "B".to_owned();
+    let mut unique_1 = reward_list(
+        0.06,
+        (1..=3).map(|t| reward(0.02, 1, ship(t))).collect(),
+        true,
+    );
+    unique_1.name = "U1".to_owned();
+    let mut unique_2 = reward_list(
+        0.04,
+        (4..=5).map(|t| reward(0.02, 1, ship(t))).collect(),
+        true,
+    );
+    unique_2.name = "U2".to_owned();
+    let mut list_c = reward_list(
+        1.0,
+        vec![
+            reward(0.5, 100, LootBoxRewardType::Coal),
+            reward(0.5, 10, LootBoxRewardType::Steel),
+        ],
+        false,
+    );
+    list_c.name = "C".to_owned();
+
+    LootBox {
+        name: "Conformance".to_owned(),
+        short_name: String::new(),
+        wows_name_id: String::new(),
+        id: 2,
+        is_premium: true,
+        icon: String::new(),
+        slots: vec![
+            LootBoxSlot {
+                common: vec![list_a, list_b],
+                valuable: vec![unique_1, unique_2],
+                name: "first".to_owned(),
+                continuous_rewards: false,
+            },
+            LootBoxSlot {
+                common: vec![list_c],
+                valuable: vec![],
+                name: "second".to_owned(),
+                continuous_rewards: false,
+            },
+        ],
+        filler: Some(LootBoxFiller {
+            filler: LootBoxRewardType::Gold,
+            amount: 1000,
+        }),
+        save_point: Some(5),
+        retired: false,
+    }
+}
+
+/// Pearson's chi-square statistic of `observed` counts against `probs`.
+pub fn chi_square(observed: &[u64], probs: &[f64]) -> f64 {
+    assert_eq!(observed.len(), probs.len());
+    let total: u64 = observed.iter().sum();
+    let prob_sum: f64 = probs.iter().sum();
+    observed
+        .iter()
+        .zip(probs)
+        .map(|(&o, &p)| {
+            let e = total as f64 * p / prob_sum;
+            (o as f64 - e).powi(2) / e
+        })
+        .sum()
+}
+
+/// Upper critical value of chi-square at p = 0.001, by Wilson–Hilferty.
+pub fn chi_square_bound(dof: usize) -> f64 {
+    const Z: f64 = 3.090;
+    let k = dof as f64;
+    let c = 2.0 / (9.0 * k);
+    k * (1.0 - c + Z * c.sqrt()).powi(3)
+}
+
+pub fn assert_chi_square(observed: &[u64], probs: &[f64]) {
+    let stat = chi_square(observed, probs);
+    let bound = chi_square_bound(observed.len() - 1);
+    assert!(
+        stat < bound,
+        "chi-square {stat:.2} exceeds {bound:.2}: observed {observed:?}, expected {probs:?}"
+    );
+}
+
+pub fn lootbox_reward(id: u64) -> LootBoxRewardType {
+    LootBoxRewardType::Lootbox {
+        id,
+        name: format!("Box {id}"),
+        icon: String::new(),
+    }
+}
+
+/// A lootbox with one slot which always drops `amount` of `reward`.
+pub fn single_reward_box(id: u64, amount: u32, reward_type: LootBoxRewardType) -> LootBox {
+    LootBox {
+        name: format!("Box {id}"),
+        short_name: String::new(),
+        wows_name_id: String::new(),
+        id,
+        is_premium: false,
+        icon: String::new(),
+        slots: vec![LootBoxSlot {
+            common: vec![reward_list(
+                1.0,
+                vec![reward(1.0, amount, reward_type)],
+                false,
+            )],
+            valuable: vec![],
+            name: String::new(),
+            continuous_rewards: false,
+        }],
+        filler: None,
+        save_point: None,
+        retired: false,
+    }
+}
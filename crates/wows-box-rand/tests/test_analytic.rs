@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+
+use common::{fixture, ship};
+use rand::{rngs::SmallRng, SeedableRng};
+use wows_box::lootbox::LootBoxRewardType;
+use wows_box_rand::{
+    analytic::{expect_multi, MAX_EXPECT_TIMES},
+    error::RandError,
+    rand::rand_multi,
+};
+
+mod common;
+
+#[test]
+fn test_expect_regular_rewards() {
+    let lootbox = fixture();
+    let resp = expect_multi(&lootbox, 50, &[], 0).unwrap();
+
+    assert_eq!(resp.guaranteed_openings, 5);
+
+    let credits = resp
+        .rewards
+        .iter()
+        .find(|t| t.reward_type == LootBoxRewardType::Credits)
+        .unwrap();
+    assert!((credits.expectation - 45.0 * 0.6 * 1000.0).abs() < 1e-6);
+    assert!((credits.variance - 45.0 * 0.6 * 0.4 * 1000.0 * 1000.0).abs() < 1e-3);
+    assert!((credits.at_least_one - (1.0 - 0.4f64.powi(45))).abs() < 1e-9);
+}
+
+#[test]
+fn test_expect_guarantee_exhausts_uniques() {
+    let lootbox = fixture();
+
+    // Two guarantees always drop both ships, the third one gives the filler.
+    let resp = expect_multi(&lootbox, 30, &[], 0).unwrap();
+    assert!((resp.get(&ship(11)).unwrap().at_least_one - 1.0).abs() < 1e-9);
+    assert!((resp.get(&ship(12)).unwrap().at_least_one - 1.0).abs() < 1e-9);
+    assert!(resp.filler.unwrap().expectation >= 500.0 - 1e-9);
+    assert!((resp.unique_at_least_one - 1.0).abs() < 1e-9);
+
+    // Already owned ships can only ever produce the filler.
+    let resp = expect_multi(&lootbox, 30, &[11, 12], 0).unwrap();
+    assert!(resp.get(&ship(11)).is_none());
+    let filler = resp.filler.unwrap();
+    assert!((filler.expectation - (27.0 * 0.1 + 3.0) * 500.0).abs() < 1e-6);
+    assert!(resp.unique_at_least_one.abs() < 1e-9);
+    assert!((filler.at_least_one - 1.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_expect_too_many_openings() {
+    let lootbox = fixture();
+    assert!(expect_multi(&lootbox, MAX_EXPECT_TIMES, &[], 0).is_ok());
+    assert_eq!(
+        expect_multi(&lootbox, MAX_EXPECT_TIMES + 1, &[], 0),
+        Err(RandError::TooManyOpenings {
+            times: MAX_EXPECT_TIMES + 1,
+            max: MAX_EXPECT_TIMES
+        })
+    );
+}
+
+#[test]
+fn test_expect_matches_simulation() {
+    let lootbox = fixture();
+    let times = 25;
+    let runs = 20000;
+    let resp = expect_multi(&lootbox, times, &[], 3).unwrap();
+
+    let mut rng = SmallRng::seed_from_u64(0x5eed);
+    let mut total: HashMap<LootBoxRewardType, f64> = HashMap::new();
+    let mut has_ship = 0;
+    for _ in 0..runs {
+        let map = rand_multi(&mut rng, &lootbox, times, &[], 3).unwrap();
+        if map.keys().any(|(t, _)| *t == ship(11)) {
+            has_ship += 1;
+        }
+        for ((reward, _), amount) in map {
+            *total.entry(reward).or_default() += amount as f64;
+        }
+    }
+
+    let gold = resp.get(&LootBoxRewardType::Gold).unwrap().expectation
+        + resp.filler.as_ref().unwrap().expectation;
+    let observed_gold = total[&LootBoxRewardType::Gold] / runs as f64;
+    assert!((observed_gold - gold).abs() / gold < 0.02);
+
+    let observed_ship = has_ship as f64 / runs as f64;
+    let ship_prob = resp.get(&ship(11)).unwrap().at_least_one;
+    assert!((observed_ship - ship_prob).abs() < 0.02);
+}
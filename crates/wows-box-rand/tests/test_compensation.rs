@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use common::{fixture, ship, single_reward_box};
+use rand::{rngs::SmallRng, SeedableRng};
+use wows_box::lootbox::LootBoxRewardType;
+use wows_box_rand::{
+    compensation::{compensate, compensations, received, CompensationTable},
+    rand::{rand_multi_log, RandResult},
+};
+
+mod common;
+
+#[test]
+fn test_compensate_duplicates() {
+    // Level 8 ship from a regular list, so it drops on every opening.
+    let lootbox = single_reward_box(1, 1, ship(5));
+    let table = CompensationTable::default();
+    let mut rng = SmallRng::seed_from_u64(0);
+
+    let mut log = rand_multi_log(&mut rng, &lootbox, 3, &[], 0).unwrap();
+    let owned = compensate(&mut log, &table, &[]);
+    assert_eq!(owned, [5]);
+    assert_eq!(log[0].slots[0].compensation, None);
+    let expected = Some(RandResult::new(5000, LootBoxRewardType::Gold, false));
+    assert_eq!(log[1].slots[0].compensation, expected);
+    assert_eq!(log[2].slots[0].compensation, expected);
+
+    let map = compensations(&log);
+    assert_eq!(
+        map[&(ship(5), false)],
+        RandResult::new(10000, LootBoxRewardType::Gold, false)
+    );
+    // Valued as the ship once and the compensation twice.
+    assert_eq!(
+        received(&log),
+        HashMap::from([
+            ((ship(5), false), 1),
+            ((LootBoxRewardType::Gold, false), 10000)
+        ])
+    );
+
+    // Already owned in game, every drop is compensated.
+    let mut log = rand_multi_log(&mut rng, &lootbox, 3, &[], 0).unwrap();
+    compensate(&mut log, &table, &[5]);
+    assert!(log.iter().all(|t| t.slots[0].compensation.is_some()));
+}
+
+#[test]
+fn test_compensate_skips_other_rewards() {
+    let lootbox = fixture();
+    let mut rng = SmallRng::seed_from_u64(1);
+    let mut log = rand_multi_log(&mut rng, &lootbox, 100, &[], 0).unwrap();
+
+    // Unique lists never drop an owned ship twice.
+    compensate(&mut log, &CompensationTable::default(), &[]);
+    assert!(compensations(&log).is_empty());
+}
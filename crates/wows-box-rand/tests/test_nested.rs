@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use common::{fixture, lootbox_reward, ship, single_reward_box};
+use rand::{rngs::SmallRng, SeedableRng};
+use wows_box::lootbox::LootBoxRewardType;
+use wows_box_rand::{
+    compensation::{compensate, CompensationTable},
+    nested::{nested_box_ids, open_nested, MAX_NESTED_OPENINGS},
+    rand::{rand_multi_log, RandResult},
+};
+
+mod common;
+
+#[test]
+fn test_open_nested() {
+    // 100 -> 2 × 101 -> 3 × 1000 credits each
+    let outer = single_reward_box(100, 2, lootbox_reward(101));
+    let inner = single_reward_box(101, 1000, LootBoxRewardType::Credits);
+    assert_eq!(nested_box_ids(&outer), [101]);
+
+    let boxes = HashMap::from([(101, inner)]);
+    let table = CompensationTable::default();
+    let mut rng = SmallRng::seed_from_u64(0);
+    let log = rand_multi_log(&mut rng, &outer, 3, &[], 0).unwrap();
+    let opened = open_nested(&mut rng, &outer, &log, &boxes, &table, &[], 3);
+
+    assert_eq!(opened.unopened, 0);
+    assert_eq!(
+        opened.totals,
+        HashMap::from([((LootBoxRewardType::Credits, false), 6000)])
+    );
+
+    // Nothing is opened without depth.
+    let opened = open_nested(&mut rng, &outer, &log, &boxes, &table, &[], 0);
+    assert_eq!(opened.unopened, 6);
+    assert_eq!(
+        opened.totals,
+        HashMap::from([((lootbox_reward(101), false), 6)])
+    );
+}
+
+#[test]
+fn test_open_nested_cycle_and_depth() {
+    // 200 -> 201 -> 200 -> ...
+    let first = single_reward_box(200, 1, lootbox_reward(201));
+    let second = single_reward_box(201, 1, lootbox_reward(200));
+    let boxes = HashMap::from([(200, first.clone()), (201, second)]);
+    let table = CompensationTable::default();
+    let mut rng = SmallRng::seed_from_u64(0);
+    let log = rand_multi_log(&mut rng, &first, 1, &[], 0).unwrap();
+
+    let opened = open_nested(&mut rng, &first, &log, &boxes, &table, &[], 10);
+    assert_eq!(opened.unopened, 1);
+    assert_eq!(
+        opened.totals,
+        HashMap::from([((lootbox_reward(200), false), 1)])
+    );
+
+    // 300 -> 301 -> 302 -> 303, stopping after two levels.
+    let boxes: HashMap<_, _> = (301..=303)
+        .map(|id| (id, single_reward_box(id, 1, lootbox_reward(id + 1))))
+        .collect();
+    let outer = single_reward_box(300, 1, lootbox_reward(301));
+    let log = rand_multi_log(&mut rng, &outer, 1, &[], 0).unwrap();
+    let opened = open_nested(&mut rng, &outer, &log, &boxes, &table, &[], 2);
+    assert_eq!(
+        opened.totals,
+        HashMap::from([((lootbox_reward(303), false), 1)])
+    );
+}
+
+#[test]
+fn test_open_nested_unique_rewards_per_box() {
+    // Every ship of the inner box is dropped once at most, across all openings.
+    let outer = single_reward_box(100, 30, lootbox_reward(1));
+    let boxes = HashMap::from([(1, fixture())]);
+    let table = CompensationTable::default();
+    let mut rng = SmallRng::seed_from_u64(1);
+    let log = rand_multi_log(&mut rng, &outer, 1, &[], 0).unwrap();
+    let opened = open_nested(&mut rng, &outer, &log, &boxes, &table, &[], 3);
+
+    // 30 openings give 3 guarantees, enough for both ships.
+    let ships: Vec<_> = opened
+        .totals
+        .iter()
+        .filter(|((t, _), _)| matches!(t, LootBoxRewardType::Ship { .. }))
+        .collect();
+    assert_eq!(ships.len(), 2);
+    assert!(ships.iter().all(|(_, &amount)| amount == 1));
+    assert!(opened.totals.contains_key(&(LootBoxRewardType::Gold, true)));
+}
+
+#[test]
+fn test_open_nested_compensates() {
+    // 100 -> 2 × 101 -> 1 × ship 5 each, owned after the first one.
+    let outer = single_reward_box(100, 2, lootbox_reward(101));
+    let inner = single_reward_box(101, 1, ship(5));
+    let boxes = HashMap::from([(101, inner)]);
+    let table = CompensationTable::default();
+    let mut rng = SmallRng::seed_from_u64(0);
+    let mut log = rand_multi_log(&mut rng, &outer, 1, &[], 0).unwrap();
+    let owned = compensate(&mut log, &table, &[]);
+    let opened = open_nested(&mut rng, &outer, &log, &boxes, &table, &owned, 3);
+
+    assert_eq!(opened.totals[&(ship(5), false)], 2);
+    assert_eq!(
+        opened.compensations[&(ship(5), false)],
+        RandResult::new(5000, LootBoxRewardType::Gold, false)
+    );
+
+    // Owned in game, both are compensated.
+    let opened = open_nested(&mut rng, &outer, &log, &boxes, &table, &[5], 3);
+    assert_eq!(opened.compensations[&(ship(5), false)].amount, 10000);
+    assert_eq!(
+        opened.received,
+        HashMap::from([((LootBoxRewardType::Gold, false), 10000)])
+    );
+}
+
+#[test]
+fn test_open_nested_broken_and_bounded() {
+    // A broken inner box stays closed, the others are still opened.
+    let mut broken = single_reward_box(401, 1, LootBoxRewardType::Credits);
+    broken.slots[0].common[0].probability = 0.0;
+    let outer = single_reward_box(400, 1, lootbox_reward(401));
+    let boxes = HashMap::from([(401, broken)]);
+    let table = CompensationTable::default();
+    let mut rng = SmallRng::seed_from_u64(0);
+    let log = rand_multi_log(&mut rng, &outer, 2, &[], 0).unwrap();
+    let opened = open_nested(&mut rng, &outer, &log, &boxes, &table, &[], 3);
+    assert_eq!(opened.unopened, 2);
+
+    // Amounts multiply with every level, the budget stops them.
+    let outer = single_reward_box(500, 1000, lootbox_reward(501));
+    let inner = single_reward_box(501, 1000, LootBoxRewardType::Credits);
+    let boxes = HashMap::from([(501, inner)]);
+    let log = rand_multi_log(&mut rng, &outer, 200, &[], 0).unwrap();
+    let opened = open_nested(&mut rng, &outer, &log, &boxes, &table, &[], 3);
+    assert_eq!(opened.unopened, 200 * 1000 - MAX_NESTED_OPENINGS);
+}
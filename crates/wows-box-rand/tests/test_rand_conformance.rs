@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use common::{assert_chi_square, conformance_fixture, ship};
+use rand::{rngs::SmallRng, SeedableRng};
+use wows_box::lootbox::LootBoxRewardType;
+use wows_box_rand::rand::{rand_multi_log, rand_single_log, rand_unique_log};
+
+mod common;
+
+const DRAWS: u64 = 1_000_000;
+
+#[test]
+fn test_single_list_frequency() {
+    let lootbox = conformance_fixture();
+    let mut rng = SmallRng::seed_from_u64(1);
+
+    let mut lists: HashMap<String, u64> = HashMap::new();
+    for _ in 0..DRAWS {
+        let resp = rand_single_log(&mut rng, &lootbox, &[]).unwrap();
+        *lists.entry(resp[0].list_name.clone()).or_default() += 1;
+    }
+
+    let slot = &lootbox.slots[0];
+    let (observed, probs): (Vec<_>, Vec<_>) = slot
+        .common
+        .iter()
+        .chain(slot.valuable.iter())
+        .map(|t| (lists.get(&t.name).copied().unwrap_or(0), t.probability))
+        .unzip();
+    assert_chi_square(&observed, &probs);
+}
+
+#[test]
+fn test_single_reward_frequency() {
+    let lootbox = conformance_fixture();
+    let mut rng = SmallRng::seed_from_u64(2);
+
+    let mut rewards: HashMap<(usize, LootBoxRewardType), u64> = HashMap::new();
+    for _ in 0..DRAWS {
+        for record in rand_single_log(&mut rng, &lootbox, &[]).unwrap() {
+            *rewards
+                .entry((record.slot_index, record.reward.reward_type))
+                .or_default() += 1;
+        }
+    }
+
+    for (idx, slot) in lootbox.slots.iter().enumerate() {
+        let (observed, probs): (Vec<_>, Vec<_>) = slot
+            .common
+            .iter()
+            .chain(slot.valuable.iter())
+            .flat_map(|t| t.rewards.iter())
+            .map(|t| {
+                let count = rewards.get(&(idx, t.reward.clone())).copied();
+                (count.unwrap_or(0), t.probability)
+            })
+            .unzip();
+        assert_chi_square(&observed, &probs);
+    }
+}
+
+#[test]
+fn test_unique_frequency() {
+    let lootbox = conformance_fixture();
+    let mut rng = SmallRng::seed_from_u64(3);
+
+    let mut lists: HashMap<String, u64> = HashMap::new();
+    let mut ships: HashMap<LootBoxRewardType, u64> = HashMap::new();
+    for _ in 0..DRAWS {
+        let resp = rand_unique_log(&mut rng, &lootbox, &[]).unwrap();
+        assert!(!resp[0].is_filler);
+        assert!(resp[0].reward.is_unique);
+        *lists.entry(resp[0].list_name.clone()).or_default() += 1;
+        *ships.entry(resp[0].reward.reward_type.clone()).or_default() += 1;
+        // Slots without unique lists are drawn as usual.
+        assert_eq!(resp[1].list_name, "C");
+    }
+
+    // Unique lists are weighted by their own probability.
+    assert_chi_square(&[lists["U1"], lists["U2"]], &[0.06, 0.04]);
+    // Rewards in a unique list are equally likely.
+    assert_chi_square(
+        &[ships[&ship(1)], ships[&ship(2)], ships[&ship(3)]],
+        &[1.0; 3],
+    );
+    assert_chi_square(&[ships[&ship(4)], ships[&ship(5)]], &[1.0; 2]);
+}
+
+#[test]
+fn test_unique_skips_owned() {
+    let lootbox = conformance_fixture();
+    let mut rng = SmallRng::seed_from_u64(4);
+
+    let mut ships: HashMap<LootBoxRewardType, u64> = HashMap::new();
+    for _ in 0..DRAWS / 10 {
+        let resp = rand_unique_log(&mut rng, &lootbox, &[2, 4]).unwrap();
+        *ships.entry(resp[0].reward.reward_type.clone()).or_default() += 1;
+    }
+
+    assert!(!ships.contains_key(&ship(2)));
+    assert!(!ships.contains_key(&ship(4)));
+    assert_chi_square(&[ships[&ship(1)], ships[&ship(3)]], &[1.0; 2]);
+}
+
+#[test]
+fn test_exhausted_unique_gives_filler() {
+    let lootbox = conformance_fixture();
+    let mut rng = SmallRng::seed_from_u64(5);
+    let owned = [1, 2, 3, 4, 5];
+
+    let mut filler = 0;
+    for _ in 0..DRAWS / 10 {
+        let resp = rand_single_log(&mut rng, &lootbox, &owned).unwrap();
+        let record = &resp[0];
+        let from_unique = record.list_name == "U1" || record.list_name == "U2";
+        assert_eq!(record.is_filler, from_unique);
+        if from_unique {
+            assert_eq!(record.reward.reward_type, LootBoxRewardType::Gold);
+            assert_eq!(record.reward.amount, 1000);
+            filler += 1;
+        }
+
+        let resp = rand_unique_log(&mut rng, &lootbox, &owned).unwrap();
+        assert!(resp[0].is_filler);
+    }
+
+    // Unique lists are hit 10% of the time.
+    assert_chi_square(&[filler, DRAWS / 10 - filler], &[0.1, 0.9]);
+}
+
+#[test]
+fn test_guarantee_fires_on_save_point() {
+    let lootbox = conformance_fixture();
+    let save_point = lootbox.save_point.unwrap();
+    let mut rng = SmallRng::seed_from_u64(6);
+
+    for current_try in 0..save_point {
+        let log = rand_multi_log(&mut rng, &lootbox, 50, &[], current_try).unwrap();
+        let guaranteed: Vec<_> = log
+            .iter()
+            .filter(|t| t.is_guaranteed)
+            .map(|t| t.index)
+            .collect();
+        let expected: Vec<_> = (save_point - current_try..=50)
+            .step_by(save_point as usize)
+            .collect();
+        assert_eq!(guaranteed, expected, "starting from {current_try}");
+    }
+
+    // A counter already past the save point fires on the next opening.
+    let log = rand_multi_log(&mut rng, &lootbox, 1, &[], save_point + 3).unwrap();
+    assert!(log[0].is_guaranteed);
+}
+
+#[test]
+fn test_multi_never_repeats_unique() {
+    let lootbox = conformance_fixture();
+    let mut rng = SmallRng::seed_from_u64(7);
+
+    for _ in 0..1000 {
+        let log = rand_multi_log(&mut rng, &lootbox, 25, &[], 0).unwrap();
+        for opening in log.iter().filter(|t| t.is_guaranteed) {
+            let list = &opening.slots[0].list_name;
+            assert!(list == "U1" || list == "U2", "guaranteed from {list}");
+        }
+
+        let mut dropped: Vec<_> = log
+            .iter()
+            .flat_map(|t| t.slots.iter())
+            .filter(|t| t.reward.is_unique && !t.is_filler)
+            .filter_map(|t| t.reward.reward_type.get_id())
+            .collect();
+        let total = dropped.len();
+        dropped.sort_unstable();
+        dropped.dedup();
+        assert_eq!(dropped.len(), total);
+    }
+}
@@ -0,0 +1,51 @@
+use common::fixture;
+use rand::{rngs::SmallRng, SeedableRng};
+use wows_box_rand::rand::{aggregate, rand_multi, rand_multi_log, state_after};
+
+mod common;
+
+#[test]
+fn test_rand_multi_log() {
+    let lootbox = fixture();
+
+    let mut rng = SmallRng::seed_from_u64(42);
+    let log = rand_multi_log(&mut rng, &lootbox, 35, &[], 4).unwrap();
+
+    assert_eq!(log.len(), 35);
+    for (idx, opening) in log.iter().enumerate() {
+        assert_eq!(opening.index, idx as u32 + 1);
+        assert_eq!(opening.slots.len(), lootbox.slots.len());
+        assert_eq!(opening.is_guaranteed, opening.pity_before + 1 >= 10);
+        if opening.is_guaranteed {
+            assert_eq!(opening.pity_after, 0);
+        } else {
+            assert_eq!(opening.pity_after, opening.pity_before + 1);
+        }
+    }
+    // Guarantees at 6, 16 and 26 give both ships, the last one the filler.
+    let guaranteed: Vec<_> = log.iter().filter(|t| t.is_guaranteed).collect();
+    assert_eq!(
+        guaranteed.iter().map(|t| t.index).collect::<Vec<_>>(),
+        [6, 16, 26]
+    );
+    assert!(guaranteed[2].slots[0].is_filler);
+
+    let mut rng = SmallRng::seed_from_u64(42);
+    let map = rand_multi(&mut rng, &lootbox, 35, &[], 4).unwrap();
+    assert_eq!(aggregate(&log), map);
+}
+
+#[test]
+fn test_state_after() {
+    let lootbox = fixture();
+
+    let mut rng = SmallRng::seed_from_u64(7);
+    let log = rand_multi_log(&mut rng, &lootbox, 12, &[11], 3).unwrap();
+    let (owned, current_try) = state_after(&log, &[11], 3);
+
+    // The guarantee at opening 7 drops the only ship left.
+    assert_eq!(owned, [11, 12]);
+    assert_eq!(current_try, 5);
+
+    assert_eq!(state_after(&[], &[11], 3), (vec![11], 3));
+}
@@ -28,7 +28,7 @@ async fn test_rand_multi() -> anyhow::Result<()> {
 
     let mut rng = SmallRng::from_entropy();
 
-    let resp = rand_multi(&mut rng, &lootbox, 100, &found, 0);
+    let resp = rand_multi(&mut rng, &lootbox, 100, &found, 0)?;
 
     println!("{:#?}", resp);
 
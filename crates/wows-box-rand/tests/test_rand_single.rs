@@ -24,14 +24,14 @@ async fn test_rand_single() -> anyhow::Result<()> {
     let col: Collection<LootBox> = client.database("wowslootbox-zh-sg").collection("list");
     let lootbox = col.find_one(doc! { "id": 4288861104_u32 }).await?.unwrap();
 
-    let found = vec![];
+    let found = [];
 
     let mut map = HashMap::new();
 
     let mut rng = SmallRng::from_entropy();
 
     for _ in 0..200 {
-        let resp = rand_single(&mut rng, &lootbox, &found[..]);
+        let resp = rand_single(&mut rng, &lootbox, &found[..])?;
         for r in resp {
             *map.entry(r.reward_type).or_insert(0) += r.amount;
         }
@@ -0,0 +1,34 @@
+use common::{fixture, ship};
+use rand::{rngs::SmallRng, SeedableRng};
+use wows_box_rand::rand::{rand_until_distribution, worst_case_until};
+
+mod common;
+
+#[test]
+fn test_worst_case_until() {
+    let lootbox = fixture();
+    let target = |t: &_| *t == ship(11);
+
+    // Guarantees at 10 and 20 hand out both ships.
+    assert_eq!(worst_case_until(&lootbox, target, &[], 0), Some(20));
+    assert_eq!(worst_case_until(&lootbox, target, &[], 7), Some(13));
+    assert_eq!(worst_case_until(&lootbox, target, &[12], 0), Some(10));
+}
+
+#[test]
+fn test_rand_until_distribution() {
+    let lootbox = fixture();
+    let mut rng = SmallRng::seed_from_u64(0x5eed);
+    let dist = rand_until_distribution(&mut rng, &lootbox, |t| *t == ship(11), &[], 0, 5000, 1000)
+        .unwrap();
+
+    assert_eq!(dist.failed, 0);
+    assert_eq!(dist.openings.len(), 5000);
+    assert!(dist.openings.iter().all(|&t| (1..=20).contains(&t)));
+    assert!(dist.median().unwrap() <= dist.percentile(0.9).unwrap());
+    assert_eq!(dist.percentile(1.0), dist.openings.last().copied());
+
+    let histogram = dist.histogram(5);
+    assert!(histogram.len() <= 5);
+    assert_eq!(histogram.iter().map(|t| t.2).sum::<u32>(), 5000);
+}
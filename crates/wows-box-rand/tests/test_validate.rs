@@ -0,0 +1,87 @@
+use common::{fixture, reward, reward_list};
+use rand::{rngs::SmallRng, SeedableRng};
+use wows_box::lootbox::LootBoxRewardType;
+use wows_box_rand::{
+    error::RandError,
+    rand::{rand_multi_log, rand_unique, validate},
+};
+
+mod common;
+
+#[test]
+fn test_validate_fixture() {
+    assert_eq!(validate(&fixture()), Ok(()));
+}
+
+#[test]
+fn test_validate_rejects_broken_data() {
+    let mut lootbox = fixture();
+    lootbox.slots[0].common[0].rewards[0].probability = 0.4;
+    assert!(matches!(
+        validate(&lootbox),
+        Err(RandError::ListProbability { slot: 0, .. })
+    ));
+
+    let mut lootbox = fixture();
+    lootbox.slots[0].common[0].probability = 0.7;
+    lootbox.slots[0].common[0].rewards[0].probability = 0.4;
+    assert!(matches!(
+        validate(&lootbox),
+        Err(RandError::SlotProbability { slot: 0, .. })
+    ));
+
+    let mut lootbox = fixture();
+    lootbox.slots[0].common[0].rewards.clear();
+    assert!(matches!(
+        validate(&lootbox),
+        Err(RandError::EmptyList { slot: 0, .. })
+    ));
+
+    let mut lootbox = fixture();
+    lootbox.slots[0].common.clear();
+    lootbox.slots[0].valuable.clear();
+    assert_eq!(validate(&lootbox), Err(RandError::EmptySlot { slot: 0 }));
+
+    let mut lootbox = fixture();
+    lootbox.slots[0].common[0].rewards[0].probability = f64::NAN;
+    assert!(matches!(
+        validate(&lootbox),
+        Err(RandError::InvalidProbability { slot: 0, .. })
+    ));
+
+    let mut lootbox = fixture();
+    lootbox.filler = None;
+    assert!(matches!(
+        validate(&lootbox),
+        Err(RandError::MissingFiller { slot: 0, .. })
+    ));
+}
+
+#[test]
+fn test_broken_data_does_not_panic() {
+    let mut rng = SmallRng::seed_from_u64(0);
+
+    let mut lootbox = fixture();
+    lootbox.filler = None;
+    assert!(rand_multi_log(&mut rng, &lootbox, 100, &[], 0).is_err());
+    // Sampling without validation reports the exhausted list.
+    assert!(matches!(
+        rand_unique(&mut rng, &lootbox, &[11, 12]),
+        Err(RandError::Exhausted { slot: 0, .. })
+    ));
+
+    // Rounding errors within the tolerance are accepted.
+    let mut lootbox = fixture();
+    lootbox.slots[0].common = vec![reward_list(
+        0.895,
+        vec![
+            reward(0.6, 1000, LootBoxRewardType::Credits),
+            reward(0.295, 100, LootBoxRewardType::Gold),
+        ],
+        false,
+    )];
+    assert_eq!(validate(&lootbox), Ok(()));
+    for _ in 0..10000 {
+        rand_multi_log(&mut rng, &lootbox, 10, &[], 0).unwrap();
+    }
+}
@@ -0,0 +1,161 @@
+//! Expiry of the rendered files in `CACHE_DIR`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use log::{debug, warn};
+use uuid::Uuid;
+
+/// Files written by a render are named with this, anything else in the
+/// directory is left alone.
+pub const RENDER_PREFIX: &str = "render-";
+
+/// A new path in `CACHE_DIR` for a rendered image, managed by
+/// [`CacheManager`].
+pub fn render_path() -> anyhow::Result<String> {
+    Ok(format!(
+        "{}/{RENDER_PREFIX}{}.png",
+        env::var("CACHE_DIR")?,
+        Uuid::new_v4()
+    ))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheOptions {
+    /// Files are removed this long after they are written.
+    pub ttl: Duration,
+    /// The oldest files are removed while the total size is over this, in
+    /// bytes.
+    pub max_size: u64,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        CacheOptions {
+            ttl: Duration::from_secs(3600),
+            max_size: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+impl CacheOptions {
+    /// Read `CACHE_TTL` (in seconds) and `CACHE_MAX_SIZE` (in megabytes), with
+    /// the defaults for unset ones.
+    pub fn from_env() -> Self {
+        let var = |key: &str| env::var(key).ok().and_then(|t| t.parse::<u64>().ok());
+        let default = CacheOptions::default();
+        CacheOptions {
+            ttl: var("CACHE_TTL").map_or(default.ttl, Duration::from_secs),
+            max_size: var("CACHE_MAX_SIZE").map_or(default.max_size, |t| t * 1024 * 1024),
+        }
+    }
+}
+
+/// A file written by a render.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// What a [`CacheManager::sweep`] removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SweepReport {
+    pub removed: usize,
+    pub freed: u64,
+    /// Total size of the files kept.
+    pub kept: u64,
+}
+
+/// Removes rendered files once they are no longer needed.
+///
+/// Only files directly in the directory and named by [`render_path`] are
+/// managed, subdirectories such as the icon cache and files of other tools are
+/// left alone.
+#[derive(Debug, Clone)]
+pub struct CacheManager {
+    dir: PathBuf,
+    options: CacheOptions,
+}
+
+impl CacheManager {
+    pub fn new(dir: impl AsRef<Path>, options: CacheOptions) -> Self {
+        CacheManager {
+            dir: dir.as_ref().to_path_buf(),
+            options,
+        }
+    }
+
+    pub fn options(&self) -> &CacheOptions {
+        &self.options
+    }
+
+    /// Rendered files in the directory, oldest first.
+    pub fn files(&self) -> anyhow::Result<Vec<CachedFile>> {
+        let mut files = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if !entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(RENDER_PREFIX)
+            {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            files.push(CachedFile {
+                path: entry.path(),
+                size: metadata.len(),
+                modified: metadata.modified()?,
+            });
+        }
+        files.sort_by_key(|t| t.modified);
+        Ok(files)
+    }
+
+    /// Files to remove at `now`: the expired ones, then the oldest ones until
+    /// the rest fits in the size budget.
+    pub fn expired(&self, files: Vec<CachedFile>, now: SystemTime) -> Vec<CachedFile> {
+        let mut total: u64 = files.iter().map(|t| t.size).sum();
+        let mut expired = vec![];
+        for file in files {
+            let age = now.duration_since(file.modified).unwrap_or_default();
+            if age < self.options.ttl && total <= self.options.max_size {
+                break;
+            }
+            total -= file.size;
+            expired.push(file);
+        }
+        expired
+    }
+
+    /// Remove the files of [`CacheManager::expired`].
+    pub fn sweep(&self) -> anyhow::Result<SweepReport> {
+        let files = self.files()?;
+        let total: u64 = files.iter().map(|t| t.size).sum();
+        let mut report = SweepReport {
+            kept: total,
+            ..Default::default()
+        };
+        for file in self.expired(files, SystemTime::now()) {
+            // Someone else may have removed it already.
+            match fs::remove_file(&file.path) {
+                Ok(()) => {
+                    report.removed += 1;
+                    report.freed += file.size;
+                    report.kept -= file.size;
+                }
+                Err(e) => warn!("Cannot remove {}: {e}", file.path.display()),
+            }
+        }
+        debug!("Cache sweep: {report:?}");
+        Ok(report)
+    }
+}
@@ -0,0 +1,225 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::anyhow;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use wows_box::{
+    item::ItemData,
+    lootbox::{LootBox, LootBoxRewardType},
+    store::LootboxStore,
+    valuation::ValuationTable,
+};
+use wows_box_rand::{
+    analytic::{expect_multi, LootBoxExpectation},
+    rand::validate,
+};
+
+use crate::{
+    cache::render_path,
+    cost::fetch_items,
+    icon::{IconCache, ICON_CACHE},
+    process::{precedence_key, reward_to_imgs},
+    renderer::{Renderer, View, RENDERER},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompareProp {
+    pub times: u32,
+    pub times_text: &'static str,
+    pub boxes: Vec<CompareBoxProp>,
+    /// Expected total of every currency, ordered like the lootbox list.
+    pub currencies: Vec<CompareRowProp>,
+    pub stats: Vec<CompareRowProp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompareBoxProp {
+    pub icon: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompareRowProp {
+    pub icons: Vec<String>,
+    pub label: String,
+    /// One for each lootbox.
+    pub values: Vec<String>,
+}
+
+fn times_text(lang: &str) -> &'static str {
+    match lang {
+        "zh-sg" => "开箱次数",
+        _ => "Openings",
+    }
+}
+
+/// Labels: expected unique items, expected ships, chance of any unique item,
+/// value per opening.
+fn compare_labels(lang: &str) -> [&'static str; 4] {
+    match lang {
+        "zh-sg" => ["独特物品期望", "舰船期望", "获得独特物品概率", "每箱价值"],
+        _ => [
+            "Expected unique items",
+            "Expected ships",
+            "Chance of a unique item",
+            "Value per opening",
+        ],
+    }
+}
+
+/// Expected total of every currency, including the filler.
+pub fn expected_currencies(expectation: &LootBoxExpectation) -> HashMap<LootBoxRewardType, f64> {
+    let mut map = HashMap::new();
+    for reward in expectation.rewards.iter().chain(expectation.filler.iter()) {
+        if reward.reward_type.as_currency().is_some() {
+            *map.entry(reward.reward_type.clone()).or_default() += reward.expectation;
+        }
+    }
+    map
+}
+
+/// Rows other than currencies, one value for each expectation.
+pub fn compare_stats(
+    lang: &str,
+    expectations: &[LootBoxExpectation],
+    valuation: &ValuationTable,
+    items: &HashMap<u64, ItemData>,
+) -> Vec<CompareRowProp> {
+    let [uniques, ships, chance, value] = compare_labels(lang);
+    let row = |label: &str, f: &dyn Fn(&LootBoxExpectation) -> String| CompareRowProp {
+        icons: vec![],
+        label: label.to_owned(),
+        values: expectations.iter().map(f).collect(),
+    };
+    let expected_uniques = |t: &LootBoxExpectation| -> f64 {
+        t.rewards
+            .iter()
+            .filter(|t| t.is_unique)
+            .fold(0.0, |sum, t| sum + t.expectation)
+    };
+    let per_opening = |t: &LootBoxExpectation, x: f64| x / t.times.max(1) as f64;
+
+    vec![
+        row(uniques, &|t| format!("{:.2}", expected_uniques(t))),
+        row(ships, &|t| {
+            let ships: f64 = t
+                .rewards
+                .iter()
+                .filter(|t| matches!(t.reward_type, LootBoxRewardType::Ship { .. }))
+                .fold(0.0, |sum, t| sum + t.expectation);
+            format!("{ships:.2}")
+        }),
+        row(chance, &|t| {
+            format!("{:.2}%", t.unique_at_least_one * 100.0)
+        }),
+        row(value, &|t| {
+            let (value, unvalued) = t.value(valuation, items);
+            let mark = if unvalued > 0 { "*" } else { "" };
+            format!("{:.1}{mark}", per_opening(t, value))
+        }),
+    ]
+}
+
+impl CompareProp {
+    /// Compare `boxes` by the analytic expectation of `times` openings.
+    pub async fn from_boxes(
+        lang: &str,
+        store: &impl LootboxStore,
+        boxes: &[LootBox],
+        times: u32,
+        valuation: &ValuationTable,
+    ) -> anyhow::Result<Self> {
+        let owned = boxes.to_vec();
+        let expectations = tokio::task::spawn_blocking(move || {
+            owned
+                .iter()
+                .map(|t| expect_multi(t, times, &[], 0))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .await??;
+
+        let ids: Vec<_> = expectations
+            .iter()
+            .flat_map(|t| t.rewards.iter())
+            .filter_map(|t| t.reward_type.get_id())
+            .collect();
+        let items = fetch_items(lang, store, ids).await?;
+        let stats = compare_stats(lang, &expectations, &valuation.for_lang(lang), &items);
+
+        debug!("Fetching currency data...");
+        let amounts: Vec<_> = expectations.iter().map(expected_currencies).collect();
+        let mut rewards: Vec<_> = amounts.iter().flat_map(|t| t.keys()).collect();
+        rewards.sort_by_key(|t| precedence_key(t.as_precedence()));
+        rewards.dedup();
+
+        let mut currencies = vec![];
+        for reward in rewards {
+            let (label, icons) = reward_to_imgs(lang, store, reward.clone()).await?;
+            let values = amounts
+                .iter()
+                .map(|t| match t.get(reward) {
+                    Some(amount) => format!("{amount:.0}"),
+                    None => "-".to_owned(),
+                })
+                .collect();
+            currencies.push(CompareRowProp {
+                icons,
+                label,
+                values,
+            });
+        }
+
+        Ok(CompareProp {
+            times,
+            times_text: times_text(lang),
+            boxes: boxes
+                .iter()
+                .map(|t| CompareBoxProp {
+                    icon: t.icon.clone(),
+                    title: t.name.clone(),
+                })
+                .collect(),
+            currencies,
+            stats,
+        })
+    }
+
+    /// Point every icon to its copy in `cache`.
+    pub async fn localize_icons(&mut self, cache: &IconCache) {
+        let urls = self
+            .boxes
+            .iter_mut()
+            .map(|t| &mut t.icon)
+            .chain(self.currencies.iter_mut().flat_map(|t| t.icons.iter_mut()))
+            .collect();
+        cache.localize(urls).await;
+    }
+}
+
+pub async fn render_compare_to_file(
+    lang: &str,
+    store: &impl LootboxStore,
+    keys: &[u64],
+    times: u32,
+    valuation: &ValuationTable,
+) -> anyhow::Result<String> {
+    let mut boxes = vec![];
+    for key in keys {
+        let lootbox = store
+            .get_box(lang, *key)
+            .await?
+            .ok_or(anyhow!("Cannot find lootbox {}", key))?;
+        validate(&lootbox)?;
+        boxes.push(lootbox);
+    }
+
+    let mut prop = CompareProp::from_boxes(lang, store, &boxes, times, valuation).await?;
+    prop.localize_icons(&ICON_CACHE).await;
+
+    let file_path = render_path()?;
+    RENDERER
+        .render(View::Compare(&prop), None, Path::new(&file_path))
+        .await?;
+
+    Ok(file_path)
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use wows_box::{
+    currencies::CurrencyType,
+    item::ItemData,
+    price::{CostReport, PriceData},
+    store::LootboxStore,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CostLineProp {
+    pub label: String,
+    pub value: String,
+}
+
+/// Labels: spent, reward value, value ratio, unvalued rewards.
+fn cost_labels(lang: &str) -> [&'static str; 4] {
+    match lang {
+        "zh-sg" => ["花费", "奖励价值", "回报率", "未计价物品"],
+        _ => ["Spent", "Reward value", "Value ratio", "Unvalued rewards"],
+    }
+}
+
+/// Footer lines of the lootbox render, empty if nothing is known.
+pub fn cost_lines(lang: &str, report: &CostReport) -> Vec<CostLineProp> {
+    let [spent, value, ratio, unvalued] = cost_labels(lang);
+    let gold = CurrencyType::Gold.as_name_string(lang);
+
+    let spent_text = report
+        .gold
+        .map(|t| format!("{t} {gold}"))
+        .into_iter()
+        .chain(
+            report
+                .real
+                .iter()
+                .map(|t| format!("{:.2} {} ({})", t.price, t.currency, t.region)),
+        )
+        .collect::<Vec<_>>()
+        .join(" / ");
+    if spent_text.is_empty() {
+        return vec![];
+    }
+
+    let mut lines = vec![
+        CostLineProp {
+            label: spent.to_owned(),
+            value: spent_text,
+        },
+        CostLineProp {
+            label: value.to_owned(),
+            value: format!("{:.0} {gold}", report.value),
+        },
+    ];
+    if let Some(t) = report.ratio() {
+        lines.push(CostLineProp {
+            label: ratio.to_owned(),
+            value: format!("{:.1}%", t * 100.0),
+        });
+    }
+    if report.unvalued > 0 {
+        lines.push(CostLineProp {
+            label: unvalued.to_owned(),
+            value: report.unvalued.to_string(),
+        });
+    }
+    lines
+}
+
+pub async fn fetch_price(
+    lang: &str,
+    store: &impl LootboxStore,
+    box_id: u64,
+) -> anyhow::Result<Option<PriceData>> {
+    store.get_price(lang, box_id).await
+}
+
+/// Item data of the rewards with these ids, for valuations by item name.
+pub async fn fetch_items(
+    lang: &str,
+    store: &impl LootboxStore,
+    ids: impl IntoIterator<Item = u64>,
+) -> anyhow::Result<HashMap<u64, ItemData>> {
+    let mut items = HashMap::new();
+    for id in ids {
+        if items.contains_key(&id) {
+            continue;
+        }
+        if let Some(item) = store.get_item(lang, id).await? {
+            items.insert(id, item);
+        }
+    }
+    Ok(items)
+}
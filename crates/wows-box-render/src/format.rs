@@ -0,0 +1,109 @@
+use std::{fs, io::BufWriter, path::Path};
+
+use image::{codecs::jpeg::JpegEncoder, ImageFormat};
+use serde::{Deserialize, Serialize};
+
+use crate::process::{LootBoxListProp, LootBoxListRewardProp};
+
+/// Quality of JPEG images, 1 ~ 100.
+pub const JPEG_QUALITY: u8 = 85;
+
+/// What a render produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    /// Lossless WebP, smaller than PNG.
+    Webp,
+    /// Lossy JPEG, smallest but without transparency.
+    Jpeg,
+    /// Plain text summary, for platforms that cannot show images.
+    Text,
+    /// The raw [`LootBoxListProp`], for frontends drawing their own UI.
+    Json,
+}
+
+impl OutputFormat {
+    pub fn is_image(self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Png | OutputFormat::Webp | OutputFormat::Jpeg
+        )
+    }
+}
+
+/// A rendered page, see [`OutputFormat`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum Rendered {
+    /// Path of the image file.
+    Image(String),
+    Text(String),
+    Json(Box<LootBoxListProp>),
+}
+
+/// Convert the PNG at `path` into `format`, returning the path of the new
+/// file. The PNG is removed if converted.
+pub fn encode_image(path: &str, format: OutputFormat) -> anyhow::Result<String> {
+    let extension = match format {
+        OutputFormat::Webp => "webp",
+        OutputFormat::Jpeg => "jpg",
+        _ => return Ok(path.to_owned()),
+    };
+    let image = image::open(path)?;
+    let target = Path::new(path).with_extension(extension);
+    let mut file = BufWriter::new(fs::File::create(&target)?);
+    match format {
+        OutputFormat::Jpeg => image
+            .into_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut file, JPEG_QUALITY))?,
+        _ => image.write_to(&mut file, ImageFormat::WebP)?,
+    }
+    fs::remove_file(path)?;
+
+    Ok(target.display().to_string())
+}
+
+fn reward_lines(rewards: &[LootBoxListRewardProp], guarantee_text: &str) -> Vec<String> {
+    rewards
+        .iter()
+        .map(|t| {
+            let mut line = format!("{} × {}", t.text, t.amount);
+            if t.is_guaranteed {
+                line.push_str(guarantee_text);
+            }
+            if let Some(compensation) = &t.compensation {
+                line = format!("{line} {compensation}");
+            }
+            line
+        })
+        .collect()
+}
+
+impl LootBoxListProp {
+    /// Plain text with the same contents as the image.
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!("{} × {}", self.box_title, self.times)];
+        match &self.opened_rewards {
+            Some(opened) => {
+                lines.push(format!("[{}]", self.raw_text));
+                lines.extend(reward_lines(&self.rewards, self.guarantee_text));
+                lines.push(format!("[{}]", self.opened_text));
+                lines.extend(reward_lines(opened, self.guarantee_text));
+            }
+            None => lines.extend(reward_lines(&self.rewards, self.guarantee_text)),
+        }
+        lines.extend(
+            self.cost
+                .iter()
+                .map(|t| format!("{}: {}", t.label, t.value)),
+        );
+        if let Some(seed) = &self.seed_code {
+            match &self.revision {
+                Some(revision) => lines.push(format!("#{seed} @{revision}")),
+                None => lines.push(format!("#{seed}")),
+            }
+        }
+        lines.join("\n")
+    }
+}
@@ -0,0 +1,169 @@
+//! Icons downloaded once and served from disk, so renders do not wait on the CDN.
+
+use std::{
+    collections::HashSet,
+    env,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use futures::{stream, StreamExt};
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use uuid::Uuid;
+use wows_box::store::LootboxStore;
+
+use crate::process::reward_to_imgs;
+
+/// Downloads taking longer are given up, the remote URL is kept instead.
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(10);
+/// Upper bound of icons downloaded at the same time.
+const CONCURRENCY: usize = 16;
+
+/// 64-bit FNV-1a, stable across builds unlike the std hasher.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Extension of the file `url` points to, if it looks like one.
+fn extension_of(url: &str) -> Option<&str> {
+    let path = url.split(['?', '#']).next()?;
+    let name = path.rsplit('/').next()?;
+    let (_, ext) = name.rsplit_once('.')?;
+    (!ext.is_empty() && ext.len() <= 5 && ext.chars().all(|t| t.is_ascii_alphanumeric()))
+        .then_some(ext)
+}
+
+fn is_remote(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// Remote icons stored in a directory, named by the hash of their URL.
+#[derive(Debug, Clone)]
+pub struct IconCache {
+    dir: PathBuf,
+    client: reqwest::Client,
+}
+
+impl IconCache {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        let dir = dir.as_ref();
+        IconCache {
+            // Pages are opened from elsewhere, `file://` URLs must be absolute.
+            dir: std::path::absolute(dir).unwrap_or_else(|_| dir.to_path_buf()),
+            client: reqwest::Client::builder()
+                .timeout(DOWNLOAD_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Where the icon at `url` is stored, whether downloaded or not.
+    pub fn path_of(&self, url: &str) -> PathBuf {
+        let name = format!("{:016x}", fnv1a(url.as_bytes()));
+        match extension_of(url) {
+            Some(ext) => self.dir.join(format!("{name}.{ext}")),
+            None => self.dir.join(name),
+        }
+    }
+
+    /// Download the icon at `url` unless it is stored already.
+    pub async fn fetch(&self, url: &str) -> anyhow::Result<PathBuf> {
+        let path = self.path_of(url);
+        if tokio::fs::try_exists(&path).await? {
+            return Ok(path);
+        }
+
+        debug!("Downloading icon {url}...");
+        let data = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        tokio::fs::create_dir_all(&self.dir).await?;
+        // Written aside first, so a broken download is never picked up.
+        let temp = self.dir.join(format!("{}.tmp", Uuid::new_v4()));
+        tokio::fs::write(&temp, &data).await?;
+        tokio::fs::rename(&temp, &path).await?;
+        Ok(path)
+    }
+
+    /// `file://` URL of the stored icon, or `url` itself if it is not remote
+    /// or cannot be downloaded.
+    pub async fn local_url(&self, url: &str) -> String {
+        if !is_remote(url) {
+            return url.to_owned();
+        }
+        match self.fetch(url).await {
+            Ok(path) => format!("file://{}", path.display()),
+            Err(e) => {
+                warn!("Cannot cache icon {url}: {e}");
+                url.to_owned()
+            }
+        }
+    }
+
+    /// Replace every URL of `urls` by [`IconCache::local_url`].
+    pub async fn localize(&self, urls: Vec<&mut String>) {
+        let tasks: Vec<_> = urls.iter().map(|t| self.local_url(t)).collect();
+        let locals: Vec<_> = stream::iter(tasks).buffered(CONCURRENCY).collect().await;
+        for (url, local) in urls.into_iter().zip(locals) {
+            *url = local;
+        }
+    }
+
+    /// Download the icons of every lootbox of `lang` and of their rewards,
+    /// returning how many icons are stored.
+    pub async fn prefetch(&self, lang: &str, store: &impl LootboxStore) -> anyhow::Result<usize> {
+        let mut rewards = HashSet::new();
+        let mut urls = HashSet::new();
+        for lootbox in store.list_boxes(lang).await? {
+            urls.insert(lootbox.icon.clone());
+            for slot in lootbox.slots.iter() {
+                for list in slot.common.iter().chain(slot.valuable.iter()) {
+                    rewards.extend(list.rewards.iter().map(|t| t.reward.clone()));
+                }
+            }
+            rewards.extend(lootbox.filler.map(|t| t.filler));
+        }
+
+        debug!("Resolving icons of {} rewards...", rewards.len());
+        for reward in rewards {
+            match reward_to_imgs(lang, store, reward.clone()).await {
+                Ok((_, icons)) => urls.extend(icons),
+                Err(e) => warn!("Cannot resolve icons of {reward:?}: {e}"),
+            }
+        }
+
+        let urls: Vec<_> = urls.into_iter().filter(|t| is_remote(t)).collect();
+        info!("Prefetching {} icons [lang {lang}]...", urls.len());
+        let tasks: Vec<_> = urls
+            .iter()
+            .map(|url| async move { (url, self.fetch(url).await) })
+            .collect();
+        let mut results = stream::iter(tasks).buffer_unordered(CONCURRENCY);
+        let mut stored = 0;
+        while let Some((url, res)) = results.next().await {
+            match res {
+                Ok(_) => stored += 1,
+                Err(e) => warn!("Cannot cache icon {url}: {e}"),
+            }
+        }
+        Ok(stored)
+    }
+}
+
+lazy_static! {
+    /// Stored under `$CACHE_DIR/icons`.
+    pub static ref ICON_CACHE: IconCache =
+        IconCache::new(Path::new(&env::var("CACHE_DIR").unwrap_or_else(|_| ".".to_owned())).join("icons"));
+}
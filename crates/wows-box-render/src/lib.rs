@@ -1,2 +1,16 @@
+pub mod cache;
+pub mod compare;
+pub mod cost;
+pub mod format;
+#[cfg(feature = "chrome")]
 pub mod html;
+pub mod icon;
+#[cfg(feature = "chrome")]
+pub mod pool;
 pub mod process;
+#[cfg(feature = "raster")]
+pub mod raster;
+pub mod renderer;
+pub mod sheet;
+pub mod theme;
+pub mod until;
@@ -0,0 +1,250 @@
+//! Browsers kept alive across renders.
+
+use std::{
+    env,
+    path::Path,
+    sync::{Condvar, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use headless_chrome::{protocol::cdp::Page, Browser, LaunchOptions};
+use lazy_static::lazy_static;
+use log::{debug, warn};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolOptions {
+    /// Browsers launched at most.
+    pub size: usize,
+    /// Limit of every wait in a single render.
+    pub render_timeout: Duration,
+    /// How long to wait for a browser when all of them are busy.
+    pub acquire_timeout: Duration,
+    /// An idle browser exits after this long, and is relaunched when needed.
+    pub idle_timeout: Duration,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        PoolOptions {
+            size: 2,
+            render_timeout: Duration::from_secs(20),
+            acquire_timeout: Duration::from_secs(60),
+            idle_timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+impl PoolOptions {
+    /// Read `BROWSER_POOL_SIZE`, `RENDER_TIMEOUT`, `BROWSER_ACQUIRE_TIMEOUT` and
+    /// `BROWSER_IDLE_TIMEOUT` (in seconds), with the defaults for unset ones.
+    pub fn from_env() -> Self {
+        let var = |key: &str| env::var(key).ok().and_then(|t| t.parse::<u64>().ok());
+        let default = PoolOptions::default();
+        PoolOptions {
+            size: var("BROWSER_POOL_SIZE").map_or(default.size, |t| t.max(1) as usize),
+            render_timeout: var("RENDER_TIMEOUT")
+                .map_or(default.render_timeout, Duration::from_secs),
+            acquire_timeout: var("BROWSER_ACQUIRE_TIMEOUT")
+                .map_or(default.acquire_timeout, Duration::from_secs),
+            idle_timeout: var("BROWSER_IDLE_TIMEOUT")
+                .map_or(default.idle_timeout, Duration::from_secs),
+        }
+    }
+}
+
+#[derive(Default)]
+struct PoolState {
+    idle: Vec<Browser>,
+    busy: usize,
+}
+
+/// A fixed number of headless Chrome instances shared by all renders.
+///
+/// Every render takes an idle browser, or launches one while under the size,
+/// and opens its own tab. Browsers that stop responding are dropped, which
+/// kills their process, and are relaunched on demand.
+pub struct BrowserPool {
+    options: PoolOptions,
+    state: Mutex<PoolState>,
+    released: Condvar,
+}
+
+/// A browser taken from the pool, given back when dropped.
+struct PooledBrowser<'a> {
+    pool: &'a BrowserPool,
+    browser: Option<Browser>,
+}
+
+impl PooledBrowser<'_> {
+    fn browser(&self) -> &Browser {
+        self.browser.as_ref().unwrap()
+    }
+
+    /// Do not give the browser back, so it is relaunched on the next use.
+    fn discard(&mut self) {
+        self.browser = None;
+    }
+}
+
+impl Drop for PooledBrowser<'_> {
+    fn drop(&mut self) {
+        let mut state = self.pool.lock();
+        state.busy -= 1;
+        if let Some(browser) = self.browser.take() {
+            state.idle.push(browser);
+        }
+        self.pool.released.notify_one();
+    }
+}
+
+fn is_alive(browser: &Browser) -> bool {
+    browser.get_version().is_ok()
+}
+
+impl BrowserPool {
+    pub fn new(options: PoolOptions) -> Self {
+        BrowserPool {
+            options,
+            state: Mutex::new(PoolState::default()),
+            released: Condvar::new(),
+        }
+    }
+
+    pub fn options(&self) -> &PoolOptions {
+        &self.options
+    }
+
+    fn lock(&self) -> MutexGuard<'_, PoolState> {
+        // The state stays consistent even if a holder panicked.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn launch(&self) -> anyhow::Result<Browser> {
+        debug!("Launching browser...");
+        Browser::new(
+            LaunchOptions::default_builder()
+                .window_size(Some((3000, 5000)))
+                .sandbox(false)
+                .devtools(false)
+                .idle_browser_timeout(self.options.idle_timeout)
+                .build()?,
+        )
+    }
+
+    fn acquire(&self) -> anyhow::Result<PooledBrowser<'_>> {
+        let mut state = self.lock();
+        loop {
+            if let Some(browser) = state.idle.pop() {
+                state.busy += 1;
+                drop(state);
+                let mut pooled = PooledBrowser {
+                    pool: self,
+                    browser: Some(browser),
+                };
+                if is_alive(pooled.browser()) {
+                    return Ok(pooled);
+                }
+                warn!("Browser stopped responding, restarting it");
+                pooled.discard();
+                drop(pooled);
+                state = self.lock();
+                continue;
+            }
+
+            if state.busy < self.options.size {
+                state.busy += 1;
+                drop(state);
+                let mut pooled = PooledBrowser {
+                    pool: self,
+                    browser: None,
+                };
+                pooled.browser = Some(self.launch()?);
+                return Ok(pooled);
+            }
+
+            let (next, wait) = self
+                .released
+                .wait_timeout(state, self.options.acquire_timeout)
+                .unwrap_or_else(|e| e.into_inner());
+            state = next;
+            if wait.timed_out() && state.idle.is_empty() && state.busy >= self.options.size {
+                return Err(anyhow!(
+                    "No browser available after {:?}",
+                    self.options.acquire_timeout
+                ));
+            }
+        }
+    }
+
+    /// Launch every browser of the pool ahead of the first render.
+    pub fn warm_up(&self) -> anyhow::Result<()> {
+        let browsers = (0..self.options.size)
+            .map(|_| self.acquire())
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        debug!("{} browsers ready", browsers.len());
+        Ok(())
+    }
+
+    /// Drop idle browsers that stopped responding.
+    pub fn check_health(&self) {
+        // Counted as busy while checked, so no extra browser is launched.
+        let idle = {
+            let mut state = self.lock();
+            state.busy += state.idle.len();
+            std::mem::take(&mut state.idle)
+        };
+        let checked = idle.len();
+        let (alive, dead): (Vec<_>, Vec<_>) = idle.into_iter().partition(is_alive);
+        if !dead.is_empty() {
+            warn!("Dropping {} browsers that stopped responding", dead.len());
+        }
+
+        let mut state = self.lock();
+        state.busy -= checked;
+        state.idle.extend(alive);
+        self.released.notify_all();
+    }
+
+    /// Screenshot `target_el` of the HTML file at `path` into a PNG at `target`,
+    /// after `wait_el` shows up.
+    pub fn render(
+        &self,
+        path: impl AsRef<Path>,
+        target: impl AsRef<Path>,
+        target_el: &str,
+        wait_el: &str,
+    ) -> anyhow::Result<()> {
+        let mut pooled = self.acquire()?;
+
+        let tab = match pooled.browser().new_tab() {
+            Ok(tab) => tab,
+            Err(e) => {
+                pooled.discard();
+                return Err(e);
+            }
+        };
+        tab.set_default_timeout(self.options.render_timeout);
+        let png_data = (|| {
+            let url = format!("file://{}", env::current_dir()?.join(path).display());
+            tab.navigate_to(&url)?;
+
+            tab.wait_for_element(wait_el)?;
+            let body = tab.wait_for_element(target_el)?;
+
+            body.capture_screenshot(Page::CaptureScreenshotFormatOption::Png)
+        })();
+        if tab.close(false).is_err() || png_data.is_err() && !is_alive(pooled.browser()) {
+            warn!("Browser broke while rendering, restarting it");
+            pooled.discard();
+        }
+
+        std::fs::write(target, png_data?)?;
+        Ok(())
+    }
+}
+
+lazy_static! {
+    /// Shared by every render, configured by [`PoolOptions::from_env`].
+    pub static ref BROWSER_POOL: BrowserPool = BrowserPool::new(PoolOptions::from_env());
+}
@@ -1,23 +1,32 @@
-use std::{collections::HashMap, env, fs};
+use std::{collections::HashMap, env, path::Path};
 
 use anyhow::anyhow;
-use bson::doc;
 use itertools::Itertools;
-use lazy_static::lazy_static;
 use log::debug;
-use minijinja::{Environment, Template, Value};
-use mongodb::{Client, Collection};
-use rand::{rngs::SmallRng, SeedableRng};
+use minijinja::{Environment, Value};
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
 use wows_box::{
-    currencies::{CurrencyData, CurrencyType},
-    item::ItemData,
+    currencies::CurrencyType,
+    history::{box_at, date_text},
     lootbox::{LootBox, LootBoxRewardType},
+    store::LootboxStore,
+    valuation::ValuationTable,
+};
+use wows_box_rand::{
+    compensation::{compensate, compensations, received, CompensationTable},
+    nested::{nested_box_ids, open_nested},
+    rand::{aggregate, rand_multi_log, state_after, RandResult},
+    seed::SeedCode,
 };
-use wows_box_rand::rand::rand_multi;
 
-use crate::html::render_html;
+use crate::{
+    cache::render_path,
+    cost::{cost_lines, fetch_items, fetch_price, CostLineProp},
+    format::{encode_image, OutputFormat, Rendered},
+    icon::{IconCache, ICON_CACHE},
+    renderer::{Renderer, View, RENDERER},
+    theme::BUILTIN_THEMES,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct LootBoxListProp {
@@ -26,6 +35,14 @@ pub struct LootBoxListProp {
     pub rewards: Vec<LootBoxListRewardProp>,
     pub times: u32,
     pub guarantee_text: &'static str,
+    pub seed_code: Option<String>,
+    pub revision: Option<String>,
+    /// Rewards with every inner lootbox opened, shown below the raw ones.
+    pub opened_rewards: Option<Vec<LootBoxListRewardProp>>,
+    pub raw_text: &'static str,
+    pub opened_text: &'static str,
+    /// Cost footer, empty if the price is unknown.
+    pub cost: Vec<CostLineProp>,
 }
 
 pub fn guarantee_text(lang: &str) -> &'static str {
@@ -35,55 +52,153 @@ pub fn guarantee_text(lang: &str) -> &'static str {
     }
 }
 
+pub fn compensation_text(lang: &str) -> &'static str {
+    match lang {
+        "zh-sg" => "→ 补偿为",
+        _ => "→ compensated as",
+    }
+}
+
+/// Section titles of raw and fully-opened rewards.
+pub fn nested_text(lang: &str) -> (&'static str, &'static str) {
+    match lang {
+        "zh-sg" => ("开箱结果", "全部开启后"),
+        _ => ("Opened", "Fully opened"),
+    }
+}
+
 impl LootBoxListProp {
     pub async fn from_result(
         lang: &str,
-        db: &Client,
+        store: &impl LootboxStore,
         box_id: u64,
         result: HashMap<(LootBoxRewardType, bool), u32>,
         times: u32,
     ) -> anyhow::Result<Self> {
-        let box_db = db.database(&format!("wowslootbox-{lang}"));
-        let currency_col: Collection<CurrencyData> = box_db.collection("currencies");
-        let item_col: Collection<ItemData> = box_db.collection("items");
-        let list_col: Collection<LootBox> = box_db.collection("list");
+        Self::from_compensated_result(lang, store, box_id, result, HashMap::new(), times).await
+    }
 
+    /// Same as [`LootBoxListProp::from_result`], noting the compensation of
+    /// owned rewards.
+    pub async fn from_compensated_result(
+        lang: &str,
+        store: &impl LootboxStore,
+        box_id: u64,
+        result: HashMap<(LootBoxRewardType, bool), u32>,
+        compensations: HashMap<(LootBoxRewardType, bool), RandResult>,
+        times: u32,
+    ) -> anyhow::Result<Self> {
         debug!("Fetching lootbox data...");
-        let box_data = list_col
-            .find_one(doc! { "id": box_id as u32 })
+        let box_data = store
+            .get_box(lang, box_id)
             .await?
             .ok_or(anyhow!("Cannot find lootbox {}", box_id))?;
         let box_icon = box_data.icon;
         let box_title = box_data.name;
 
-        debug!("Fetching reward data...");
-        let mut vec = vec![];
-        for ((reward, guarantee), amount) in result.into_iter() {
-            let p = reward.as_precedence();
-            let (name, img) = reward_to_imgs(lang, &currency_col, &item_col, reward).await?;
-            vec.push(LootBoxListRewardProp {
-                icons: img,
-                text: name,
-                amount,
-                precedence: p,
-                is_guaranteed: guarantee,
-            });
-        }
-
-        vec.sort_by_key(|t| {
-            0u128
-                + (t.precedence.0 as u128).checked_shl(64).unwrap_or(0)
-                + t.precedence.1.unwrap_or(0) as u128
-        });
+        let rewards = reward_props(lang, store, result, compensations).await?;
+        let (raw_text, opened_text) = nested_text(lang);
 
         Ok(LootBoxListProp {
             box_icon,
             box_title,
-            rewards: vec,
+            rewards,
             times,
             guarantee_text: guarantee_text(lang),
+            seed_code: None,
+            revision: None,
+            opened_rewards: None,
+            raw_text,
+            opened_text,
+            cost: vec![],
         })
     }
+
+    /// Point every icon to its copy in `cache`.
+    pub async fn localize_icons(&mut self, cache: &IconCache) {
+        let rewards = self
+            .rewards
+            .iter_mut()
+            .chain(self.opened_rewards.iter_mut().flatten());
+        let urls = std::iter::once(&mut self.box_icon)
+            .chain(rewards.flat_map(|t| t.icons.iter_mut()))
+            .collect();
+        cache.localize(urls).await;
+    }
+}
+
+/// Display order of a reward, see [`LootBoxRewardType::as_precedence`].
+pub(crate) fn precedence_key(precedence: (u32, Option<u64>)) -> u128 {
+    (precedence.0 as u128).checked_shl(64).unwrap_or(0) + precedence.1.unwrap_or(0) as u128
+}
+
+/// Resolve names and icons of `result`, sorted for display.
+pub async fn reward_props(
+    lang: &str,
+    store: &impl LootboxStore,
+    result: HashMap<(LootBoxRewardType, bool), u32>,
+    mut compensations: HashMap<(LootBoxRewardType, bool), RandResult>,
+) -> anyhow::Result<Vec<LootBoxListRewardProp>> {
+    debug!("Fetching reward data...");
+    let mut vec = vec![];
+    for ((reward, guarantee), amount) in result.into_iter() {
+        let p = reward.as_precedence();
+        let compensation = match compensations.remove(&(reward.clone(), guarantee)) {
+            Some(t) => {
+                let (name, _) = reward_to_imgs(lang, store, t.reward_type).await?;
+                Some(format!("{} {} {name}", compensation_text(lang), t.amount))
+            }
+            None => None,
+        };
+        let (name, img) = reward_to_imgs(lang, store, reward).await?;
+        vec.push(LootBoxListRewardProp {
+            icons: img,
+            text: name,
+            amount,
+            precedence: p,
+            is_guaranteed: guarantee,
+            compensation,
+        });
+    }
+
+    // Break ties by name, so the same result always renders the same.
+    vec.sort_by(|a, b| {
+        precedence_key(a.precedence)
+            .cmp(&precedence_key(b.precedence))
+            .then_with(|| a.text.cmp(&b.text))
+            .then_with(|| a.is_guaranteed.cmp(&b.is_guaranteed))
+    });
+
+    Ok(vec)
+}
+
+/// Fetch every lootbox `data` can drop, down to `max_depth` levels.
+pub async fn fetch_nested_boxes(
+    lang: &str,
+    store: &impl LootboxStore,
+    data: &LootBox,
+    max_depth: usize,
+) -> anyhow::Result<HashMap<u64, LootBox>> {
+    let mut boxes = HashMap::new();
+    let mut pending = nested_box_ids(data);
+    for _ in 0..max_depth {
+        let mut next = vec![];
+        for id in pending {
+            if id == data.id || boxes.contains_key(&id) {
+                continue;
+            }
+            debug!("Fetching nested lootbox {}...", id);
+            // Unknown lootboxes are left closed.
+            let Some(inner) = store.get_box(lang, id).await? else {
+                continue;
+            };
+            next.extend(nested_box_ids(&inner));
+            boxes.insert(id, inner);
+        }
+        pending = next;
+    }
+
+    Ok(boxes)
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -93,57 +208,60 @@ pub struct LootBoxListRewardProp {
     pub amount: u32,
     pub precedence: (u32, Option<u64>),
     pub is_guaranteed: bool,
+    /// What the owned reward was converted into.
+    pub compensation: Option<String>,
 }
 
-async fn reward_to_imgs(
+pub(crate) async fn reward_to_imgs(
     lang: &str,
-    curr_col: &Collection<CurrencyData>,
-    item_col: &Collection<ItemData>,
+    store: &impl LootboxStore,
     reward: LootBoxRewardType,
 ) -> anyhow::Result<(String, Vec<String>)> {
     use LootBoxRewardType::*;
     match reward {
-        Credits => currency_to_img(curr_col, CurrencyType::Credits.as_icon_name()).await,
-        Gold => currency_to_img(curr_col, CurrencyType::Gold.as_icon_name()).await,
-        FreeXp => currency_to_img(curr_col, CurrencyType::FreeXp.as_icon_name()).await,
-        EliteXp => currency_to_img(curr_col, CurrencyType::EliteXp.as_icon_name()).await,
-        ParagonXp => currency_to_img(curr_col, CurrencyType::ParagonXp.as_icon_name()).await,
-        Steel => currency_to_img(curr_col, CurrencyType::Steel.as_icon_name()).await,
-        Coal => currency_to_img(curr_col, CurrencyType::Coal.as_icon_name()).await,
-        Molybdenum => currency_to_img(curr_col, CurrencyType::Molybdenum.as_icon_name()).await,
-        Brass => currency_to_img(curr_col, CurrencyType::Brass.as_icon_name()).await,
-        Saltpeter => currency_to_img(curr_col, CurrencyType::Saltpeter.as_icon_name()).await,
+        Credits => currency_to_img(store, lang, CurrencyType::Credits.as_icon_name()).await,
+        Gold => currency_to_img(store, lang, CurrencyType::Gold.as_icon_name()).await,
+        FreeXp => currency_to_img(store, lang, CurrencyType::FreeXp.as_icon_name()).await,
+        EliteXp => currency_to_img(store, lang, CurrencyType::EliteXp.as_icon_name()).await,
+        ParagonXp => currency_to_img(store, lang, CurrencyType::ParagonXp.as_icon_name()).await,
+        Steel => currency_to_img(store, lang, CurrencyType::Steel.as_icon_name()).await,
+        Coal => currency_to_img(store, lang, CurrencyType::Coal.as_icon_name()).await,
+        Molybdenum => currency_to_img(store, lang, CurrencyType::Molybdenum.as_icon_name()).await,
+        Brass => currency_to_img(store, lang, CurrencyType::Brass.as_icon_name()).await,
+        Saltpeter => currency_to_img(store, lang, CurrencyType::Saltpeter.as_icon_name()).await,
         RecruitmentPoints => {
-            currency_to_img(curr_col, CurrencyType::RecruitmentPoints.as_icon_name()).await
+            currency_to_img(store, lang, CurrencyType::RecruitmentPoints.as_icon_name()).await
+        }
+        Eventum3 => currency_to_img(store, lang, CurrencyType::Eventum3.as_icon_name()).await,
+        Eventum4 => currency_to_img(store, lang, CurrencyType::Eventum4.as_icon_name()).await,
+        Eventum5 => currency_to_img(store, lang, CurrencyType::Eventum5.as_icon_name()).await,
+        Eventum6 => currency_to_img(store, lang, CurrencyType::Eventum6.as_icon_name()).await,
+        Eventum7 => currency_to_img(store, lang, CurrencyType::Eventum7.as_icon_name()).await,
+        Eventum8 => currency_to_img(store, lang, CurrencyType::Eventum8.as_icon_name()).await,
+        Eventum9 => currency_to_img(store, lang, CurrencyType::Eventum9.as_icon_name()).await,
+        Eventum10 => currency_to_img(store, lang, CurrencyType::Eventum10.as_icon_name()).await,
+        EventumCn => currency_to_img(store, lang, CurrencyType::EventumCn.as_icon_name()).await,
+        Santium => currency_to_img(store, lang, CurrencyType::Santium.as_icon_name()).await,
+        Dockyardum1 => currency_to_img(store, lang, CurrencyType::Dockyardum1.as_icon_name()).await,
+        Dockyardum2 => currency_to_img(store, lang, CurrencyType::Dockyardum2.as_icon_name()).await,
+        Eventum11 => currency_to_img(store, lang, CurrencyType::Eventum11.as_icon_name()).await,
+        Eventum12 => currency_to_img(store, lang, CurrencyType::Eventum12.as_icon_name()).await,
+        Eventum13 => currency_to_img(store, lang, CurrencyType::Eventum13.as_icon_name()).await,
+        Eventum14 => currency_to_img(store, lang, CurrencyType::Eventum14.as_icon_name()).await,
+        Eventum1 => currency_to_img(store, lang, CurrencyType::Eventum1.as_icon_name()).await,
+        Eventum2 => currency_to_img(store, lang, CurrencyType::Eventum2.as_icon_name()).await,
+        Clientum1 => currency_to_img(store, lang, CurrencyType::Clientum1.as_icon_name()).await,
+        Clientum2 => currency_to_img(store, lang, CurrencyType::Clientum2.as_icon_name()).await,
+        ClanResource => {
+            currency_to_img(store, lang, CurrencyType::ClanResource.as_icon_name()).await
         }
-        Eventum3 => currency_to_img(curr_col, CurrencyType::Eventum3.as_icon_name()).await,
-        Eventum4 => currency_to_img(curr_col, CurrencyType::Eventum4.as_icon_name()).await,
-        Eventum5 => currency_to_img(curr_col, CurrencyType::Eventum5.as_icon_name()).await,
-        Eventum6 => currency_to_img(curr_col, CurrencyType::Eventum6.as_icon_name()).await,
-        Eventum7 => currency_to_img(curr_col, CurrencyType::Eventum7.as_icon_name()).await,
-        Eventum8 => currency_to_img(curr_col, CurrencyType::Eventum8.as_icon_name()).await,
-        Eventum9 => currency_to_img(curr_col, CurrencyType::Eventum9.as_icon_name()).await,
-        Eventum10 => currency_to_img(curr_col, CurrencyType::Eventum10.as_icon_name()).await,
-        EventumCn => currency_to_img(curr_col, CurrencyType::EventumCn.as_icon_name()).await,
-        Santium => currency_to_img(curr_col, CurrencyType::Santium.as_icon_name()).await,
-        Dockyardum1 => currency_to_img(curr_col, CurrencyType::Dockyardum1.as_icon_name()).await,
-        Dockyardum2 => currency_to_img(curr_col, CurrencyType::Dockyardum2.as_icon_name()).await,
-        Eventum11 => currency_to_img(curr_col, CurrencyType::Eventum11.as_icon_name()).await,
-        Eventum12 => currency_to_img(curr_col, CurrencyType::Eventum12.as_icon_name()).await,
-        Eventum13 => currency_to_img(curr_col, CurrencyType::Eventum13.as_icon_name()).await,
-        Eventum14 => currency_to_img(curr_col, CurrencyType::Eventum14.as_icon_name()).await,
-        Eventum1 => currency_to_img(curr_col, CurrencyType::Eventum1.as_icon_name()).await,
-        Eventum2 => currency_to_img(curr_col, CurrencyType::Eventum2.as_icon_name()).await,
-        Clientum1 => currency_to_img(curr_col, CurrencyType::Clientum1.as_icon_name()).await,
-        Clientum2 => currency_to_img(curr_col, CurrencyType::Clientum2.as_icon_name()).await,
-        ClanResource => currency_to_img(curr_col, CurrencyType::ClanResource.as_icon_name()).await,
 
         // do not edit following
-        WowsPremium => currency_to_img(curr_col, "wows-premium").await,
-        Slots => currency_to_img(curr_col, "slots").await,
+        WowsPremium => currency_to_img(store, lang, "wows-premium").await,
+        Slots => currency_to_img(store, lang, "slots").await,
 
         CamoBoost { id } | CollectionAlbum { id } | Signal { id, .. } | Style { id } => {
-            item_to_img(item_col, id).await
+            item_to_img(store, lang, id).await
         }
         Ship {
             crew_level,
@@ -156,11 +274,13 @@ async fn reward_to_imgs(
                 "{} {name}{}",
                 level_to_str(ship_level),
                 if let Some(level) = crew_level {
-                    format!("（{level} {}）", 
-                    match lang {
-                        "zh-sg" => "级舰长",
-                        _ => "Lv. Crew",
-                    })
+                    format!(
+                        "（{level} {}）",
+                        match lang {
+                            "zh-sg" => "级舰长",
+                            _ => "Lv. Crew",
+                        }
+                    )
                 } else {
                     "".to_owned()
                 }
@@ -221,11 +341,12 @@ async fn reward_to_imgs(
 
 /// Return type: Name, Icon URL
 async fn currency_to_img(
-    col: &Collection<CurrencyData>,
+    store: &impl LootboxStore,
+    lang: &str,
     curr: &str,
 ) -> anyhow::Result<(String, Vec<String>)> {
-    let data = col
-        .find_one(doc! { "type": curr })
+    let data = store
+        .get_currency(lang, curr)
         .await?
         .ok_or(anyhow!("Cannot find currency {}", curr))?;
 
@@ -234,11 +355,12 @@ async fn currency_to_img(
 
 /// Return type: Name, Icon URL
 async fn item_to_img(
-    col: &Collection<ItemData>,
+    store: &impl LootboxStore,
+    lang: &str,
     item_id: u64,
 ) -> anyhow::Result<(String, Vec<String>)> {
-    let data = col
-        .find_one(doc! { "id": item_id as u32 })
+    let data = store
+        .get_item(lang, item_id)
         .await?
         .ok_or(anyhow!("Cannot find item {}", item_id))?;
 
@@ -275,55 +397,208 @@ fn env(value: &str) -> String {
     env::var(value).unwrap_or_default()
 }
 
-lazy_static! {
-    pub static ref JINJA_ENVIRONMENT: Environment<'static> = {
-        let mut e = Environment::new();
+/// The built-in templates and themes, see [`crate::theme::THEMES`] for the
+/// ones provided at runtime.
+pub fn base_environment() -> Environment<'static> {
+    let mut e = Environment::new();
 
-        e.add_filter("chunks", chunks);
+    e.add_filter("chunks", chunks);
 
-        e.add_function("env", env);
+    e.add_function("env", env);
 
-        e.add_template(
-            "lootbox",
-            include_str!(concat!(
-                env!("CARGO_MANIFEST_DIR"),
-                "/templates/lootbox.jinja"
-            )),
-        )
-        .unwrap();
+    e.add_template(
+        "lootbox",
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/templates/lootbox.jinja"
+        )),
+    )
+    .unwrap();
+
+    e.add_template(
+        "until",
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/templates/until.jinja"
+        )),
+    )
+    .unwrap();
+
+    e.add_template(
+        "sheet",
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/templates/sheet.jinja"
+        )),
+    )
+    .unwrap();
+
+    e.add_template(
+        "compare",
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/templates/compare.jinja"
+        )),
+    )
+    .unwrap();
 
-        e
-    };
-    pub static ref LOOTBOX_TEMPLATE: Template<'static, 'static> =
-        JINJA_ENVIRONMENT.get_template("lootbox").unwrap();
-}
+    for (theme, name, source) in BUILTIN_THEMES {
+        e.add_template_owned(format!("{theme}/{name}"), *source)
+            .unwrap();
+    }
 
-pub async fn render_to_file(
-    lang: &str,
-    client: &Client,
-    key: u64,
-    times: u32,
-) -> anyhow::Result<String> {
-    let col: Collection<LootBox> = client
-        .database(&format!("wowslootbox-{lang}"))
-        .collection("list");
-    let lootbox = col.find_one(doc! { "id": key as u32 }).await?.unwrap();
+    e
+}
 
-    let found = vec![];
+/// How to simulate the openings.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RandOptions {
+    pub times: u32,
+    /// Unique reward ids owned before the first opening.
+    pub unique_rewards_list: Vec<u64>,
+    /// Guarantee counter before the first opening.
+    pub current_try: u32,
+    /// A random seed is picked if not given.
+    pub seed: Option<SeedCode>,
+    /// Also show the rewards with every inner lootbox opened.
+    pub open_nested: bool,
+    /// Ids of ships and camouflages owned in game, which are compensated.
+    pub owned_items: Vec<u64>,
+    pub compensation: CompensationTable,
+    /// Values rewards in the cost footer, with the overrides of the language.
+    pub valuation: ValuationTable,
+    pub format: OutputFormat,
+    /// Built-in theme if not given, see [`crate::theme::THEMES`].
+    pub theme: Option<String>,
+    /// Open the lootbox as it was at this time instead of the current one,
+    /// see [`wows_box::history`].
+    pub at: Option<u64>,
+}
 
-    let mut rng = SmallRng::from_entropy();
+/// How deep inner lootboxes are opened.
+pub const MAX_NESTED_DEPTH: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RenderOutput {
+    pub rendered: Rendered,
+    /// Unique reward ids owned after the openings.
+    pub unique_rewards_list: Vec<u64>,
+    /// Guarantee counter after the openings.
+    pub current_try: u32,
+    pub seed: SeedCode,
+    /// See [`LootBox::revision`].
+    pub revision: u64,
+}
 
-    let resp = rand_multi(&mut rng, &lootbox, times, &found, 0);
+pub fn revision_text(revision: u64) -> String {
+    format!("{:08x}", revision >> 32)
+}
 
-    let list_prop = LootBoxListProp::from_result(lang, client, key, resp, times).await?;
+pub async fn render_to_file(
+    lang: &str,
+    store: &impl LootboxStore,
+    key: u64,
+    options: &RandOptions,
+) -> anyhow::Result<RenderOutput> {
+    let lootbox = match options.at {
+        Some(at) => box_at(store, lang, key, at).await?.ok_or(anyhow!(
+            "Cannot find lootbox {} at {}",
+            key,
+            date_text(at)
+        ))?,
+        None => store
+            .get_box(lang, key)
+            .await?
+            .ok_or(anyhow!("Cannot find lootbox {}", key))?,
+    };
 
-    let uuid = Uuid::new_v4();
-    let cache_html_file_path = format!("{}/{}.html", env::var("CACHE_DIR")?, uuid);
-    let file_path = format!("{}/{}.png", env::var("CACHE_DIR")?, uuid);
+    let seed = options.seed.unwrap_or_else(SeedCode::random);
+    let revision = lootbox.revision();
+    let mut rng = seed.rng();
+
+    let mut log = rand_multi_log(
+        &mut rng,
+        &lootbox,
+        options.times,
+        &options.unique_rewards_list,
+        options.current_try,
+    )?;
+    let owned_items: Vec<_> = options
+        .owned_items
+        .iter()
+        .chain(options.unique_rewards_list.iter())
+        .copied()
+        .collect();
+    let owned_items = compensate(&mut log, &options.compensation, &owned_items);
+    // Only the state of this lootbox is kept, inner ones have their own.
+    let (unique_rewards_list, current_try) =
+        state_after(&log, &options.unique_rewards_list, options.current_try);
+
+    let mut list_prop = LootBoxListProp::from_compensated_result(
+        lang,
+        store,
+        key,
+        aggregate(&log),
+        compensations(&log),
+        options.times,
+    )
+    .await?;
+    // Past revisions may have had another name.
+    list_prop.box_title = lootbox.name.clone();
+    list_prop.seed_code = Some(seed.to_string());
+    list_prop.revision = Some(revision_text(revision));
+
+    // Owned rewards are valued as the compensation given instead.
+    let mut totals = received(&log);
+    if options.open_nested {
+        let boxes = fetch_nested_boxes(lang, store, &lootbox, MAX_NESTED_DEPTH).await?;
+        if !boxes.is_empty() {
+            let opened = open_nested(
+                &mut rng,
+                &lootbox,
+                &log,
+                &boxes,
+                &options.compensation,
+                &owned_items,
+                MAX_NESTED_DEPTH,
+            );
+            list_prop.opened_rewards =
+                Some(reward_props(lang, store, opened.totals, opened.compensations).await?);
+            totals = opened.received;
+        }
+    }
 
-    LOOTBOX_TEMPLATE.render_to_write(list_prop, fs::File::create(&cache_html_file_path)?)?;
+    if let Some(price) = fetch_price(lang, store, key).await? {
+        let ids: Vec<_> = totals.keys().filter_map(|(t, _)| t.get_id()).collect();
+        let items = fetch_items(lang, store, ids).await?;
+        let valuation = options.valuation.for_lang(lang);
+        let report = price.cost_report(options.times, &totals, &items, &valuation);
+        list_prop.cost = cost_lines(lang, &report);
+    }
 
-    render_html(cache_html_file_path, &file_path, "table#list", "div#loaded")?;
+    let rendered = match options.format {
+        OutputFormat::Text => Rendered::Text(list_prop.to_text()),
+        // Icons stay remote, local paths mean nothing to other frontends.
+        OutputFormat::Json => Rendered::Json(Box::new(list_prop)),
+        format => {
+            list_prop.localize_icons(&ICON_CACHE).await;
+            let file_path = render_path()?;
+            RENDERER
+                .render(
+                    View::List(&list_prop),
+                    options.theme.as_deref(),
+                    Path::new(&file_path),
+                )
+                .await?;
+            Rendered::Image(encode_image(&file_path, format)?)
+        }
+    };
 
-    Ok(file_path)
+    Ok(RenderOutput {
+        rendered,
+        unique_rewards_list,
+        current_try,
+        seed,
+        revision,
+    })
 }
@@ -0,0 +1,846 @@
+//! Draws pages straight to PNG, so no browser is needed.
+
+use std::{
+    collections::HashMap,
+    env,
+    future::Future,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use cosmic_text::{Attrs, Buffer, Color, Family, FontSystem, Metrics, Shaping, SwashCache, Weight};
+use futures::future::join_all;
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+use log::warn;
+
+use crate::{
+    compare::CompareProp,
+    process::{LootBoxListProp, LootBoxListRewardProp},
+    renderer::{Renderer, View},
+    sheet::SheetProp,
+    until::UntilProp,
+};
+
+const PADDING: i32 = 20;
+const GAP: i32 = 10;
+const TITLE_ICON: u32 = 80;
+const ICON: u32 = 60;
+const SMALL_ICON: u32 = 40;
+/// Height of the highest histogram bar.
+const BAR_HEIGHT: f64 = 300.0;
+const BAR_WIDTH: i32 = 36;
+
+const WHITE: [u8; 4] = [255, 255, 255, 255];
+const GREY: [u8; 4] = [208, 208, 213, 255];
+const GOLD: [u8; 4] = [255, 204, 102, 255];
+/// Used when the background image of `ASSET_FOLDER` cannot be loaded.
+const BACKGROUND: [u8; 4] = [30, 34, 42, 255];
+/// Remote icons taking longer are left out.
+const ICON_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Style {
+    Title,
+    Heading,
+    Normal,
+    Bold,
+    Small,
+}
+
+impl Style {
+    fn size(self) -> f32 {
+        match self {
+            Style::Title => 32.0,
+            Style::Heading => 22.0,
+            Style::Normal | Style::Bold => 18.0,
+            Style::Small => 13.0,
+        }
+    }
+
+    fn weight(self) -> Weight {
+        match self {
+            Style::Normal | Style::Small => Weight::NORMAL,
+            _ => Weight::BOLD,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Span {
+    text: String,
+    style: Style,
+    color: [u8; 4],
+}
+
+impl Span {
+    fn new(text: impl Into<String>, style: Style) -> Self {
+        Span {
+            text: text.into(),
+            style,
+            color: WHITE,
+        }
+    }
+
+    fn color(mut self, color: [u8; 4]) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+/// Icons followed by lines of text.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Cell {
+    icons: Vec<String>,
+    icon_size: u32,
+    lines: Vec<Vec<Span>>,
+}
+
+impl Cell {
+    fn text(spans: Vec<Span>) -> Self {
+        Cell {
+            lines: vec![spans],
+            ..Default::default()
+        }
+    }
+
+    fn with_icons(icons: &[String], icon_size: u32, spans: Vec<Span>) -> Self {
+        Cell {
+            icons: icons.to_vec(),
+            icon_size,
+            lines: vec![spans],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Cell(Cell, Align),
+    Grid {
+        columns: usize,
+        cells: Vec<Cell>,
+    },
+    /// Histogram bars: label, count and height relative to the highest one.
+    Bars(Vec<(String, u32, f64)>),
+    Rule,
+}
+
+/// Layout of a page, built from the same props as the templates.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Page {
+    blocks: Vec<Block>,
+}
+
+fn reward_cells(rewards: &[LootBoxListRewardProp], guarantee_text: &str) -> Vec<Cell> {
+    rewards
+        .iter()
+        .map(|t| {
+            let mut spans = vec![
+                Span::new(&t.text, Style::Normal),
+                Span::new(format!("× {}", t.amount), Style::Bold),
+            ];
+            if t.is_guaranteed {
+                spans.push(Span::new(guarantee_text, Style::Normal).color(GOLD));
+            }
+            if let Some(compensation) = &t.compensation {
+                spans.push(Span::new(compensation, Style::Normal).color(GREY));
+            }
+            Cell::with_icons(&t.icons, ICON, spans)
+        })
+        .collect()
+}
+
+fn title(icon: &str, text: &str, subtitle: Option<String>) -> Block {
+    let mut lines = vec![vec![Span::new(text, Style::Title)]];
+    lines.extend(subtitle.map(|t| vec![Span::new(t, Style::Heading)]));
+    Block::Cell(
+        Cell {
+            icons: vec![icon.to_owned()],
+            icon_size: TITLE_ICON,
+            lines,
+        },
+        Align::Center,
+    )
+}
+
+fn heading(text: &str) -> Block {
+    Block::Cell(
+        Cell::text(vec![Span::new(text, Style::Heading)]),
+        Align::Left,
+    )
+}
+
+impl From<&LootBoxListProp> for Page {
+    fn from(prop: &LootBoxListProp) -> Self {
+        let mut blocks = vec![
+            title(
+                &prop.box_icon,
+                &prop.box_title,
+                Some(format!("× {}", prop.times)),
+            ),
+            Block::Rule,
+        ];
+        if prop.opened_rewards.is_some() {
+            blocks.push(heading(prop.raw_text));
+        }
+        blocks.push(Block::Grid {
+            columns: 3,
+            cells: reward_cells(&prop.rewards, prop.guarantee_text),
+        });
+        if let Some(opened) = &prop.opened_rewards {
+            blocks.push(heading(prop.opened_text));
+            blocks.push(Block::Grid {
+                columns: 3,
+                cells: reward_cells(opened, prop.guarantee_text),
+            });
+        }
+        if !prop.cost.is_empty() {
+            let spans = prop
+                .cost
+                .iter()
+                .flat_map(|t| {
+                    [
+                        Span::new(&t.label, Style::Normal),
+                        Span::new(&t.value, Style::Bold),
+                    ]
+                })
+                .collect();
+            blocks.push(Block::Cell(Cell::text(spans), Align::Left));
+        }
+        if let Some(seed_code) = &prop.seed_code {
+            let mut spans = vec![Span::new(format!("#{seed_code}"), Style::Small).color(GREY)];
+            if let Some(revision) = &prop.revision {
+                spans.push(Span::new(format!("@{revision}"), Style::Small).color(GREY));
+            }
+            blocks.push(Block::Cell(Cell::text(spans), Align::Right));
+        }
+        Page { blocks }
+    }
+}
+
+impl From<&UntilProp> for Page {
+    fn from(prop: &UntilProp) -> Self {
+        let target = Cell::with_icons(
+            &prop.target_icons,
+            SMALL_ICON,
+            vec![Span::new(format!("→ {}", prop.target_text), Style::Heading)],
+        );
+        let stats = prop
+            .stats
+            .iter()
+            .flat_map(|t| {
+                [
+                    Cell::text(vec![Span::new(&t.label, Style::Normal)]),
+                    Cell::text(vec![Span::new(&t.value, Style::Bold)]),
+                ]
+            })
+            .collect();
+        let bars = prop
+            .bars
+            .iter()
+            .map(|t| (t.label.clone(), t.count, t.height))
+            .collect();
+        Page {
+            blocks: vec![
+                title(&prop.box_icon, &prop.box_title, None),
+                Block::Cell(target, Align::Center),
+                Block::Grid {
+                    columns: 2,
+                    cells: stats,
+                },
+                Block::Bars(bars),
+            ],
+        }
+    }
+}
+
+impl From<&CompareProp> for Page {
+    fn from(prop: &CompareProp) -> Self {
+        let mut cells = vec![Cell::default()];
+        cells.extend(prop.boxes.iter().map(|t| {
+            Cell::with_icons(
+                std::slice::from_ref(&t.icon),
+                ICON,
+                vec![Span::new(&t.title, Style::Bold)],
+            )
+        }));
+        for row in prop.currencies.iter().chain(prop.stats.iter()) {
+            cells.push(Cell::with_icons(
+                &row.icons,
+                SMALL_ICON,
+                vec![Span::new(&row.label, Style::Normal)],
+            ));
+            cells.extend(
+                row.values
+                    .iter()
+                    .map(|t| Cell::text(vec![Span::new(t, Style::Bold)])),
+            );
+        }
+        Page {
+            blocks: vec![
+                Block::Cell(
+                    Cell::text(vec![Span::new(
+                        format!("{}: {}", prop.times_text, prop.times),
+                        Style::Heading,
+                    )]),
+                    Align::Center,
+                ),
+                Block::Grid {
+                    columns: prop.boxes.len() + 1,
+                    cells,
+                },
+            ],
+        }
+    }
+}
+
+impl From<&SheetProp> for Page {
+    fn from(prop: &SheetProp) -> Self {
+        let mut blocks = vec![title(&prop.box_icon, &prop.box_title, None)];
+        for slot in prop.slots.iter() {
+            blocks.push(heading(&slot.title));
+            blocks.push(Block::Rule);
+            for list in slot.lists.iter() {
+                let color = if list.is_valuable { GOLD } else { WHITE };
+                let mut spans = vec![
+                    Span::new(&list.title, Style::Bold).color(color),
+                    Span::new(&list.probability, Style::Bold).color(color),
+                ];
+                if list.has_unique_rewards {
+                    spans.push(Span::new(prop.unique_text, Style::Normal).color(GOLD));
+                }
+                blocks.push(Block::Cell(Cell::text(spans), Align::Left));
+
+                let cells = list
+                    .rewards
+                    .iter()
+                    .flat_map(|t| {
+                        [
+                            Cell::with_icons(
+                                &t.icons,
+                                SMALL_ICON,
+                                vec![Span::new(&t.text, Style::Normal)],
+                            ),
+                            Cell::text(vec![Span::new(format!("× {}", t.amount), Style::Bold)]),
+                            Cell::text(vec![Span::new(&t.probability, Style::Bold)]),
+                        ]
+                    })
+                    .collect();
+                blocks.push(Block::Grid { columns: 3, cells });
+            }
+        }
+        for note in prop.notes.iter() {
+            blocks.push(Block::Cell(
+                Cell::text(vec![Span::new(note, Style::Normal)]),
+                Align::Left,
+            ));
+        }
+        Page { blocks }
+    }
+}
+
+impl From<View<'_>> for Page {
+    fn from(view: View<'_>) -> Self {
+        match view {
+            View::List(prop) => prop.into(),
+            View::Until(prop) => prop.into(),
+            View::Compare(prop) => prop.into(),
+            View::Sheet(prop) => prop.into(),
+        }
+    }
+}
+
+impl Page {
+    fn icons(&self) -> impl Iterator<Item = &String> {
+        let cells = self.blocks.iter().flat_map(|t| match t {
+            Block::Cell(cell, _) => std::slice::from_ref(cell),
+            Block::Grid { cells, .. } => cells.as_slice(),
+            _ => &[],
+        });
+        cells.flat_map(|t| t.icons.iter())
+    }
+}
+
+/// Positioned drawing operation.
+#[derive(Debug, Clone, PartialEq)]
+enum Op {
+    Icon {
+        src: String,
+        x: i32,
+        y: i32,
+        size: u32,
+    },
+    Text {
+        span: Span,
+        x: i32,
+        y: i32,
+    },
+    Rect {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        color: [u8; 4],
+    },
+}
+
+impl Op {
+    fn offset(mut self, dx: i32, dy: i32) -> Op {
+        match &mut self {
+            Op::Icon { x, y, .. } | Op::Text { x, y, .. } | Op::Rect { x, y, .. } => {
+                *x += dx;
+                *y += dy;
+            }
+        }
+        self
+    }
+}
+
+/// Size and operations of something laid out at the origin.
+struct Laid {
+    width: i32,
+    height: i32,
+    ops: Vec<Op>,
+}
+
+impl Laid {
+    fn empty() -> Laid {
+        Laid {
+            width: 0,
+            height: 0,
+            ops: vec![],
+        }
+    }
+}
+
+fn text_buffer(fonts: &mut FontSystem, span: &Span) -> Buffer {
+    let size = span.style.size();
+    let mut buffer = Buffer::new(fonts, Metrics::new(size, size * 1.3));
+    let attrs = Attrs::new()
+        .family(Family::SansSerif)
+        .weight(span.style.weight());
+    buffer.set_size(fonts, None, None);
+    buffer.set_text(fonts, &span.text, attrs, Shaping::Advanced);
+    buffer.shape_until_scroll(fonts, false);
+    buffer
+}
+
+fn measure(fonts: &mut FontSystem, span: &Span) -> (i32, i32) {
+    let buffer = text_buffer(fonts, span);
+    let (width, height) = buffer.layout_runs().fold((0.0f32, 0.0f32), |(w, h), t| {
+        (w.max(t.line_w), h + t.line_height)
+    });
+    (width.ceil() as i32, height.ceil() as i32)
+}
+
+fn layout_cell(fonts: &mut FontSystem, cell: &Cell) -> Laid {
+    let mut ops = vec![];
+
+    let mut text_width = 0;
+    let mut text_height = 0;
+    let mut lines = vec![];
+    for line in cell.lines.iter() {
+        let mut x = 0;
+        let mut line_height = 0;
+        let mut spans = vec![];
+        for span in line.iter().filter(|t| !t.text.is_empty()) {
+            let (w, h) = measure(fonts, span);
+            if x > 0 {
+                x += GAP;
+            }
+            spans.push((span, x, h));
+            x += w;
+            line_height = line_height.max(h);
+        }
+        lines.push((spans, text_height, line_height));
+        text_width = text_width.max(x);
+        text_height += line_height;
+    }
+
+    let icons_width = cell.icons.len() as i32 * (cell.icon_size as i32 + 4);
+    let icons_height = if cell.icons.is_empty() {
+        0
+    } else {
+        cell.icon_size as i32
+    };
+    let height = icons_height.max(text_height);
+
+    for (idx, src) in cell.icons.iter().enumerate() {
+        ops.push(Op::Icon {
+            src: src.clone(),
+            x: idx as i32 * (cell.icon_size as i32 + 4),
+            y: (height - icons_height) / 2,
+            size: cell.icon_size,
+        });
+    }
+
+    let text_x = if icons_width > 0 && text_width > 0 {
+        icons_width + GAP
+    } else {
+        icons_width
+    };
+    let text_y = (height - text_height) / 2;
+    for (spans, line_y, line_height) in lines {
+        for (span, x, h) in spans {
+            ops.push(Op::Text {
+                span: span.clone(),
+                x: text_x + x,
+                y: text_y + line_y + (line_height - h) / 2,
+            });
+        }
+    }
+
+    Laid {
+        width: text_x + text_width,
+        height,
+        ops,
+    }
+}
+
+fn layout_grid(fonts: &mut FontSystem, columns: usize, cells: &[Cell]) -> Laid {
+    let columns = columns.max(1);
+    let laid: Vec<_> = cells.iter().map(|t| layout_cell(fonts, t)).collect();
+
+    let mut widths = vec![0; columns];
+    for (idx, cell) in laid.iter().enumerate() {
+        widths[idx % columns] = widths[idx % columns].max(cell.width);
+    }
+
+    let mut ops = vec![];
+    let mut y = 0;
+    for row in laid.chunks(columns) {
+        let row_height = row.iter().map(|t| t.height).max().unwrap_or(0);
+        let mut x = 0;
+        for (cell, width) in row.iter().zip(widths.iter()) {
+            let dy = y + (row_height - cell.height) / 2;
+            ops.extend(cell.ops.iter().cloned().map(|t| t.offset(x, dy)));
+            x += width + 2 * GAP;
+        }
+        y += row_height + 4;
+    }
+
+    let width = widths.iter().sum::<i32>() + 2 * GAP * (columns as i32 - 1);
+    Laid {
+        width: width.max(0),
+        height: (y - 4).max(0),
+        ops,
+    }
+}
+
+fn layout_bars(fonts: &mut FontSystem, bars: &[(String, u32, f64)]) -> Laid {
+    let mut ops = vec![];
+    let mut x = 0;
+    let label_height = measure(fonts, &Span::new("0", Style::Small)).1;
+    let top = label_height + 4;
+    let bottom = top + BAR_HEIGHT as i32;
+    for (label, count, height) in bars {
+        let label = Span::new(label, Style::Small);
+        let count = Span::new(count.to_string(), Style::Small);
+        let (label_width, _) = measure(fonts, &label);
+        let (count_width, _) = measure(fonts, &count);
+        let width = BAR_WIDTH.max(label_width).max(count_width);
+
+        let bar_height = (height / 100.0 * BAR_HEIGHT).round() as i32;
+        ops.push(Op::Rect {
+            x: x + (width - BAR_WIDTH) / 2,
+            y: bottom - bar_height,
+            width: BAR_WIDTH as u32,
+            height: bar_height as u32,
+            color: GREY,
+        });
+        ops.push(Op::Text {
+            span: count,
+            x: x + (width - count_width) / 2,
+            y: bottom - bar_height - label_height - 2,
+        });
+        ops.push(Op::Text {
+            span: label,
+            x: x + (width - label_width) / 2,
+            y: bottom + 4,
+        });
+        x += width + 4;
+    }
+    Laid {
+        width: (x - 4).max(0),
+        height: bottom + 4 + label_height,
+        ops,
+    }
+}
+
+fn layout(fonts: &mut FontSystem, page: &Page) -> Laid {
+    let laid: Vec<_> = page
+        .blocks
+        .iter()
+        .map(|block| {
+            let laid = match block {
+                Block::Cell(cell, _) => layout_cell(fonts, cell),
+                Block::Grid { columns, cells } => layout_grid(fonts, *columns, cells),
+                Block::Bars(bars) => layout_bars(fonts, bars),
+                Block::Rule => Laid::empty(),
+            };
+            (block, laid)
+        })
+        .collect();
+
+    let content_width = laid.iter().map(|(_, t)| t.width).max().unwrap_or(0);
+    let mut ops = vec![];
+    let mut y = PADDING;
+    for (block, laid) in laid {
+        let x = match block {
+            Block::Cell(_, Align::Center) => PADDING + (content_width - laid.width) / 2,
+            Block::Cell(_, Align::Right) => PADDING + content_width - laid.width,
+            Block::Rule => {
+                ops.push(Op::Rect {
+                    x: PADDING,
+                    y: y + 4,
+                    width: content_width as u32,
+                    height: 2,
+                    color: GREY,
+                });
+                y += 10 + GAP;
+                continue;
+            }
+            _ => PADDING,
+        };
+        ops.extend(laid.ops.into_iter().map(|t| t.offset(x, y)));
+        y += laid.height + GAP;
+    }
+
+    Laid {
+        width: content_width + 2 * PADDING,
+        height: y - GAP + PADDING,
+        ops,
+    }
+}
+
+fn blend(canvas: &mut RgbaImage, x: i32, y: i32, color: [u8; 4]) {
+    if x < 0 || y < 0 || x >= canvas.width() as i32 || y >= canvas.height() as i32 {
+        return;
+    }
+    let pixel = canvas.get_pixel_mut(x as u32, y as u32);
+    let alpha = color[3] as u32;
+    for i in 0..3 {
+        pixel[i] = ((color[i] as u32 * alpha + pixel[i] as u32 * (255 - alpha)) / 255) as u8;
+    }
+}
+
+fn load_background() -> Option<DynamicImage> {
+    let path = format!("{}/bg.jpg", env::var("ASSET_FOLDER").unwrap_or_default());
+    match image::open(&path) {
+        Ok(bg) => Some(bg),
+        Err(e) => {
+            warn!("Cannot load background {path}: {e}");
+            None
+        }
+    }
+}
+
+async fn load_icon(client: &reqwest::Client, src: &str) -> anyhow::Result<Vec<u8>> {
+    if src.starts_with("http://") || src.starts_with("https://") {
+        Ok(client
+            .get(src)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?
+            .to_vec())
+    } else {
+        Ok(tokio::fs::read(src.trim_start_matches("file://")).await?)
+    }
+}
+
+/// Draws pages with cosmic-text, using the system fonts and the font file at
+/// `FONT_FILE` if set.
+pub struct RasterRenderer {
+    painter: Arc<Painter>,
+    client: reqwest::Client,
+}
+
+/// Glyph pixels drawn by cosmic-text: x, y, width, height and color.
+type GlyphPixels = (i32, i32, u32, u32, [u8; 4]);
+
+/// State shared by the blocking paints.
+struct Painter {
+    fonts: Mutex<FontSystem>,
+    glyphs: Mutex<SwashCache>,
+    /// Decoded once, `None` if it cannot be loaded.
+    background: Option<DynamicImage>,
+    /// The background resized for the last page, pages often share a size.
+    resized: Mutex<Option<RgbaImage>>,
+}
+
+impl Default for RasterRenderer {
+    fn default() -> Self {
+        let mut fonts = FontSystem::new();
+        if let Ok(path) = env::var("FONT_FILE") {
+            if let Err(e) = fonts.db_mut().load_font_file(&path) {
+                warn!("Cannot load font {path}: {e}");
+            }
+        }
+        RasterRenderer {
+            painter: Arc::new(Painter {
+                fonts: Mutex::new(fonts),
+                glyphs: Mutex::new(SwashCache::new()),
+                background: load_background(),
+                resized: Mutex::new(None),
+            }),
+            client: reqwest::Client::builder()
+                .timeout(ICON_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> anyhow::Result<MutexGuard<'_, T>> {
+    mutex
+        .lock()
+        .map_err(|_| anyhow!("Renderer lock is poisoned"))
+}
+
+impl Painter {
+    fn background(&self, width: u32, height: u32) -> anyhow::Result<RgbaImage> {
+        let Some(bg) = &self.background else {
+            return Ok(RgbaImage::from_pixel(width, height, Rgba(BACKGROUND)));
+        };
+        let mut resized = lock(&self.resized)?;
+        match resized.as_ref() {
+            Some(t) if t.dimensions() == (width, height) => {}
+            _ => {
+                *resized = Some(imageops::resize(
+                    bg,
+                    width,
+                    height,
+                    imageops::FilterType::Triangle,
+                ))
+            }
+        }
+        Ok(resized.clone().unwrap_or_default())
+    }
+
+    fn paint(
+        &self,
+        page: &Page,
+        icons: &HashMap<String, DynamicImage>,
+    ) -> anyhow::Result<RgbaImage> {
+        // Shared by every render, so only locked while shaping text.
+        let laid = layout(&mut *lock(&self.fonts)?, page);
+        let mut canvas = self.background(laid.width.max(1) as u32, laid.height.max(1) as u32)?;
+
+        for op in laid.ops {
+            match op {
+                Op::Icon { src, x, y, size } => {
+                    if let Some(icon) = icons.get(&src) {
+                        let icon = icon.thumbnail(size, size).to_rgba8();
+                        let dx = (size - icon.width()) as i64 / 2;
+                        let dy = (size - icon.height()) as i64 / 2;
+                        imageops::overlay(&mut canvas, &icon, x as i64 + dx, y as i64 + dy);
+                    }
+                }
+                Op::Text { span, x, y } => {
+                    for (gx, gy, w, h, color) in self.glyphs(&span)? {
+                        for px in gx..gx + w as i32 {
+                            for py in gy..gy + h as i32 {
+                                blend(&mut canvas, x + px, y + py, color);
+                            }
+                        }
+                    }
+                }
+                Op::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                    color,
+                } => {
+                    for px in x..x + width as i32 {
+                        for py in y..y + height as i32 {
+                            blend(&mut canvas, px, py, color);
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(canvas)
+    }
+
+    /// Pixels of the glyphs of `span`.
+    fn glyphs(&self, span: &Span) -> anyhow::Result<Vec<GlyphPixels>> {
+        let mut fonts = lock(&self.fonts)?;
+        let mut glyphs = lock(&self.glyphs)?;
+        let buffer = text_buffer(&mut fonts, span);
+        let [r, g, b, a] = span.color;
+        let mut pixels = vec![];
+        buffer.draw(
+            &mut fonts,
+            &mut glyphs,
+            Color::rgba(r, g, b, a),
+            |x, y, w, h, color| {
+                pixels.push((x, y, w, h, [color.r(), color.g(), color.b(), color.a()]))
+            },
+        );
+        Ok(pixels)
+    }
+}
+
+impl Renderer for RasterRenderer {
+    /// Themes are templates, which are not used here, so pages are always
+    /// drawn in the default look.
+    fn render(
+        &self,
+        view: View<'_>,
+        theme: Option<&str>,
+        target: &Path,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send {
+        if let Some(theme) = theme {
+            warn!("Theme {theme} is not supported without Chrome, drawing the default look");
+        }
+        let page = Page::from(view);
+        let target = target.to_path_buf();
+        async move {
+            let mut srcs: Vec<_> = page.icons().filter(|t| !t.is_empty()).cloned().collect();
+            srcs.sort_unstable();
+            srcs.dedup();
+            // Loaded together, so slow ones only cost one timeout.
+            let loads: Vec<_> = srcs.iter().map(|t| load_icon(&self.client, t)).collect();
+            let loaded = join_all(loads).await;
+            let mut data = HashMap::new();
+            for (src, icon) in srcs.iter().zip(loaded) {
+                match icon {
+                    Ok(icon) => {
+                        data.insert(src.clone(), icon);
+                    }
+                    Err(e) => warn!("Cannot load icon {src}: {e}"),
+                }
+            }
+
+            // Decoding and drawing are CPU bound, keep them off the async workers.
+            let painter = self.painter.clone();
+            tokio::task::spawn_blocking(move || {
+                let mut icons = HashMap::new();
+                for (src, data) in data {
+                    match image::load_from_memory(&data) {
+                        Ok(icon) => {
+                            icons.insert(src, icon);
+                        }
+                        Err(e) => warn!("Cannot decode icon {src}: {e}"),
+                    }
+                }
+                let image = painter.paint(&page, &icons)?;
+                image.save_with_format(&target, image::ImageFormat::Png)?;
+                Ok(())
+            })
+            .await?
+        }
+    }
+}
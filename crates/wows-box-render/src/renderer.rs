@@ -0,0 +1,87 @@
+use std::{future::Future, path::Path};
+
+use lazy_static::lazy_static;
+
+use crate::{compare::CompareProp, process::LootBoxListProp, sheet::SheetProp, until::UntilProp};
+
+#[cfg(not(any(feature = "chrome", feature = "raster")))]
+compile_error!("enable at least one of the `chrome` and `raster` features");
+
+/// A page that can be rendered.
+#[derive(Debug, Clone, Copy)]
+pub enum View<'a> {
+    List(&'a LootBoxListProp),
+    Until(&'a UntilProp),
+    Compare(&'a CompareProp),
+    Sheet(&'a SheetProp),
+}
+
+/// Draws pages to PNG images.
+pub trait Renderer {
+    /// Draw `view` to a PNG file at `target`, in `theme` if the renderer
+    /// supports it and the theme has the page.
+    fn render(
+        &self,
+        view: View<'_>,
+        theme: Option<&str>,
+        target: &Path,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+}
+
+/// Renders the minijinja templates with a headless Chrome.
+#[cfg(feature = "chrome")]
+#[derive(Debug, Clone, Default)]
+pub struct ChromeRenderer;
+
+#[cfg(feature = "chrome")]
+impl Renderer for ChromeRenderer {
+    fn render(
+        &self,
+        view: View<'_>,
+        theme: Option<&str>,
+        target: &Path,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send {
+        use std::fs;
+
+        use log::warn;
+
+        use crate::{html::render_html, theme::THEMES};
+
+        async move {
+            let (html, target_el) = match view {
+                View::List(prop) => (THEMES.render(theme, "lootbox", prop)?, "table#list"),
+                View::Until(prop) => (THEMES.render(theme, "until", prop)?, "div#app"),
+                View::Compare(prop) => (THEMES.render(theme, "compare", prop)?, "table#compare"),
+                View::Sheet(prop) => (THEMES.render(theme, "sheet", prop)?, "div#app"),
+            };
+            let html_path = target.with_extension("html");
+            fs::write(&html_path, html)?;
+
+            // Waiting for a browser blocks, keep it off the async workers.
+            let target = target.to_path_buf();
+            let page = html_path.clone();
+            let task = tokio::task::spawn_blocking(move || {
+                render_html(page, target, target_el, "div#loaded")
+            });
+            let res = match task.await {
+                Ok(res) => res,
+                Err(e) => Err(e.into()),
+            };
+            // Only the image is kept, whether or not the render succeeded.
+            if let Err(e) = fs::remove_file(&html_path) {
+                warn!("Cannot remove {}: {e}", html_path.display());
+            }
+            res
+        }
+    }
+}
+
+/// Renderer selected by the cargo features, `raster` if it is enabled.
+#[cfg(feature = "raster")]
+pub type DefaultRenderer = crate::raster::RasterRenderer;
+#[cfg(not(feature = "raster"))]
+pub type DefaultRenderer = ChromeRenderer;
+
+lazy_static! {
+    pub static ref RENDERER: DefaultRenderer = DefaultRenderer::default();
+}
@@ -0,0 +1,183 @@
+use std::path::Path;
+
+use anyhow::anyhow;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use wows_box::{
+    lootbox::{LootBox, LootBoxRewardList},
+    store::LootboxStore,
+};
+
+use crate::{
+    cache::render_path,
+    icon::{IconCache, ICON_CACHE},
+    process::reward_to_imgs,
+    renderer::{Renderer, View, RENDERER},
+};
+
+/// Contents of a lootbox with every probability.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SheetProp {
+    pub box_icon: String,
+    pub box_title: String,
+    pub slots: Vec<SheetSlotProp>,
+    /// Footer lines about the filler and the guarantee.
+    pub notes: Vec<String>,
+    pub unique_text: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SheetSlotProp {
+    pub title: String,
+    pub lists: Vec<SheetListProp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SheetListProp {
+    pub title: String,
+    pub probability: String,
+    pub is_valuable: bool,
+    pub has_unique_rewards: bool,
+    pub rewards: Vec<SheetRewardProp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SheetRewardProp {
+    pub icons: Vec<String>,
+    pub text: String,
+    pub amount: u32,
+    pub probability: String,
+}
+
+/// Labels: slot, unique rewards, filler, guarantee.
+fn sheet_labels(lang: &str) -> [&'static str; 4] {
+    match lang {
+        "zh-sg" => [
+            "槽位",
+            "独特物品",
+            "独特物品全部获得后替换为：",
+            "保底开箱次数：",
+        ],
+        _ => [
+            "Slot",
+            "Unique",
+            "Replaced when all unique rewards are owned: ",
+            "Guaranteed within openings: ",
+        ],
+    }
+}
+
+/// Probability in percent, without trailing zeros.
+pub fn probability_text(probability: f64) -> String {
+    let text = format!("{:.2}", probability * 100.0);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    format!("{text}%")
+}
+
+async fn list_prop(
+    lang: &str,
+    store: &impl LootboxStore,
+    list: &LootBoxRewardList,
+    is_valuable: bool,
+) -> anyhow::Result<SheetListProp> {
+    let mut rewards = vec![];
+    for reward in list.rewards.iter() {
+        let (text, icons) = reward_to_imgs(lang, store, reward.reward.clone()).await?;
+        rewards.push(SheetRewardProp {
+            icons,
+            text,
+            amount: reward.amount,
+            probability: probability_text(reward.probability),
+        });
+    }
+
+    Ok(SheetListProp {
+        title: list.name.clone(),
+        probability: probability_text(list.probability),
+        is_valuable,
+        has_unique_rewards: list.has_unique_rewards,
+        rewards,
+    })
+}
+
+impl SheetProp {
+    pub async fn from_lootbox(
+        lang: &str,
+        store: &impl LootboxStore,
+        data: &LootBox,
+    ) -> anyhow::Result<Self> {
+        let [slot_text, unique_text, filler_text, guarantee_text] = sheet_labels(lang);
+
+        debug!("Fetching reward data...");
+        let mut slots = vec![];
+        for (idx, slot) in data.slots.iter().enumerate() {
+            let mut lists = vec![];
+            for list in slot.common.iter() {
+                lists.push(list_prop(lang, store, list, false).await?);
+            }
+            for list in slot.valuable.iter() {
+                lists.push(list_prop(lang, store, list, true).await?);
+            }
+            slots.push(SheetSlotProp {
+                title: if slot.name.is_empty() {
+                    format!("{slot_text} {}", idx + 1)
+                } else {
+                    slot.name.clone()
+                },
+                lists,
+            });
+        }
+
+        let mut notes = vec![];
+        if let Some(filler) = &data.filler {
+            let (text, _) = reward_to_imgs(lang, store, filler.filler.clone()).await?;
+            notes.push(format!("{filler_text}{text} × {}", filler.amount));
+        }
+        if let Some(save_point) = data.save_point {
+            notes.push(format!("{guarantee_text}{save_point}"));
+        }
+
+        Ok(SheetProp {
+            box_icon: data.icon.clone(),
+            box_title: data.name.clone(),
+            slots,
+            notes,
+            unique_text,
+        })
+    }
+
+    /// Point every icon to its copy in `cache`.
+    pub async fn localize_icons(&mut self, cache: &IconCache) {
+        let rewards = self
+            .slots
+            .iter_mut()
+            .flat_map(|t| t.lists.iter_mut())
+            .flat_map(|t| t.rewards.iter_mut());
+        let urls = std::iter::once(&mut self.box_icon)
+            .chain(rewards.flat_map(|t| t.icons.iter_mut()))
+            .collect();
+        cache.localize(urls).await;
+    }
+}
+
+/// Render the contents of lootbox `key`, returning the image path.
+pub async fn render_probability_sheet(
+    lang: &str,
+    store: &impl LootboxStore,
+    key: u64,
+) -> anyhow::Result<String> {
+    let lootbox = store
+        .get_box(lang, key)
+        .await?
+        .ok_or(anyhow!("Cannot find lootbox {}", key))?;
+
+    let mut prop = SheetProp::from_lootbox(lang, store, &lootbox).await?;
+    prop.localize_icons(&ICON_CACHE).await;
+
+    let file_path = render_path()?;
+    RENDERER
+        .render(View::Sheet(&prop), None, Path::new(&file_path))
+        .await?;
+
+    Ok(file_path)
+}
@@ -0,0 +1,213 @@
+//! Named variants of the templates, and templates provided at runtime.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+use minijinja::Environment;
+use serde::Serialize;
+
+use crate::process::base_environment;
+
+/// Themes compiled in, as (theme, template, source).
+pub const BUILTIN_THEMES: &[(&str, &str, &str)] = &[
+    (
+        "dark",
+        "lootbox",
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/templates/themes/dark/lootbox.jinja"
+        )),
+    ),
+    (
+        "compact",
+        "lootbox",
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/templates/themes/compact/lootbox.jinja"
+        )),
+    ),
+    (
+        "mobile",
+        "lootbox",
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/templates/themes/mobile/lootbox.jinja"
+        )),
+    ),
+];
+
+/// A template file found in the template directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TemplateFile {
+    name: String,
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    size: u64,
+}
+
+/// `<name>.jinja` replaces a built-in template, `<theme>/<name>.jinja` adds to
+/// a theme.
+fn scan(dir: &Path) -> Vec<TemplateFile> {
+    let mut files = vec![];
+    let mut visit = |dir: &Path, prefix: &str| -> Vec<PathBuf> {
+        let mut subdirs = vec![];
+        let Ok(entries) = fs::read_dir(dir) else {
+            return subdirs;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                subdirs.push(path);
+                continue;
+            }
+            if path.extension().is_none_or(|t| t != "jinja") {
+                continue;
+            }
+            let Some(stem) = path.file_stem().and_then(|t| t.to_str()) else {
+                continue;
+            };
+            let metadata = entry.metadata().ok();
+            files.push(TemplateFile {
+                name: format!("{prefix}{stem}"),
+                modified: metadata.as_ref().and_then(|t| t.modified().ok()),
+                size: metadata.map_or(0, |t| t.len()),
+                path,
+            });
+        }
+        subdirs
+    };
+
+    for subdir in visit(dir, "") {
+        if let Some(theme) = subdir.file_name().and_then(|t| t.to_str()) {
+            visit(&subdir, &format!("{theme}/"));
+        }
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    files
+}
+
+struct Loaded {
+    files: Vec<TemplateFile>,
+    scanned: Instant,
+    environment: Arc<Environment<'static>>,
+}
+
+/// The built-in templates and themes, overridden by the files in a directory
+/// which are reloaded whenever they change.
+pub struct Themes {
+    dir: Option<PathBuf>,
+    /// The directory is scanned for changes at most once per interval.
+    rescan_interval: Duration,
+    loaded: Mutex<Option<Loaded>>,
+}
+
+impl Themes {
+    /// Default of [`Themes::rescan_interval`].
+    pub const RESCAN_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Themes {
+            dir,
+            rescan_interval: Self::RESCAN_INTERVAL,
+            loaded: Mutex::new(None),
+        }
+    }
+
+    pub fn rescan_interval(mut self, interval: Duration) -> Self {
+        self.rescan_interval = interval;
+        self
+    }
+
+    fn load(&self, files: &[TemplateFile]) -> Environment<'static> {
+        let mut environment = base_environment();
+        for file in files {
+            let source = match fs::read_to_string(&file.path) {
+                Ok(t) => t,
+                Err(e) => {
+                    warn!("Cannot read template {}: {e}", file.path.display());
+                    continue;
+                }
+            };
+            // Checked aside first, as adding a broken template removes the
+            // built-in one of the same name.
+            let res = Environment::new()
+                .add_template_owned(file.name.clone(), source.clone())
+                .and_then(|_| environment.add_template_owned(file.name.clone(), source));
+            if let Err(e) = res {
+                warn!("Cannot load template {}: {e}", file.path.display());
+            }
+        }
+        environment
+    }
+
+    /// Templates in use, reloaded first if the directory changed.
+    pub fn environment(&self) -> Arc<Environment<'static>> {
+        let mut loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(t) = loaded.as_ref() {
+            if t.scanned.elapsed() < self.rescan_interval {
+                return t.environment.clone();
+            }
+        }
+
+        let files = self.dir.as_deref().map(scan).unwrap_or_default();
+        let scanned = Instant::now();
+        match loaded.as_mut() {
+            Some(t) if t.files == files => {
+                t.scanned = scanned;
+                t.environment.clone()
+            }
+            _ => {
+                if let Some(dir) = &self.dir {
+                    info!("Loading {} templates from {}", files.len(), dir.display());
+                }
+                let environment = Arc::new(self.load(&files));
+                *loaded = Some(Loaded {
+                    files,
+                    scanned,
+                    environment: environment.clone(),
+                });
+                environment
+            }
+        }
+    }
+
+    /// Render template `name` of `theme`, or the default one if the theme does
+    /// not have it.
+    pub fn render(
+        &self,
+        theme: Option<&str>,
+        name: &str,
+        ctx: impl Serialize,
+    ) -> anyhow::Result<String> {
+        let environment = self.environment();
+        let themed = theme.and_then(|t| environment.get_template(&format!("{t}/{name}")).ok());
+        let template = match themed {
+            Some(t) => t,
+            None => {
+                if let Some(theme) = theme {
+                    debug!("No template {name} in theme {theme}, using the default one");
+                }
+                environment.get_template(name)?
+            }
+        };
+        Ok(template.render(ctx)?)
+    }
+}
+
+lazy_static! {
+    /// Overridden by the files in `TEMPLATE_DIR` if set, checked for changes
+    /// every `TEMPLATE_RESCAN` seconds.
+    pub static ref THEMES: Themes =
+        Themes::new(env::var("TEMPLATE_DIR").ok().map(PathBuf::from)).rescan_interval(
+            env::var("TEMPLATE_RESCAN")
+                .ok()
+                .and_then(|t| t.parse().ok())
+                .map_or(Themes::RESCAN_INTERVAL, Duration::from_secs),
+        );
+}
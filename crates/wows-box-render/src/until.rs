@@ -0,0 +1,176 @@
+use std::path::Path;
+
+use anyhow::anyhow;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use wows_box::{lootbox::LootBoxRewardType, store::LootboxStore};
+use wows_box_rand::rand::UntilDistribution;
+
+use crate::{
+    cache::render_path,
+    icon::{IconCache, ICON_CACHE},
+    process::reward_to_imgs,
+    renderer::{Renderer, View, RENDERER},
+};
+
+/// Maximum amount of histogram bars.
+const HISTOGRAM_BUCKETS: u32 = 30;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UntilProp {
+    pub box_icon: String,
+    pub box_title: String,
+    pub target_icons: Vec<String>,
+    pub target_text: String,
+    pub stats: Vec<UntilStatProp>,
+    pub bars: Vec<UntilBarProp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UntilStatProp {
+    pub label: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UntilBarProp {
+    pub label: String,
+    pub count: u32,
+    /// Height relative to the highest bar, 0 ~ 100.
+    pub height: f64,
+}
+
+/// Labels: runs, mean, median, p90, p99, worst case, failed runs.
+fn until_labels(lang: &str) -> [&'static str; 7] {
+    match lang {
+        "zh-sg" => [
+            "模拟次数",
+            "平均",
+            "中位数",
+            "90%",
+            "99%",
+            "保底上限",
+            "未获得",
+        ],
+        _ => [
+            "Runs",
+            "Mean",
+            "Median",
+            "90th percentile",
+            "99th percentile",
+            "Worst case",
+            "Not obtained",
+        ],
+    }
+}
+
+/// Text summary of the distribution, one `label: value` per line.
+pub fn until_summary(lang: &str, dist: &UntilDistribution) -> Vec<UntilStatProp> {
+    let [runs, mean, median, p90, p99, worst, failed] = until_labels(lang);
+    let or_dash = |t: Option<u32>| t.map(|t| t.to_string()).unwrap_or("-".to_owned());
+
+    let mut stats = vec![
+        UntilStatProp {
+            label: runs.to_owned(),
+            value: dist.runs.to_string(),
+        },
+        UntilStatProp {
+            label: mean.to_owned(),
+            value: dist
+                .mean()
+                .map(|t| format!("{t:.1}"))
+                .unwrap_or("-".to_owned()),
+        },
+        UntilStatProp {
+            label: median.to_owned(),
+            value: or_dash(dist.median()),
+        },
+        UntilStatProp {
+            label: p90.to_owned(),
+            value: or_dash(dist.percentile(0.9)),
+        },
+        UntilStatProp {
+            label: p99.to_owned(),
+            value: or_dash(dist.percentile(0.99)),
+        },
+        UntilStatProp {
+            label: worst.to_owned(),
+            value: or_dash(dist.worst_case),
+        },
+    ];
+    if dist.failed > 0 {
+        stats.push(UntilStatProp {
+            label: failed.to_owned(),
+            value: dist.failed.to_string(),
+        });
+    }
+    stats
+}
+
+impl UntilProp {
+    pub async fn from_distribution(
+        lang: &str,
+        store: &impl LootboxStore,
+        box_id: u64,
+        target: LootBoxRewardType,
+        dist: &UntilDistribution,
+    ) -> anyhow::Result<Self> {
+        debug!("Fetching lootbox data...");
+        let box_data = store
+            .get_box(lang, box_id)
+            .await?
+            .ok_or(anyhow!("Cannot find lootbox {}", box_id))?;
+
+        let (target_text, target_icons) = reward_to_imgs(lang, store, target).await?;
+
+        let histogram = dist.histogram(HISTOGRAM_BUCKETS);
+        let highest = histogram.iter().map(|t| t.2).max().unwrap_or(1) as f64;
+        let bars = histogram
+            .into_iter()
+            .map(|(start, end, count)| UntilBarProp {
+                label: if start == end {
+                    start.to_string()
+                } else {
+                    format!("{start}-{end}")
+                },
+                count,
+                height: count as f64 / highest * 100.0,
+            })
+            .collect();
+
+        Ok(UntilProp {
+            box_icon: box_data.icon,
+            box_title: box_data.name,
+            target_icons,
+            target_text,
+            stats: until_summary(lang, dist),
+            bars,
+        })
+    }
+
+    /// Point every icon to its copy in `cache`.
+    pub async fn localize_icons(&mut self, cache: &IconCache) {
+        let urls = std::iter::once(&mut self.box_icon)
+            .chain(self.target_icons.iter_mut())
+            .collect();
+        cache.localize(urls).await;
+    }
+}
+
+pub async fn render_until_to_file(
+    lang: &str,
+    store: &impl LootboxStore,
+    key: u64,
+    target: LootBoxRewardType,
+    dist: &UntilDistribution,
+) -> anyhow::Result<String> {
+    let mut prop = UntilProp::from_distribution(lang, store, key, target, dist).await?;
+    prop.localize_icons(&ICON_CACHE).await;
+
+    let file_path = render_path()?;
+    RENDERER
+        .render(View::Until(&prop), None, Path::new(&file_path))
+        .await?;
+
+    Ok(file_path)
+}
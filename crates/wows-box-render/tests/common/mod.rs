@@ -0,0 +1,15 @@
+#![allow(dead_code)]
+
+use wows_box::lootbox::LootBoxRewardType;
+
+pub fn ship(id: u64) -> LootBoxRewardType {
+    LootBoxRewardType::Ship {
+        crew_level: None,
+        ship_level: 8,
+        id,
+        name: format!("Ship {id}"),
+        is_premium: true,
+        is_special: false,
+        icon: String::new(),
+    }
+}
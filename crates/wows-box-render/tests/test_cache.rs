@@ -0,0 +1,78 @@
+use std::{
+    env, fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use wows_box_render::cache::{CacheManager, CacheOptions, CachedFile};
+
+fn file(name: &str, size: u64, age: u64, now: SystemTime) -> CachedFile {
+    CachedFile {
+        path: PathBuf::from(name),
+        size,
+        modified: now - Duration::from_secs(age),
+    }
+}
+
+fn names(files: Vec<CachedFile>) -> Vec<String> {
+    files
+        .into_iter()
+        .map(|t| t.path.display().to_string())
+        .collect()
+}
+
+#[test]
+fn test_expired() {
+    let now = SystemTime::now();
+    let cache = CacheManager::new(
+        "/tmp",
+        CacheOptions {
+            ttl: Duration::from_secs(60),
+            max_size: 100,
+        },
+    );
+
+    let files = vec![
+        file("a", 10, 120, now),
+        file("b", 10, 30, now),
+        file("c", 10, 10, now),
+    ];
+    assert_eq!(names(cache.expired(files, now)), ["a"]);
+
+    // Over the budget, the oldest are removed even if they are fresh.
+    let files = vec![
+        file("a", 50, 30, now),
+        file("b", 40, 20, now),
+        file("c", 30, 10, now),
+    ];
+    assert_eq!(names(cache.expired(files, now)), ["a"]);
+
+    let files = vec![file("a", 10, 30, now)];
+    assert!(cache.expired(files, now).is_empty());
+}
+
+#[test]
+fn test_sweep() {
+    let dir = env::temp_dir().join("test_cache_sweep");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("icons")).unwrap();
+    fs::write(dir.join("render-a.png"), [0; 16]).unwrap();
+    fs::write(dir.join("other.png"), [0; 16]).unwrap();
+    fs::write(dir.join("icons").join("b.png"), [0; 16]).unwrap();
+
+    let cache = CacheManager::new(
+        &dir,
+        CacheOptions {
+            ttl: Duration::ZERO,
+            ..Default::default()
+        },
+    );
+    let report = cache.sweep().unwrap();
+    assert_eq!(report.removed, 1);
+    assert_eq!(report.freed, 16);
+    assert!(!dir.join("render-a.png").exists());
+    assert!(dir.join("other.png").exists());
+    assert!(dir.join("icons").join("b.png").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
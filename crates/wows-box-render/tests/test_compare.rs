@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use common::ship;
+use wows_box::{lootbox::LootBoxRewardType, valuation::ValuationTable};
+use wows_box_rand::analytic::{LootBoxExpectation, RewardExpectation};
+use wows_box_render::compare::{compare_stats, expected_currencies};
+
+mod common;
+
+fn reward(reward_type: LootBoxRewardType, is_unique: bool, expectation: f64) -> RewardExpectation {
+    RewardExpectation {
+        reward_type,
+        is_unique,
+        expectation,
+        variance: 0.0,
+        at_least_one: 1.0,
+    }
+}
+
+#[test]
+fn test_compare_stats() {
+    let expectations = [
+        LootBoxExpectation {
+            times: 10,
+            guaranteed_openings: 0,
+            rewards: vec![
+                reward(LootBoxRewardType::Gold, false, 1000.0),
+                reward(ship(1), true, 0.5),
+            ],
+            filler: Some(reward(LootBoxRewardType::Gold, false, 500.0)),
+            unique_at_least_one: 0.4,
+        },
+        LootBoxExpectation {
+            times: 10,
+            guaranteed_openings: 0,
+            rewards: vec![
+                reward(LootBoxRewardType::Credits, false, 30000.0),
+                reward(LootBoxRewardType::CollectionAlbum { id: 1 }, false, 2.0),
+            ],
+            filler: None,
+            unique_at_least_one: 0.0,
+        },
+    ];
+
+    // The filler is counted with the same currency.
+    let currencies = expected_currencies(&expectations[0]);
+    assert_eq!(currencies[&LootBoxRewardType::Gold], 1500.0);
+    assert!(!currencies.contains_key(&ship(1)));
+
+    let stats = compare_stats(
+        "en",
+        &expectations,
+        &ValuationTable::default(),
+        &HashMap::new(),
+    );
+    let values: Vec<_> = stats.iter().map(|t| t.values.clone()).collect();
+    assert_eq!(
+        values,
+        vec![
+            vec!["0.50", "0.00"],
+            vec!["0.50", "0.00"],
+            vec!["40.00%", "0.00%"],
+            // (1500 + 0.5 * 10000) / 10, 30000 / 3000 / 10 with an unvalued album.
+            vec!["650.0", "1.0*"],
+        ]
+    );
+}
@@ -0,0 +1,75 @@
+use std::{env, fs};
+
+use image::{ImageFormat, RgbaImage};
+use wows_box_render::{
+    cost::CostLineProp,
+    format::{encode_image, OutputFormat},
+    process::{LootBoxListProp, LootBoxListRewardProp},
+};
+
+fn reward(text: &str, amount: u32, is_guaranteed: bool) -> LootBoxListRewardProp {
+    LootBoxListRewardProp {
+        icons: vec![],
+        text: text.to_owned(),
+        amount,
+        precedence: (0, None),
+        is_guaranteed,
+        compensation: None,
+    }
+}
+
+#[test]
+fn test_text_summary() {
+    let mut prop = LootBoxListProp {
+        box_icon: "".to_owned(),
+        box_title: "Santa's Gift".to_owned(),
+        rewards: vec![reward("Credits", 50000, false), reward("Ship", 1, true)],
+        times: 2,
+        guarantee_text: "(Guaranteed)",
+        seed_code: Some("abc".to_owned()),
+        revision: Some("0000beef".to_owned()),
+        opened_rewards: None,
+        raw_text: "Opened",
+        opened_text: "Fully opened",
+        cost: vec![CostLineProp {
+            label: "Spent".to_owned(),
+            value: "1000 Doubloons".to_owned(),
+        }],
+    };
+    prop.rewards[1].compensation = Some("→ compensated as 100 Doubloons".to_owned());
+
+    assert_eq!(
+        prop.to_text(),
+        "Santa's Gift × 2\n\
+         Credits × 50000\n\
+         Ship × 1(Guaranteed) → compensated as 100 Doubloons\n\
+         Spent: 1000 Doubloons\n\
+         #abc @0000beef"
+    );
+
+    prop.opened_rewards = Some(vec![reward("Coal", 10, false)]);
+    prop.cost.clear();
+    prop.revision = None;
+    assert!(prop.to_text().ends_with("[Fully opened]\nCoal × 10\n#abc"));
+}
+
+#[test]
+fn test_encode_image() {
+    let dir = env::temp_dir();
+    for (format, extension, image_format) in [
+        (OutputFormat::Webp, "webp", ImageFormat::WebP),
+        (OutputFormat::Jpeg, "jpg", ImageFormat::Jpeg),
+    ] {
+        let path = dir.join(format!("test_encode_{extension}.png"));
+        RgbaImage::new(8, 8).save(&path).unwrap();
+
+        let target = encode_image(path.to_str().unwrap(), format).unwrap();
+        assert!(target.ends_with(extension));
+        assert!(!path.exists());
+        let data = fs::read(&target).unwrap();
+        assert_eq!(image::guess_format(&data).unwrap(), image_format);
+        fs::remove_file(target).unwrap();
+    }
+
+    assert_eq!(encode_image("a.png", OutputFormat::Png).unwrap(), "a.png");
+}
@@ -0,0 +1,45 @@
+use wows_box_render::{
+    icon::IconCache,
+    process::{LootBoxListProp, LootBoxListRewardProp},
+};
+
+#[test]
+fn test_icon_path() {
+    let cache = IconCache::new("/tmp/icons");
+    let path = cache.path_of("https://example.com/a/icon.png?v=1");
+
+    assert_eq!(path, cache.path_of("https://example.com/a/icon.png?v=1"));
+    assert_ne!(path, cache.path_of("https://example.com/b/icon.png?v=1"));
+    assert_eq!(path.extension().unwrap(), "png");
+    assert!(path.starts_with("/tmp/icons"));
+    assert_eq!(cache.path_of("https://example.com/icon").extension(), None);
+}
+
+#[tokio::test]
+async fn test_localize_local_icons() {
+    let cache = IconCache::new("/tmp/icons");
+    let mut prop = LootBoxListProp {
+        box_icon: "file:///tmp/box.png".to_owned(),
+        box_title: "Test".to_owned(),
+        rewards: vec![LootBoxListRewardProp {
+            icons: vec!["".to_owned(), "ship.png".to_owned()],
+            text: "Ship".to_owned(),
+            amount: 1,
+            precedence: (0, None),
+            is_guaranteed: false,
+            compensation: None,
+        }],
+        times: 1,
+        guarantee_text: "",
+        seed_code: None,
+        revision: None,
+        opened_rewards: None,
+        raw_text: "",
+        opened_text: "",
+        cost: vec![],
+    };
+    let expected = prop.clone();
+
+    prop.localize_icons(&cache).await;
+    assert_eq!(prop, expected);
+}
@@ -7,9 +7,9 @@ use mongodb::{
     Client, Collection,
 };
 use rand::{rngs::SmallRng, SeedableRng};
-use wows_box::lootbox::LootBox;
+use wows_box::{lootbox::LootBox, store::MongoStore};
 use wows_box_rand::rand::rand_multi;
-use wows_box_render::process::{LootBoxListProp, LOOTBOX_TEMPLATE};
+use wows_box_render::{process::LootBoxListProp, theme::THEMES};
 
 #[tokio::test]
 async fn test_rand_multi() -> anyhow::Result<()> {
@@ -30,19 +30,19 @@ async fn test_rand_multi() -> anyhow::Result<()> {
 
     let mut rng = SmallRng::from_entropy();
 
-    let resp = rand_multi(&mut rng, &lootbox, 100, &found, 0);
+    let resp = rand_multi(&mut rng, &lootbox, 100, &found, 0)?;
 
     println!("{:#?}", resp);
 
-    let list_prop = LootBoxListProp::from_result("zh-sg", &client, box_id, resp, 100).await?;
+    let list_prop =
+        LootBoxListProp::from_result("zh-sg", &MongoStore::new(client.clone()), box_id, resp, 100)
+            .await?;
 
     println!("{:#?}", list_prop);
 
-    LOOTBOX_TEMPLATE.render_to_write(
-        list_prop,
-        fs::File::create(
-            r"D:\WBH\rust\wows-rand-box\crates\wows-box-render\tests\lootbox.output.html",
-        )?,
+    fs::write(
+        r"D:\WBH\rust\wows-rand-box\crates\wows-box-render\tests\lootbox.output.html",
+        THEMES.render(None, "lootbox", list_prop)?,
     )?;
 
     Ok(())
@@ -1,3 +1,5 @@
+#![cfg(feature = "chrome")]
+
 use std::{env, fs};
 
 use dotenvy::dotenv;
@@ -7,12 +9,9 @@ use mongodb::{
     Client, Collection,
 };
 use rand::{rngs::SmallRng, SeedableRng};
-use wows_box::lootbox::LootBox;
+use wows_box::{lootbox::LootBox, store::MongoStore};
 use wows_box_rand::rand::rand_multi;
-use wows_box_render::{
-    html::render_html,
-    process::{LootBoxListProp, LOOTBOX_TEMPLATE},
-};
+use wows_box_render::{html::render_html, process::LootBoxListProp, theme::THEMES};
 
 #[tokio::test]
 async fn test_rand_multi_render() -> anyhow::Result<()> {
@@ -33,15 +32,15 @@ async fn test_rand_multi_render() -> anyhow::Result<()> {
 
     let mut rng = SmallRng::from_entropy();
 
-    let resp = rand_multi(&mut rng, &lootbox, 25, &found, 0);
+    let resp = rand_multi(&mut rng, &lootbox, 25, &found, 0)?;
 
-    let list_prop = LootBoxListProp::from_result("zh-sg", &client, box_id, resp, 25).await?;
+    let list_prop =
+        LootBoxListProp::from_result("zh-sg", &MongoStore::new(client.clone()), box_id, resp, 25)
+            .await?;
 
-    LOOTBOX_TEMPLATE.render_to_write(
-        list_prop,
-        fs::File::create(
-            r"D:\WBH\rust\wows-rand-box\crates\wows-box-render\tests\lootbox.output.html",
-        )?,
+    fs::write(
+        r"D:\WBH\rust\wows-rand-box\crates\wows-box-render\tests\lootbox.output.html",
+        THEMES.render(None, "lootbox", list_prop)?,
     )?;
 
     render_html(
@@ -0,0 +1,45 @@
+#![cfg(feature = "raster")]
+
+use std::{env, fs};
+
+use wows_box_render::{
+    process::{LootBoxListProp, LootBoxListRewardProp},
+    raster::RasterRenderer,
+    renderer::{Renderer, View},
+};
+
+#[tokio::test]
+async fn test_raster_list() -> anyhow::Result<()> {
+    let reward = |text: &str, amount| LootBoxListRewardProp {
+        icons: vec![],
+        text: text.to_owned(),
+        amount,
+        precedence: (0, None),
+        is_guaranteed: false,
+        compensation: None,
+    };
+    let prop = LootBoxListProp {
+        box_icon: String::new(),
+        box_title: "Test".to_owned(),
+        rewards: vec![reward("Credits", 1000), reward("Gold", 500)],
+        times: 10,
+        guarantee_text: "Guaranteed",
+        seed_code: Some("seed".to_owned()),
+        revision: None,
+        opened_rewards: None,
+        raw_text: "Opened",
+        opened_text: "Fully opened",
+        cost: vec![],
+    };
+
+    let target = env::temp_dir().join(format!("raster-{}.png", std::process::id()));
+    RasterRenderer::default()
+        .render(View::List(&prop), None, &target)
+        .await?;
+
+    let data = fs::read(&target)?;
+    fs::remove_file(&target)?;
+    assert!(data.starts_with(b"\x89PNG"));
+
+    Ok(())
+}
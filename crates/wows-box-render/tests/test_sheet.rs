@@ -0,0 +1,43 @@
+use wows_box_render::{
+    sheet::{probability_text, SheetListProp, SheetProp, SheetRewardProp, SheetSlotProp},
+    theme::THEMES,
+};
+
+#[test]
+fn test_probability_text() {
+    assert_eq!(probability_text(0.5), "50%");
+    assert_eq!(probability_text(0.0125), "1.25%");
+    assert_eq!(probability_text(0.001), "0.1%");
+    assert_eq!(probability_text(1.0), "100%");
+}
+
+#[test]
+fn test_render_sheet_template() {
+    let prop = SheetProp {
+        box_icon: "box.png".to_owned(),
+        box_title: "Test".to_owned(),
+        slots: vec![SheetSlotProp {
+            title: "Slot 1".to_owned(),
+            lists: vec![SheetListProp {
+                title: "Ships".to_owned(),
+                probability: "3%".to_owned(),
+                is_valuable: true,
+                has_unique_rewards: true,
+                rewards: vec![SheetRewardProp {
+                    icons: vec!["ship.png".to_owned()],
+                    text: "Ship".to_owned(),
+                    amount: 1,
+                    probability: "50%".to_owned(),
+                }],
+            }],
+        }],
+        notes: vec!["Guaranteed within openings: 40".to_owned()],
+        unique_text: "Unique",
+    };
+
+    let html = THEMES.render(None, "sheet", prop).unwrap();
+    assert!(html.contains(r#"class="list-title valuable""#));
+    assert!(html.contains("<span class=\"unique\">Unique</span>"));
+    assert!(html.contains("ship.png"));
+    assert!(html.contains("Guaranteed within openings: 40"));
+}
@@ -0,0 +1,37 @@
+use std::{env, fs};
+
+use rand::{rngs::SmallRng, SeedableRng};
+use wows_box::{
+    snapshot::Snapshot,
+    store::{FileStore, LootboxStore},
+};
+use wows_box_rand::rand::rand_multi;
+use wows_box_render::process::LootBoxListProp;
+
+/// Lootbox data for tests without a database.
+const FIXTURE: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../wows-box/tests/fixtures/snapshot.jsonl"
+);
+
+#[tokio::test]
+async fn test_rand_multi_offline() -> anyhow::Result<()> {
+    let dir = env::temp_dir().join(format!("snapshot-process-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = FileStore::new(&dir);
+    Snapshot::load(FIXTURE)?.import(&store).await?;
+
+    let lootbox = store.get_box("en", 1).await?.unwrap();
+    let mut rng = SmallRng::seed_from_u64(0);
+    let resp = rand_multi(&mut rng, &lootbox, 20, &[], 0)?;
+
+    let list_prop = LootBoxListProp::from_result("en", &store, 1, resp, 20).await?;
+    assert_eq!(list_prop.box_title, "Test Container");
+    // Ten openings reach the guarantee.
+    assert!(list_prop.rewards.iter().any(|t| t.text.contains("Ship")));
+    // Icons of items and currencies come from the store.
+    assert!(list_prop.rewards.iter().all(|t| !t.icons.is_empty()));
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use std::{env, fs, time::Duration};
+
+use wows_box_render::{
+    process::{LootBoxListProp, LootBoxListRewardProp},
+    theme::Themes,
+};
+
+fn prop() -> LootBoxListProp {
+    LootBoxListProp {
+        box_icon: "box.png".to_owned(),
+        box_title: "Test".to_owned(),
+        rewards: (0..4)
+            .map(|t| LootBoxListRewardProp {
+                icons: vec![],
+                text: format!("Reward {t}"),
+                amount: 1,
+                precedence: (0, None),
+                is_guaranteed: false,
+                compensation: None,
+            })
+            .collect(),
+        times: 4,
+        guarantee_text: "",
+        seed_code: Some("abc".to_owned()),
+        revision: None,
+        opened_rewards: None,
+        raw_text: "",
+        opened_text: "",
+        cost: vec![],
+    }
+}
+
+fn rows(html: &str) -> usize {
+    html.matches(r#"<tr class="reward">"#).count()
+}
+
+#[test]
+fn test_builtin_themes() {
+    let themes = Themes::new(None);
+
+    let default = themes.render(None, "lootbox", prop()).unwrap();
+    assert_eq!(rows(&default), 2);
+    assert!(!default.contains("#1d1e24"));
+
+    let dark = themes.render(Some("dark"), "lootbox", prop()).unwrap();
+    assert_eq!(rows(&dark), 2);
+    assert!(dark.contains("#1d1e24"));
+
+    let mobile = themes.render(Some("mobile"), "lootbox", prop()).unwrap();
+    assert_eq!(rows(&mobile), 4);
+    assert!(mobile.contains(r#"colspan="2""#));
+
+    let unknown = themes.render(Some("unknown"), "lootbox", prop()).unwrap();
+    assert_eq!(unknown, default);
+}
+
+#[test]
+fn test_template_dir() {
+    let dir = env::temp_dir().join("test_theme_dir");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("plain")).unwrap();
+    let themes = Themes::new(Some(dir.clone())).rescan_interval(Duration::ZERO);
+    let cached = Themes::new(Some(dir.clone())).rescan_interval(Duration::from_secs(3600));
+    assert_eq!(rows(&cached.render(None, "lootbox", prop()).unwrap()), 2);
+
+    fs::write(dir.join("plain").join("lootbox.jinja"), "{{ box_title }}").unwrap();
+    assert_eq!(
+        themes.render(Some("plain"), "lootbox", prop()).unwrap(),
+        "Test"
+    );
+
+    // Reloaded after a change, and broken files are skipped.
+    fs::write(dir.join("plain").join("lootbox.jinja"), "{{ times }} times").unwrap();
+    fs::write(dir.join("lootbox.jinja"), "{% if %}").unwrap();
+    assert_eq!(
+        themes.render(Some("plain"), "lootbox", prop()).unwrap(),
+        "4 times"
+    );
+    assert_eq!(rows(&themes.render(None, "lootbox", prop()).unwrap()), 2);
+
+    // Not rescanned within the interval.
+    assert_ne!(
+        cached.render(Some("plain"), "lootbox", prop()).unwrap(),
+        "4 times"
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
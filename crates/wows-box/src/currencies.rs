@@ -19,7 +19,7 @@ pub enum CurrencyType {
     Coal,
     /// 军团代币
     Molybdenum,
-    ///  
+    /// (unnamed)
     Brass,
     /// 网站筹码
     Saltpeter,
@@ -63,7 +63,7 @@ pub enum CurrencyType {
     Eventum2,
     /// 战斗代币
     Clientum1,
-    ///  
+    /// (unnamed)
     Clientum2,
     /// 石油
     ClanResource,
@@ -235,7 +235,7 @@ impl CurrencyType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CurrencyData {
     pub r#type: CurrencyType,
     pub name: String,
@@ -0,0 +1,310 @@
+use std::{fmt, mem};
+
+use crate::lootbox::{LootBox, LootBoxFiller, LootBoxRewardList, LootBoxRewardType};
+
+/// A difference found by [`LootBox::diff`], in terms players care about.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LootBoxChange {
+    Renamed {
+        from: String,
+        to: String,
+    },
+    SlotAdded {
+        slot: usize,
+    },
+    SlotRemoved {
+        slot: usize,
+    },
+    ListAdded {
+        slot: usize,
+        list: String,
+        probability: f64,
+    },
+    ListRemoved {
+        slot: usize,
+        list: String,
+    },
+    ListProbability {
+        slot: usize,
+        list: String,
+        from: f64,
+        to: f64,
+    },
+    RewardAdded {
+        slot: usize,
+        list: String,
+        reward: String,
+        probability: f64,
+    },
+    RewardRemoved {
+        slot: usize,
+        list: String,
+        reward: String,
+    },
+    RewardProbability {
+        slot: usize,
+        list: String,
+        reward: String,
+        from: f64,
+        to: f64,
+    },
+    RewardAmount {
+        slot: usize,
+        list: String,
+        reward: String,
+        from: u32,
+        to: u32,
+    },
+    /// The same reward with other details, such as its name or icon.
+    RewardDetails {
+        slot: usize,
+        list: String,
+        reward: String,
+    },
+    Filler {
+        from: String,
+        to: String,
+    },
+    SavePoint {
+        from: Option<u32>,
+        to: Option<u32>,
+    },
+    Retired,
+    Restored,
+    /// Anything else, such as icons or ship data.
+    Details,
+}
+
+/// Short name of a reward for humans.
+pub fn reward_label(reward: &LootBoxRewardType) -> String {
+    if let Some(name) = reward.get_name() {
+        return name.to_owned();
+    }
+    if let Some(currency) = reward.as_currency() {
+        return currency.as_icon_name().to_owned();
+    }
+    match reward.get_id() {
+        Some(id) => format!("#{id}"),
+        None => format!("{reward:?}"),
+    }
+}
+
+fn filler_label(filler: &Option<LootBoxFiller>) -> String {
+    match filler {
+        Some(t) => format!("{} × {}", reward_label(&t.filler), t.amount),
+        None => "none".to_owned(),
+    }
+}
+
+fn option_label(value: &Option<u32>) -> String {
+    match value {
+        Some(t) => t.to_string(),
+        None => "none".to_owned(),
+    }
+}
+
+/// Rewards are told apart by their kind and id, so a renamed ship is still
+/// the same ship.
+fn same_reward(a: &LootBoxRewardType, b: &LootBoxRewardType) -> bool {
+    mem::discriminant(a) == mem::discriminant(b) && a.get_id() == b.get_id()
+}
+
+/// Lists of a slot are told apart by their names.
+fn diff_lists(
+    slot: usize,
+    old: &[LootBoxRewardList],
+    new: &[LootBoxRewardList],
+    changes: &mut Vec<LootBoxChange>,
+) {
+    for old_list in old {
+        let list = old_list.name.clone();
+        let Some(new_list) = new.iter().find(|t| t.name == old_list.name) else {
+            changes.push(LootBoxChange::ListRemoved { slot, list });
+            continue;
+        };
+        if old_list.probability != new_list.probability {
+            changes.push(LootBoxChange::ListProbability {
+                slot,
+                list: list.clone(),
+                from: old_list.probability,
+                to: new_list.probability,
+            });
+        }
+        for old_reward in old_list.rewards.iter() {
+            let reward = reward_label(&old_reward.reward);
+            let Some(new_reward) = new_list
+                .rewards
+                .iter()
+                .find(|t| same_reward(&t.reward, &old_reward.reward))
+            else {
+                changes.push(LootBoxChange::RewardRemoved {
+                    slot,
+                    list: list.clone(),
+                    reward,
+                });
+                continue;
+            };
+            if old_reward.probability != new_reward.probability {
+                changes.push(LootBoxChange::RewardProbability {
+                    slot,
+                    list: list.clone(),
+                    reward: reward.clone(),
+                    from: old_reward.probability,
+                    to: new_reward.probability,
+                });
+            }
+            if old_reward.amount != new_reward.amount {
+                changes.push(LootBoxChange::RewardAmount {
+                    slot,
+                    list: list.clone(),
+                    reward: reward.clone(),
+                    from: old_reward.amount,
+                    to: new_reward.amount,
+                });
+            }
+            if old_reward.reward != new_reward.reward {
+                changes.push(LootBoxChange::RewardDetails {
+                    slot,
+                    list: list.clone(),
+                    reward,
+                });
+            }
+        }
+        for new_reward in new_list.rewards.iter() {
+            if !old_list
+                .rewards
+                .iter()
+                .any(|t| same_reward(&t.reward, &new_reward.reward))
+            {
+                changes.push(LootBoxChange::RewardAdded {
+                    slot,
+                    list: list.clone(),
+                    reward: reward_label(&new_reward.reward),
+                    probability: new_reward.probability,
+                });
+            }
+        }
+    }
+    for new_list in new {
+        if !old.iter().any(|t| t.name == new_list.name) {
+            changes.push(LootBoxChange::ListAdded {
+                slot,
+                list: new_list.name.clone(),
+                probability: new_list.probability,
+            });
+        }
+    }
+}
+
+impl LootBox {
+    /// What changed from `self` to `new`, empty if they are the same.
+    pub fn diff(&self, new: &LootBox) -> Vec<LootBoxChange> {
+        let mut changes = vec![];
+        if self.name != new.name {
+            changes.push(LootBoxChange::Renamed {
+                from: self.name.clone(),
+                to: new.name.clone(),
+            });
+        }
+        for (slot, (old_slot, new_slot)) in self.slots.iter().zip(new.slots.iter()).enumerate() {
+            diff_lists(slot, &old_slot.common, &new_slot.common, &mut changes);
+            diff_lists(slot, &old_slot.valuable, &new_slot.valuable, &mut changes);
+        }
+        for slot in new.slots.len()..self.slots.len() {
+            changes.push(LootBoxChange::SlotRemoved { slot });
+        }
+        for slot in self.slots.len()..new.slots.len() {
+            changes.push(LootBoxChange::SlotAdded { slot });
+        }
+        if self.filler != new.filler {
+            changes.push(LootBoxChange::Filler {
+                from: filler_label(&self.filler),
+                to: filler_label(&new.filler),
+            });
+        }
+        if self.save_point != new.save_point {
+            changes.push(LootBoxChange::SavePoint {
+                from: self.save_point,
+                to: new.save_point,
+            });
+        }
+        match (self.retired, new.retired) {
+            (false, true) => changes.push(LootBoxChange::Retired),
+            (true, false) => changes.push(LootBoxChange::Restored),
+            _ => {}
+        }
+        if changes.is_empty() && self != new {
+            changes.push(LootBoxChange::Details);
+        }
+        changes
+    }
+}
+
+impl fmt::Display for LootBoxChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Renamed { from, to } => write!(f, "renamed from `{from}` to `{to}`"),
+            Self::SlotAdded { slot } => write!(f, "slot {slot} added"),
+            Self::SlotRemoved { slot } => write!(f, "slot {slot} removed"),
+            Self::ListAdded {
+                slot,
+                list,
+                probability,
+            } => write!(f, "list `{list}` added to slot {slot} at {probability}"),
+            Self::ListRemoved { slot, list } => {
+                write!(f, "list `{list}` removed from slot {slot}")
+            }
+            Self::ListProbability {
+                slot,
+                list,
+                from,
+                to,
+            } => write!(f, "list `{list}` of slot {slot}: {from} → {to}"),
+            Self::RewardAdded {
+                slot,
+                list,
+                reward,
+                probability,
+            } => write!(
+                f,
+                "{reward} added to list `{list}` of slot {slot} at {probability}"
+            ),
+            Self::RewardRemoved { slot, list, reward } => {
+                write!(f, "{reward} removed from list `{list}` of slot {slot}")
+            }
+            Self::RewardProbability {
+                slot,
+                list,
+                reward,
+                from,
+                to,
+            } => write!(f, "{reward} in list `{list}` of slot {slot}: {from} → {to}"),
+            Self::RewardAmount {
+                slot,
+                list,
+                reward,
+                from,
+                to,
+            } => write!(
+                f,
+                "{reward} in list `{list}` of slot {slot}: amount {from} → {to}"
+            ),
+            Self::RewardDetails { slot, list, reward } => {
+                write!(
+                    f,
+                    "{reward} in list `{list}` of slot {slot}: details changed"
+                )
+            }
+            Self::Filler { from, to } => write!(f, "filler: {from} → {to}"),
+            Self::SavePoint { from, to } => write!(
+                f,
+                "guarantee: {} → {}",
+                option_label(from),
+                option_label(to)
+            ),
+            Self::Retired => write!(f, "retired"),
+            Self::Restored => write!(f, "listed again"),
+            Self::Details => write!(f, "details changed"),
+        }
+    }
+}
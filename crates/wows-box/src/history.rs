@@ -0,0 +1,173 @@
+//! Every version of a lootbox the updater has seen.
+//!
+//! Times are Unix seconds, dates are in UTC.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{lootbox::LootBox, store::LootboxStore};
+
+const DAY: u64 = 24 * 60 * 60;
+/// Years [`parse_date`] accepts, so the day arithmetic cannot overflow.
+const YEARS: std::ops::RangeInclusive<i64> = 1970..=9999;
+
+/// A lootbox as it was listed from `valid_from` until `valid_until`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LootBoxRevision {
+    pub box_id: u64,
+    pub valid_from: u64,
+    /// Still listed if not given.
+    pub valid_until: Option<u64>,
+    pub data: LootBox,
+}
+
+impl LootBoxRevision {
+    pub fn contains(&self, at: u64) -> bool {
+        self.valid_from <= at && self.valid_until.is_none_or(|t| at < t)
+    }
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// The last second of `YYYY`, `YYYY-MM` or `YYYY-MM-DD`, so "2023" means the
+/// lootbox as it was by the end of 2023. Years out of 1970 to 9999 are refused.
+pub fn parse_date(text: &str) -> Option<u64> {
+    let parts = text
+        .trim()
+        .split('-')
+        .map(|t| t.parse::<i64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    if !YEARS.contains(parts.first()?) {
+        return None;
+    }
+    let next = match parts[..] {
+        [year] => days_from_civil(year + 1, 1, 1),
+        [year, 12] => days_from_civil(year + 1, 1, 1),
+        [year, month] if (1..12).contains(&month) => days_from_civil(year, month + 1, 1),
+        [year, month, day] if (1..=12).contains(&month) && (1..=31).contains(&day) => {
+            let next_month = match month {
+                12 => days_from_civil(year + 1, 1, 1),
+                _ => days_from_civil(year, month + 1, 1),
+            };
+            let days = days_from_civil(year, month, day) + 1;
+            if days > next_month {
+                return None;
+            }
+            days
+        }
+        _ => return None,
+    };
+    let next = u64::try_from(next).ok()?;
+    next.checked_mul(DAY)?.checked_sub(1)
+}
+
+/// `YYYY-MM-DD` of a time.
+pub fn date_text(at: u64) -> String {
+    let (year, month, day) = civil_from_days((at / DAY) as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// The revision of `history` listed at `at`.
+pub fn revision_at(history: &[LootBoxRevision], at: u64) -> Option<&LootBoxRevision> {
+    history.iter().rev().find(|t| t.contains(at))
+}
+
+/// The lootbox as it was listed at `at`.
+pub async fn box_at(
+    store: &impl LootboxStore,
+    lang: &str,
+    box_id: u64,
+    at: u64,
+) -> anyhow::Result<Option<LootBox>> {
+    let history = store.list_history(lang, box_id).await?;
+    Ok(revision_at(&history, at).map(|t| t.data.clone()))
+}
+
+/// Note that `lootbox` is listed as of `now`, returning whether the history
+/// changed.
+///
+/// A new revision is started when the contents changed, and the current one
+/// ends when the lootbox is retired.
+pub async fn record(
+    store: &impl LootboxStore,
+    lang: &str,
+    lootbox: &LootBox,
+    now: u64,
+) -> anyhow::Result<bool> {
+    Ok(record_all(store, lang, std::slice::from_ref(lootbox), now).await? > 0)
+}
+
+/// [`record`] every lootbox of `lootboxes`, writing the history once.
+/// Returns how many of their histories changed.
+pub async fn record_all(
+    store: &impl LootboxStore,
+    lang: &str,
+    lootboxes: &[LootBox],
+    now: u64,
+) -> anyhow::Result<usize> {
+    let mut revisions = vec![];
+    let mut changed = 0;
+    for lootbox in lootboxes {
+        let history = store.list_history(lang, lootbox.id).await?;
+        let revised = revise(&history, lootbox, now);
+        if !revised.is_empty() {
+            changed += 1;
+            revisions.extend(revised);
+        }
+    }
+    store.upsert_histories(lang, &revisions).await?;
+    Ok(changed)
+}
+
+/// Revisions to write for `lootbox` listed as of `now`, see [`record`].
+fn revise(history: &[LootBoxRevision], lootbox: &LootBox, now: u64) -> Vec<LootBoxRevision> {
+    let mut revisions = vec![];
+    let current = history.iter().find(|t| t.valid_until.is_none());
+    if let Some(current) = current {
+        if !lootbox.retired && current.data.revision() == lootbox.revision() {
+            return revisions;
+        }
+        revisions.push(LootBoxRevision {
+            valid_until: Some(now),
+            ..current.clone()
+        });
+    }
+    if !lootbox.retired {
+        revisions.push(LootBoxRevision {
+            box_id: lootbox.id,
+            valid_from: now,
+            valid_until: None,
+            data: lootbox.clone(),
+        });
+    }
+    revisions
+}
@@ -1,3 +1,11 @@
 pub mod currencies;
+pub mod diff;
+pub mod history;
 pub mod item;
 pub mod lootbox;
+pub mod price;
+pub mod snapshot;
+pub mod store;
+pub mod user;
+pub mod validate;
+pub mod valuation;
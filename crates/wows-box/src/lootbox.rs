@@ -2,6 +2,8 @@ use std::num::NonZeroU8;
 
 use serde::{Deserialize, Serialize};
 
+use crate::currencies::CurrencyType;
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LootBox {
@@ -17,6 +19,34 @@ pub struct LootBox {
     pub filler: Option<LootBoxFiller>,
     /// guarantee
     pub save_point: Option<u32>,
+    /// No longer listed on the official website.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub retired: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl LootBox {
+    /// Stable hash of the whole lootbox data, to tell whether it has changed.
+    pub fn revision(&self) -> u64 {
+        // Retiring a lootbox does not change its contents.
+        if self.retired {
+            return LootBox {
+                retired: false,
+                ..self.clone()
+            }
+            .revision();
+        }
+        // FNV-1a, so the value does not depend on the std hasher.
+        bson::to_vec(self)
+            .unwrap_or_default()
+            .into_iter()
+            .fold(0xcbf29ce484222325, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -263,6 +293,68 @@ impl LootBoxRewardType {
         }
     }
 
+    /// Name embedded in the reward data, if any.
+    ///
+    /// Currencies and items only carry their ids, their names are stored separately.
+    pub fn get_name(&self) -> Option<&str> {
+        match self {
+            Self::Signal { name, .. }
+            | Self::Ship { name, .. }
+            | Self::Skin { name, .. }
+            | Self::Camouflage { name, .. }
+            | Self::Permoflage { name, .. }
+            | Self::Mskin { name, .. }
+            | Self::Crew { name, .. }
+            | Self::Multiboost { name, .. }
+            | Self::Ensign { name, .. }
+            | Self::Lootbox { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// The currency this reward is, if any.
+    pub fn as_currency(&self) -> Option<CurrencyType> {
+        match self {
+            Self::Credits => Some(CurrencyType::Credits),
+            Self::Gold => Some(CurrencyType::Gold),
+            Self::FreeXp => Some(CurrencyType::FreeXp),
+            Self::EliteXp => Some(CurrencyType::EliteXp),
+            Self::ParagonXp => Some(CurrencyType::ParagonXp),
+            Self::Steel => Some(CurrencyType::Steel),
+            Self::Coal => Some(CurrencyType::Coal),
+            Self::Molybdenum => Some(CurrencyType::Molybdenum),
+            Self::Brass => Some(CurrencyType::Brass),
+            Self::Saltpeter => Some(CurrencyType::Saltpeter),
+            Self::RecruitmentPoints => Some(CurrencyType::RecruitmentPoints),
+            Self::Eventum3 => Some(CurrencyType::Eventum3),
+            Self::Eventum4 => Some(CurrencyType::Eventum4),
+            Self::Eventum5 => Some(CurrencyType::Eventum5),
+            Self::Eventum6 => Some(CurrencyType::Eventum6),
+            Self::Eventum7 => Some(CurrencyType::Eventum7),
+            Self::Eventum8 => Some(CurrencyType::Eventum8),
+            Self::Eventum9 => Some(CurrencyType::Eventum9),
+            Self::Eventum10 => Some(CurrencyType::Eventum10),
+            Self::EventumCn => Some(CurrencyType::EventumCn),
+            Self::Santium => Some(CurrencyType::Santium),
+            Self::Dockyardum1 => Some(CurrencyType::Dockyardum1),
+            Self::Dockyardum2 => Some(CurrencyType::Dockyardum2),
+            Self::Eventum11 => Some(CurrencyType::Eventum11),
+            Self::Eventum12 => Some(CurrencyType::Eventum12),
+            Self::Eventum13 => Some(CurrencyType::Eventum13),
+            Self::Eventum14 => Some(CurrencyType::Eventum14),
+            Self::Eventum1 => Some(CurrencyType::Eventum1),
+            Self::Eventum2 => Some(CurrencyType::Eventum2),
+            Self::Clientum1 => Some(CurrencyType::Clientum1),
+            Self::Clientum2 => Some(CurrencyType::Clientum2),
+            Self::ClanResource => Some(CurrencyType::ClanResource),
+
+            // do not edit following
+            Self::WowsPremium => Some(CurrencyType::WowsPremium),
+            Self::Slots => Some(CurrencyType::Slots),
+            _ => None,
+        }
+    }
+
     pub fn as_precedence(&self) -> (u32, Option<u64>) {
         match self {
             Self::Lootbox { id, .. } => (0, Some(*id)),
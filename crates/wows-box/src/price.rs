@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{item::ItemData, lootbox::LootBoxRewardType, valuation::ValuationTable};
+
+/// Prices of a lootbox, stored in the `prices` collection next to `list`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceData {
+    pub box_id: u64,
+    pub bundles: Vec<PriceBundle>,
+}
+
+/// A way to buy `amount` lootboxes at once.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceBundle {
+    pub amount: u32,
+    /// Price in doubloons, if sold for doubloons.
+    pub gold: Option<u32>,
+    /// Prices in real currency.
+    #[serde(default)]
+    pub real: Vec<RegionalPrice>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegionalPrice {
+    /// e.g. `cn`, `eu`.
+    pub region: String,
+    /// ISO 4217 code, e.g. `CNY`.
+    pub currency: String,
+    pub price: f64,
+}
+
+/// What a number of openings cost and what the rewards are worth.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostReport {
+    pub times: u32,
+    /// Doubloons spent, if the lootbox is sold for doubloons.
+    pub gold: Option<u32>,
+    /// Real currency spent, one per region.
+    pub real: Vec<RegionalPrice>,
+    /// Doubloon value of the rewards.
+    pub value: f64,
+    /// Reward kinds without a known value, not counted in `value`.
+    pub unvalued: u32,
+}
+
+impl CostReport {
+    /// Reward value per doubloon spent.
+    pub fn ratio(&self) -> Option<f64> {
+        self.gold
+            .filter(|&t| t > 0)
+            .map(|gold| self.value / gold as f64)
+    }
+}
+
+impl PriceData {
+    /// Cheapest total of `price` to buy at least `times` lootboxes.
+    ///
+    /// Some cheapest purchase has less than `a` bundles other than the one
+    /// with the lowest unit price, `a` being its amount: any `a` of them
+    /// contain a few whose amounts add up to a multiple of `a`, which that
+    /// bundle buys for less. So only the last `a × largest amount` lootboxes
+    /// need a search, the rest are bought in that bundle.
+    fn cheapest(&self, times: u32, price: impl Fn(&PriceBundle) -> Option<f64>) -> Option<f64> {
+        let bundles: Vec<_> = self
+            .bundles
+            .iter()
+            .filter(|t| t.amount > 0)
+            .filter_map(|t| price(t).map(|p| (t.amount as u64, p)))
+            .collect();
+        let &(best_amount, best_price) = bundles
+            .iter()
+            .min_by(|a, b| (a.1 / a.0 as f64).total_cmp(&(b.1 / b.0 as f64)))?;
+        let largest = bundles.iter().map(|t| t.0).max()?;
+
+        let times = times as u64;
+        let window = (best_amount.saturating_mul(largest)).min(times) as usize;
+        // Cheapest total for at least `n` lootboxes, `n` within the window.
+        let mut cost = vec![0.0; window + 1];
+        for n in 1..=window {
+            cost[n] = bundles
+                .iter()
+                .map(|&(amount, price)| cost[n.saturating_sub(amount as usize)] + price)
+                .fold(f64::INFINITY, f64::min);
+        }
+
+        // Fewest bundles with the lowest unit price which leave the rest
+        // within the window, and every count up to buying only those.
+        let first = (times - window as u64).div_ceil(best_amount);
+        let last = times.div_ceil(best_amount);
+        (first..=last)
+            .map(|count| {
+                let rest = times.saturating_sub(count * best_amount) as usize;
+                count as f64 * best_price + cost[rest]
+            })
+            .min_by(f64::total_cmp)
+    }
+
+    pub fn gold_cost(&self, times: u32) -> Option<u32> {
+        self.cheapest(times, |t| t.gold.map(|g| g as f64))
+            .map(|t| t.round() as u32)
+    }
+
+    /// Real currency cost in every region some bundle has a price for.
+    pub fn real_cost(&self, times: u32) -> Vec<RegionalPrice> {
+        let mut regions: Vec<_> = self
+            .bundles
+            .iter()
+            .flat_map(|t| t.real.iter())
+            .map(|t| (t.region.clone(), t.currency.clone()))
+            .collect();
+        regions.sort_unstable();
+        regions.dedup();
+
+        regions
+            .into_iter()
+            .filter_map(|(region, currency)| {
+                let price = self.cheapest(times, |t| {
+                    t.real
+                        .iter()
+                        .find(|p| p.region == region && p.currency == currency)
+                        .map(|p| p.price)
+                })?;
+                Some(RegionalPrice {
+                    region,
+                    currency,
+                    price,
+                })
+            })
+            .collect()
+    }
+
+    /// `rewards` is the result of opening the lootbox `times` times, `items`
+    /// the data of the items in it.
+    pub fn cost_report(
+        &self,
+        times: u32,
+        rewards: &HashMap<(LootBoxRewardType, bool), u32>,
+        items: &HashMap<u64, ItemData>,
+        valuation: &ValuationTable,
+    ) -> CostReport {
+        let (value, unvalued) = valuation.total(rewards, items);
+
+        CostReport {
+            times,
+            gold: self.gold_cost(times),
+            real: self.real_cost(times),
+            value,
+            unvalued,
+        }
+    }
+}
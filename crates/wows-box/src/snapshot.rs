@@ -0,0 +1,213 @@
+//! A whole copy of the lootbox data in one file, to move it between stores.
+//!
+//! The file is JSON lines, the [`SnapshotManifest`] first and then one
+//! [`SnapshotRecord`] per document.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::{currencies::CurrencyData, item::ItemData, lootbox::LootBox, store::LootboxStore};
+
+/// Bumped whenever the layout of the file changes.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotManifest {
+    pub version: u32,
+    /// Unix time the data was read out of the store.
+    pub fetched_at: u64,
+    /// Asset version of the official website the data was fetched with.
+    pub wows_web_version: Option<String>,
+    pub langs: Vec<String>,
+    /// Amount of records following the manifest.
+    pub records: usize,
+}
+
+/// A single document of a language.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "collection", content = "data", rename_all = "kebab-case")]
+pub enum SnapshotData {
+    List(Box<LootBox>),
+    Items(ItemData),
+    Currencies(CurrencyData),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotRecord {
+    pub lang: String,
+    #[serde(flatten)]
+    pub data: SnapshotData,
+}
+
+/// Documents of a language.
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotLang {
+    pub boxes: Vec<LootBox>,
+    pub items: Vec<ItemData>,
+    pub currencies: Vec<CurrencyData>,
+}
+
+impl SnapshotLang {
+    fn len(&self) -> usize {
+        self.boxes.len() + self.items.len() + self.currencies.len()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub manifest: SnapshotManifest,
+    pub langs: BTreeMap<String, SnapshotLang>,
+}
+
+impl Snapshot {
+    /// Read the lootboxes, items and currencies of `langs` out of `store`.
+    ///
+    /// Documents the store leaves out of its lists are lost, use a strict
+    /// store like [`crate::store::MongoStore::strict`] to fail instead.
+    pub async fn export(
+        store: &impl LootboxStore,
+        langs: &[&str],
+        wows_web_version: Option<String>,
+    ) -> anyhow::Result<Snapshot> {
+        let fetched_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut data = BTreeMap::new();
+        for lang in langs {
+            let snapshot_lang = SnapshotLang {
+                boxes: store.list_boxes(lang).await?,
+                items: store.list_items(lang).await?,
+                currencies: store.list_currencies(lang).await?,
+            };
+            data.insert(lang.to_string(), snapshot_lang);
+        }
+
+        Ok(Snapshot {
+            manifest: SnapshotManifest {
+                version: SNAPSHOT_VERSION,
+                fetched_at,
+                wows_web_version,
+                langs: langs.iter().map(|t| t.to_string()).collect(),
+                records: data.values().map(SnapshotLang::len).sum(),
+            },
+            langs: data,
+        })
+    }
+
+    /// Write every document into `store`, replacing the ones with the same
+    /// ids and deleting the ones the snapshot lacks, and set its languages.
+    pub async fn import(&self, store: &impl LootboxStore) -> anyhow::Result<()> {
+        for (lang, data) in self.langs.iter() {
+            store.upsert_boxes(lang, &data.boxes).await?;
+            store.upsert_items(lang, &data.items).await?;
+            store.upsert_currencies(lang, &data.currencies).await?;
+
+            let ids: Vec<_> = data.boxes.iter().map(|t| t.id).collect();
+            store.retain_boxes(lang, &ids).await?;
+            let ids: Vec<_> = data.items.iter().map(|t| t.id).collect();
+            store.retain_items(lang, &ids).await?;
+            let types: Vec<_> = data.currencies.iter().map(|t| t.r#type).collect();
+            store.retain_currencies(lang, &types).await?;
+        }
+        let langs: Vec<_> = self.manifest.langs.iter().map(String::as_str).collect();
+        store.set_languages(&langs).await
+    }
+
+    pub fn write(&self, mut writer: impl Write) -> anyhow::Result<()> {
+        serde_json::to_writer(&mut writer, &self.manifest)?;
+        writeln!(writer)?;
+        for (lang, data) in self.langs.iter() {
+            let records = data
+                .boxes
+                .iter()
+                .map(|t| SnapshotData::List(Box::new(t.clone())))
+                .chain(data.items.iter().cloned().map(SnapshotData::Items))
+                .chain(
+                    data.currencies
+                        .iter()
+                        .cloned()
+                        .map(SnapshotData::Currencies),
+                );
+            for data in records {
+                let record = SnapshotRecord {
+                    lang: lang.clone(),
+                    data,
+                };
+                serde_json::to_writer(&mut writer, &record)?;
+                writeln!(writer)?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn read(reader: impl BufRead) -> anyhow::Result<Snapshot> {
+        let mut lines = reader.lines();
+        let manifest: SnapshotManifest = match lines.next() {
+            Some(line) => serde_json::from_str(&line?)?,
+            None => return Err(anyhow!("Empty snapshot")),
+        };
+        if manifest.version != SNAPSHOT_VERSION {
+            return Err(anyhow!(
+                "Unsupported snapshot version {}, expected {}",
+                manifest.version,
+                SNAPSHOT_VERSION
+            ));
+        }
+
+        let mut langs: BTreeMap<_, SnapshotLang> = manifest
+            .langs
+            .iter()
+            .map(|t| (t.clone(), Default::default()))
+            .collect();
+        let mut records = 0;
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: SnapshotRecord = serde_json::from_str(&line)
+                .map_err(|e| anyhow!("Broken record at line {}: {e}", index + 2))?;
+            let Some(data) = langs.get_mut(&record.lang) else {
+                return Err(anyhow!(
+                    "Record at line {} has unknown language {}",
+                    index + 2,
+                    record.lang
+                ));
+            };
+            match record.data {
+                SnapshotData::List(t) => data.boxes.push(*t),
+                SnapshotData::Items(t) => data.items.push(t),
+                SnapshotData::Currencies(t) => data.currencies.push(t),
+            }
+            records += 1;
+        }
+        // A cut off file would otherwise be imported silently.
+        if records != manifest.records {
+            return Err(anyhow!(
+                "Snapshot has {records} records, expected {}",
+                manifest.records
+            ));
+        }
+
+        Ok(Snapshot { manifest, langs })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        self.write(BufWriter::new(fs::File::create(path)?))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Snapshot> {
+        Snapshot::read(BufReader::new(fs::File::open(path)?))
+    }
+}
@@ -0,0 +1,1066 @@
+//! Where lootbox data and user states are kept.
+
+use std::{
+    any::Any,
+    borrow::Borrow,
+    collections::{HashMap, HashSet},
+    env, fmt, fs,
+    future::Future,
+    hash::Hash,
+    mem,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use anyhow::anyhow;
+use bson::doc;
+use log::warn;
+use mongodb::{
+    error::{ErrorKind, WriteFailure},
+    options::{ClientOptions, IndexOptions, ServerApi, ServerApiVersion},
+    Client, Collection, IndexModel,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    currencies::{CurrencyData, CurrencyType},
+    history::LootBoxRevision,
+    item::ItemData,
+    lootbox::LootBox,
+    price::PriceData,
+    user::UserData,
+};
+
+/// Storage of the lootbox data of every language, and of the user states.
+pub trait LootboxStore: Send + Sync {
+    fn get_box(
+        &self,
+        lang: &str,
+        id: u64,
+    ) -> impl Future<Output = anyhow::Result<Option<LootBox>>> + Send;
+
+    fn list_boxes(&self, lang: &str) -> impl Future<Output = anyhow::Result<Vec<LootBox>>> + Send;
+
+    /// Currency by its icon name, see [`crate::currencies::CurrencyType::as_icon_name`].
+    fn get_currency(
+        &self,
+        lang: &str,
+        name: &str,
+    ) -> impl Future<Output = anyhow::Result<Option<CurrencyData>>> + Send;
+
+    fn list_currencies(
+        &self,
+        lang: &str,
+    ) -> impl Future<Output = anyhow::Result<Vec<CurrencyData>>> + Send;
+
+    fn get_item(
+        &self,
+        lang: &str,
+        id: u64,
+    ) -> impl Future<Output = anyhow::Result<Option<ItemData>>> + Send;
+
+    fn list_items(&self, lang: &str) -> impl Future<Output = anyhow::Result<Vec<ItemData>>> + Send;
+
+    fn get_price(
+        &self,
+        lang: &str,
+        box_id: u64,
+    ) -> impl Future<Output = anyhow::Result<Option<PriceData>>> + Send;
+
+    fn get_user(
+        &self,
+        user_id: &str,
+    ) -> impl Future<Output = anyhow::Result<Option<UserData>>> + Send;
+
+    /// Revisions of a lootbox, oldest first.
+    fn list_history(
+        &self,
+        lang: &str,
+        box_id: u64,
+    ) -> impl Future<Output = anyhow::Result<Vec<LootBoxRevision>>> + Send;
+
+    /// Insert `data`, or replace the lootbox with the same id.
+    fn upsert_box(
+        &self,
+        lang: &str,
+        data: &LootBox,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    fn upsert_item(
+        &self,
+        lang: &str,
+        data: &ItemData,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    fn upsert_currency(
+        &self,
+        lang: &str,
+        data: &CurrencyData,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    fn upsert_price(
+        &self,
+        lang: &str,
+        data: &PriceData,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    /// [`LootboxStore::upsert_box`] for every lootbox of `data`, at once if the
+    /// store can.
+    fn upsert_boxes(
+        &self,
+        lang: &str,
+        data: &[LootBox],
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    fn upsert_items(
+        &self,
+        lang: &str,
+        data: &[ItemData],
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    fn upsert_currencies(
+        &self,
+        lang: &str,
+        data: &[CurrencyData],
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    /// Delete every lootbox of `lang` whose id is not in `ids`.
+    fn retain_boxes(
+        &self,
+        lang: &str,
+        ids: &[u64],
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    fn retain_items(
+        &self,
+        lang: &str,
+        ids: &[u64],
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    fn retain_currencies(
+        &self,
+        lang: &str,
+        types: &[CurrencyType],
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    /// Write `data` if the stored user is still at `data.version`, bumping
+    /// the version. Returns `false` without writing when another save came
+    /// first, `data` should then be reloaded and changed again.
+    fn save_user(&self, data: &UserData) -> impl Future<Output = anyhow::Result<bool>> + Send;
+
+    /// Insert `data`, or replace the revision of the same lootbox starting at
+    /// the same time.
+    fn upsert_history(
+        &self,
+        lang: &str,
+        data: &LootBoxRevision,
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    fn upsert_histories(
+        &self,
+        lang: &str,
+        data: &[LootBoxRevision],
+    ) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    fn delete_user(&self, user_id: &str) -> impl Future<Output = anyhow::Result<()>> + Send;
+
+    /// Languages with lootbox data, empty if never set.
+    fn list_languages(&self) -> impl Future<Output = anyhow::Result<Vec<String>>> + Send;
+
+    /// Replace the list of languages with `langs`.
+    fn set_languages(&self, langs: &[&str]) -> impl Future<Output = anyhow::Result<()>> + Send;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Languages {
+    langs: Vec<String>,
+}
+
+fn is_duplicate_key(e: &mongodb::error::Error) -> bool {
+    matches!(
+        &*e.kind,
+        ErrorKind::Write(WriteFailure::WriteError(t)) if t.code == 11000
+    )
+}
+
+/// Keeps every language in database `wowslootbox-{lang}`, and the users and
+/// languages in `wowslootbox-meta`.
+#[derive(Debug, Clone)]
+pub struct MongoStore {
+    client: Client,
+    /// Fail lists on a broken document instead of skipping it.
+    strict: bool,
+}
+
+impl MongoStore {
+    pub fn new(client: Client) -> Self {
+        MongoStore {
+            client,
+            strict: false,
+        }
+    }
+
+    /// Fail lists on a document which cannot be read, rather than leaving it
+    /// out. Meant for copies of the data like [`crate::snapshot::Snapshot::export`].
+    pub fn strict(self) -> Self {
+        MongoStore {
+            strict: true,
+            ..self
+        }
+    }
+
+    /// Connect with the Stable API version 1.
+    pub async fn connect(conn: &str) -> anyhow::Result<Self> {
+        let mut client_options = ClientOptions::parse(conn).await?;
+        let server_api = ServerApi::builder().version(ServerApiVersion::V1).build();
+        client_options.server_api = Some(server_api);
+        let store = MongoStore::new(Client::with_options(client_options)?);
+
+        // Two first saves of a user cannot both insert it.
+        let users: Collection<UserData> = store.collection("meta", "users");
+        let index = IndexModel::builder()
+            .keys(doc! { "userId": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        if let Err(e) = users.create_index(index).await {
+            warn!("Cannot index users: {}", e);
+        }
+
+        Ok(store)
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Make sure the server is reachable.
+    pub async fn ping(&self) -> anyhow::Result<()> {
+        self.client
+            .database("admin")
+            .run_command(doc! { "ping": 1 })
+            .await?;
+        Ok(())
+    }
+
+    fn collection<T: Send + Sync>(&self, lang: &str, name: &str) -> Collection<T> {
+        self.client
+            .database(&format!("wowslootbox-{lang}"))
+            .collection(name)
+    }
+
+    /// Every document of a collection, skipping the broken ones unless
+    /// [`MongoStore::strict`].
+    async fn list<T: DeserializeOwned + Send + Sync>(
+        &self,
+        lang: &str,
+        name: &str,
+    ) -> anyhow::Result<Vec<T>> {
+        let col: Collection<T> = self.collection(lang, name);
+        let mut cursor = col.find(doc! {}).allow_disk_use(true).await?;
+        let mut docs = vec![];
+        while cursor.advance().await? {
+            // One broken document does not hide the others.
+            match cursor.deserialize_current() {
+                Ok(t) => docs.push(t),
+                Err(e) if self.strict => {
+                    return Err(anyhow!("Broken document in {lang}/{name}: {e}"))
+                }
+                Err(e) => warn!("{}", e),
+            }
+        }
+        Ok(docs)
+    }
+}
+
+impl LootboxStore for MongoStore {
+    async fn get_box(&self, lang: &str, id: u64) -> anyhow::Result<Option<LootBox>> {
+        let col: Collection<LootBox> = self.collection(lang, "list");
+        Ok(col.find_one(doc! { "id": id as u32 }).await?)
+    }
+
+    async fn list_boxes(&self, lang: &str) -> anyhow::Result<Vec<LootBox>> {
+        self.list(lang, "list").await
+    }
+
+    async fn get_currency(&self, lang: &str, name: &str) -> anyhow::Result<Option<CurrencyData>> {
+        let col: Collection<CurrencyData> = self.collection(lang, "currencies");
+        Ok(col.find_one(doc! { "type": name }).await?)
+    }
+
+    async fn list_currencies(&self, lang: &str) -> anyhow::Result<Vec<CurrencyData>> {
+        self.list(lang, "currencies").await
+    }
+
+    async fn get_item(&self, lang: &str, id: u64) -> anyhow::Result<Option<ItemData>> {
+        let col: Collection<ItemData> = self.collection(lang, "items");
+        Ok(col.find_one(doc! { "id": id as u32 }).await?)
+    }
+
+    async fn list_items(&self, lang: &str) -> anyhow::Result<Vec<ItemData>> {
+        self.list(lang, "items").await
+    }
+
+    async fn get_price(&self, lang: &str, box_id: u64) -> anyhow::Result<Option<PriceData>> {
+        let col: Collection<PriceData> = self.collection(lang, "prices");
+        Ok(col.find_one(doc! { "boxId": box_id as u32 }).await?)
+    }
+
+    async fn get_user(&self, user_id: &str) -> anyhow::Result<Option<UserData>> {
+        let col: Collection<UserData> = self.collection("meta", "users");
+        Ok(col.find_one(doc! { "userId": user_id }).await?)
+    }
+
+    async fn list_history(&self, lang: &str, box_id: u64) -> anyhow::Result<Vec<LootBoxRevision>> {
+        let col: Collection<LootBoxRevision> = self.collection(lang, "history");
+        let mut cursor = col
+            .find(doc! { "boxId": box_id as u32 })
+            .sort(doc! { "validFrom": 1 })
+            .await?;
+        let mut revisions = vec![];
+        while cursor.advance().await? {
+            revisions.push(cursor.deserialize_current()?);
+        }
+        Ok(revisions)
+    }
+
+    async fn upsert_box(&self, lang: &str, data: &LootBox) -> anyhow::Result<()> {
+        let col: Collection<LootBox> = self.collection(lang, "list");
+        col.replace_one(doc! { "id": data.id as u32 }, data)
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    async fn upsert_item(&self, lang: &str, data: &ItemData) -> anyhow::Result<()> {
+        let col: Collection<ItemData> = self.collection(lang, "items");
+        col.replace_one(doc! { "id": data.id as u32 }, data)
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    async fn upsert_currency(&self, lang: &str, data: &CurrencyData) -> anyhow::Result<()> {
+        let col: Collection<CurrencyData> = self.collection(lang, "currencies");
+        col.replace_one(doc! { "type": data.r#type.as_icon_name() }, data)
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    async fn upsert_price(&self, lang: &str, data: &PriceData) -> anyhow::Result<()> {
+        let col: Collection<PriceData> = self.collection(lang, "prices");
+        col.replace_one(doc! { "boxId": data.box_id as u32 }, data)
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    async fn upsert_boxes(&self, lang: &str, data: &[LootBox]) -> anyhow::Result<()> {
+        for lootbox in data {
+            self.upsert_box(lang, lootbox).await?;
+        }
+        Ok(())
+    }
+
+    async fn upsert_items(&self, lang: &str, data: &[ItemData]) -> anyhow::Result<()> {
+        for item in data {
+            self.upsert_item(lang, item).await?;
+        }
+        Ok(())
+    }
+
+    async fn upsert_currencies(&self, lang: &str, data: &[CurrencyData]) -> anyhow::Result<()> {
+        for currency in data {
+            self.upsert_currency(lang, currency).await?;
+        }
+        Ok(())
+    }
+
+    async fn retain_boxes(&self, lang: &str, ids: &[u64]) -> anyhow::Result<()> {
+        let col: Collection<LootBox> = self.collection(lang, "list");
+        let ids: Vec<_> = ids.iter().map(|t| *t as u32).collect();
+        col.delete_many(doc! { "id": { "$nin": ids } }).await?;
+        Ok(())
+    }
+
+    async fn retain_items(&self, lang: &str, ids: &[u64]) -> anyhow::Result<()> {
+        let col: Collection<ItemData> = self.collection(lang, "items");
+        let ids: Vec<_> = ids.iter().map(|t| *t as u32).collect();
+        col.delete_many(doc! { "id": { "$nin": ids } }).await?;
+        Ok(())
+    }
+
+    async fn retain_currencies(&self, lang: &str, types: &[CurrencyType]) -> anyhow::Result<()> {
+        let col: Collection<CurrencyData> = self.collection(lang, "currencies");
+        let names: Vec<_> = types.iter().map(|t| t.as_icon_name()).collect();
+        col.delete_many(doc! { "type": { "$nin": names } }).await?;
+        Ok(())
+    }
+
+    async fn save_user(&self, data: &UserData) -> anyhow::Result<bool> {
+        let col: Collection<UserData> = self.collection("meta", "users");
+        let next = UserData {
+            version: data.version + 1,
+            ..data.clone()
+        };
+        if data.version > 0 {
+            let filter = doc! { "userId": &data.user_id, "version": data.version as i64 };
+            return Ok(col.replace_one(filter, &next).await?.matched_count > 0);
+        }
+
+        // Users saved before versions were kept have none.
+        let filter = doc! {
+            "userId": &data.user_id,
+            "$or": [{ "version": 0 }, { "version": { "$exists": false } }],
+        };
+        if col.replace_one(filter, &next).await?.matched_count > 0 {
+            return Ok(true);
+        }
+        if col
+            .find_one(doc! { "userId": &data.user_id })
+            .await?
+            .is_some()
+        {
+            return Ok(false);
+        }
+        match col.insert_one(&next).await {
+            Ok(_) => Ok(true),
+            // Created by another save in the meantime, see `connect`.
+            Err(e) if is_duplicate_key(&e) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn upsert_history(&self, lang: &str, data: &LootBoxRevision) -> anyhow::Result<()> {
+        let col: Collection<LootBoxRevision> = self.collection(lang, "history");
+        col.replace_one(
+            doc! { "boxId": data.box_id as u32, "validFrom": data.valid_from as i64 },
+            data,
+        )
+        .upsert(true)
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_histories(&self, lang: &str, data: &[LootBoxRevision]) -> anyhow::Result<()> {
+        for revision in data {
+            self.upsert_history(lang, revision).await?;
+        }
+        Ok(())
+    }
+
+    async fn delete_user(&self, user_id: &str) -> anyhow::Result<()> {
+        let col: Collection<UserData> = self.collection("meta", "users");
+        col.delete_one(doc! { "userId": user_id }).await?;
+        Ok(())
+    }
+
+    async fn list_languages(&self) -> anyhow::Result<Vec<String>> {
+        let col: Collection<Languages> = self.collection("meta", "languages");
+        Ok(col
+            .find_one(doc! {})
+            .await?
+            .map(|t| t.langs)
+            .unwrap_or_default())
+    }
+
+    async fn set_languages(&self, langs: &[&str]) -> anyhow::Result<()> {
+        let col: Collection<Languages> = self.collection("meta", "languages");
+        col.delete_many(doc! {}).await?;
+        col.insert_one(Languages {
+            langs: langs.iter().map(|t| t.to_string()).collect(),
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+/// Keeps every collection of [`MongoStore`] in a JSON file,
+/// `{dir}/{lang}/{collection}.json` and `{dir}/meta/{collection}.json`.
+///
+/// Collections are kept in memory once read, and read again only when their
+/// file was changed by someone else. Every write rewrites the whole file, so
+/// many documents should be written with one call, like
+/// [`LootboxStore::upsert_boxes`]. Files are read and written on the blocking
+/// thread pool, not on the async workers.
+pub struct FileStore {
+    dir: PathBuf,
+    /// Collections read so far by path, locked while a file is rewritten.
+    cache: Arc<Mutex<HashMap<PathBuf, Cached>>>,
+}
+
+/// Documents of a collection, a [`Docs`] of their type.
+struct Cached {
+    /// The file as it was when the documents were read or written.
+    stamp: Option<(SystemTime, u64)>,
+    docs: Box<dyn Any + Send>,
+}
+
+/// A document of a [`FileStore`] collection, with the key it is stored by.
+trait Document: Serialize + DeserializeOwned + Clone + Send + 'static {
+    type Key: Hash + Eq + Send + 'static;
+
+    fn key(&self) -> Self::Key;
+}
+
+impl Document for LootBox {
+    type Key = u64;
+
+    fn key(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Document for ItemData {
+    type Key = u64;
+
+    fn key(&self) -> u64 {
+        self.id
+    }
+}
+
+impl Document for CurrencyData {
+    type Key = &'static str;
+
+    fn key(&self) -> &'static str {
+        self.r#type.as_icon_name()
+    }
+}
+
+impl Document for PriceData {
+    type Key = u64;
+
+    fn key(&self) -> u64 {
+        self.box_id
+    }
+}
+
+impl Document for UserData {
+    type Key = String;
+
+    fn key(&self) -> String {
+        self.user_id.clone()
+    }
+}
+
+impl Document for LootBoxRevision {
+    type Key = (u64, u64);
+
+    fn key(&self) -> (u64, u64) {
+        (self.box_id, self.valid_from)
+    }
+}
+
+impl Document for Languages {
+    type Key = ();
+
+    fn key(&self) {}
+}
+
+/// Documents in the order of the file, with the position of every key.
+struct Docs<T: Document> {
+    list: Vec<T>,
+    index: HashMap<T::Key, usize>,
+}
+
+impl<T: Document> Docs<T> {
+    fn new(list: Vec<T>) -> Self {
+        let index = list.iter().enumerate().map(|(i, t)| (t.key(), i)).collect();
+        Docs { list, index }
+    }
+
+    fn get<Q>(&self, key: &Q) -> Option<&T>
+    where
+        T::Key: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index.get(key).map(|&i| &self.list[i])
+    }
+
+    /// Replace the document with the key of `doc`, or append it.
+    fn upsert(&mut self, doc: T) {
+        match self.index.get(&doc.key()) {
+            Some(&i) => self.list[i] = doc,
+            None => {
+                self.index.insert(doc.key(), self.list.len());
+                self.list.push(doc);
+            }
+        }
+    }
+
+    /// Delete the documents `keep` refuses, returning whether there were any.
+    fn retain(&mut self, keep: impl Fn(&T) -> bool) -> bool {
+        let len = self.list.len();
+        self.list.retain(|t| keep(t));
+        if self.list.len() == len {
+            return false;
+        }
+        *self = Docs::new(mem::take(&mut self.list));
+        true
+    }
+}
+
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Languages are directory names, nothing that reaches out of the store.
+fn is_safe_lang(lang: &str) -> bool {
+    !lang.is_empty()
+        && lang
+            .chars()
+            .all(|t| t.is_ascii_alphanumeric() || t == '-' || t == '_')
+}
+
+impl fmt::Debug for FileStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileStore")
+            .field("dir", &self.dir)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FileStore {
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        FileStore {
+            dir: dir.as_ref().to_path_buf(),
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, lang: &str, name: &str) -> anyhow::Result<PathBuf> {
+        if !is_safe_lang(lang) {
+            return Err(anyhow!("Invalid language {lang:?}"));
+        }
+        Ok(self.dir.join(lang).join(format!("{name}.json")))
+    }
+
+    /// Run `f` on the documents of a collection, empty if it does not exist.
+    /// The file is rewritten afterwards if `f` returns `true`.
+    async fn access<T, R>(
+        &self,
+        lang: &str,
+        name: &str,
+        f: impl FnOnce(&mut Docs<T>) -> (R, bool) + Send + 'static,
+    ) -> anyhow::Result<R>
+    where
+        T: Document,
+        R: Send + 'static,
+    {
+        let path = self.path(lang, name)?;
+        let cache = self.cache.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+            Self::access_locked(&mut cache, path, f)
+        })
+        .await?
+    }
+
+    fn access_locked<T, R>(
+        cache: &mut HashMap<PathBuf, Cached>,
+        path: PathBuf,
+        f: impl FnOnce(&mut Docs<T>) -> (R, bool),
+    ) -> anyhow::Result<R>
+    where
+        T: Document,
+    {
+        let current = stamp(&path);
+        let fresh = cache
+            .get(&path)
+            .is_some_and(|t| t.stamp == current && t.docs.is::<Docs<T>>());
+        if !fresh {
+            let docs: Vec<T> = match current {
+                Some(_) => {
+                    let data = fs::read_to_string(&path)?;
+                    serde_json::from_str(&data)
+                        .map_err(|e| anyhow!("Cannot read {}: {e}", path.display()))?
+                }
+                None => vec![],
+            };
+            cache.insert(
+                path.clone(),
+                Cached {
+                    stamp: current,
+                    docs: Box::new(Docs::new(docs)),
+                },
+            );
+        }
+
+        let cached = cache.get_mut(&path).expect("cached above");
+        let docs = cached
+            .docs
+            .downcast_mut::<Docs<T>>()
+            .expect("checked above");
+        let (res, changed) = f(docs);
+        if changed {
+            let written = Self::write(&path, &docs.list);
+            // Read again next time rather than trusting a failed write.
+            cached.stamp = written.as_ref().ok().and_then(|_| stamp(&path));
+            if let Err(e) = written {
+                cache.remove(&path);
+                return Err(e);
+            }
+        }
+        Ok(res)
+    }
+
+    fn write<T: Serialize>(path: &Path, docs: &[T]) -> anyhow::Result<()> {
+        fs::create_dir_all(path.parent().unwrap())?;
+        // Written aside first, so readers never see half a file.
+        let temp = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+        fs::write(&temp, serde_json::to_vec_pretty(docs)?)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    async fn read<T: Document>(&self, lang: &str, name: &str) -> anyhow::Result<Vec<T>> {
+        self.access(lang, name, |docs: &mut Docs<T>| (docs.list.clone(), false))
+            .await
+    }
+
+    async fn find<T: Document>(
+        &self,
+        lang: &str,
+        name: &str,
+        key: T::Key,
+    ) -> anyhow::Result<Option<T>> {
+        self.access(lang, name, move |docs: &mut Docs<T>| {
+            (docs.get(&key).cloned(), false)
+        })
+        .await
+    }
+
+    /// Replace the document with the key of each of `data`, or append it,
+    /// writing the file once.
+    async fn upsert<T: Document>(&self, lang: &str, name: &str, data: &[T]) -> anyhow::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let data = data.to_vec();
+        self.access(lang, name, |docs: &mut Docs<T>| {
+            for doc in data {
+                docs.upsert(doc);
+            }
+            ((), true)
+        })
+        .await
+    }
+
+    /// Delete the documents whose key is not in `keys`, writing the file only
+    /// if any.
+    async fn retain<T: Document>(
+        &self,
+        lang: &str,
+        name: &str,
+        keys: impl IntoIterator<Item = T::Key>,
+    ) -> anyhow::Result<()> {
+        let keys: HashSet<_> = keys.into_iter().collect();
+        self.access(lang, name, move |docs: &mut Docs<T>| {
+            ((), docs.retain(|t| keys.contains(&t.key())))
+        })
+        .await
+    }
+}
+
+impl LootboxStore for FileStore {
+    async fn get_box(&self, lang: &str, id: u64) -> anyhow::Result<Option<LootBox>> {
+        self.find(lang, "list", id).await
+    }
+
+    async fn list_boxes(&self, lang: &str) -> anyhow::Result<Vec<LootBox>> {
+        self.read(lang, "list").await
+    }
+
+    async fn get_currency(&self, lang: &str, name: &str) -> anyhow::Result<Option<CurrencyData>> {
+        let name = name.to_owned();
+        self.access(lang, "currencies", move |docs: &mut Docs<CurrencyData>| {
+            (docs.get(name.as_str()).cloned(), false)
+        })
+        .await
+    }
+
+    async fn list_currencies(&self, lang: &str) -> anyhow::Result<Vec<CurrencyData>> {
+        self.read(lang, "currencies").await
+    }
+
+    async fn get_item(&self, lang: &str, id: u64) -> anyhow::Result<Option<ItemData>> {
+        self.find(lang, "items", id).await
+    }
+
+    async fn list_items(&self, lang: &str) -> anyhow::Result<Vec<ItemData>> {
+        self.read(lang, "items").await
+    }
+
+    async fn get_price(&self, lang: &str, box_id: u64) -> anyhow::Result<Option<PriceData>> {
+        self.find(lang, "prices", box_id).await
+    }
+
+    async fn get_user(&self, user_id: &str) -> anyhow::Result<Option<UserData>> {
+        self.find("meta", "users", user_id.to_owned()).await
+    }
+
+    async fn list_history(&self, lang: &str, box_id: u64) -> anyhow::Result<Vec<LootBoxRevision>> {
+        let mut revisions = self
+            .access(lang, "history", move |docs: &mut Docs<LootBoxRevision>| {
+                let revisions: Vec<_> = docs
+                    .list
+                    .iter()
+                    .filter(|t| t.box_id == box_id)
+                    .cloned()
+                    .collect();
+                (revisions, false)
+            })
+            .await?;
+        revisions.sort_by_key(|t| t.valid_from);
+        Ok(revisions)
+    }
+
+    async fn upsert_box(&self, lang: &str, data: &LootBox) -> anyhow::Result<()> {
+        self.upsert_boxes(lang, std::slice::from_ref(data)).await
+    }
+
+    async fn upsert_item(&self, lang: &str, data: &ItemData) -> anyhow::Result<()> {
+        self.upsert_items(lang, std::slice::from_ref(data)).await
+    }
+
+    async fn upsert_currency(&self, lang: &str, data: &CurrencyData) -> anyhow::Result<()> {
+        self.upsert_currencies(lang, std::slice::from_ref(data))
+            .await
+    }
+
+    async fn upsert_price(&self, lang: &str, data: &PriceData) -> anyhow::Result<()> {
+        self.upsert(lang, "prices", std::slice::from_ref(data))
+            .await
+    }
+
+    async fn upsert_boxes(&self, lang: &str, data: &[LootBox]) -> anyhow::Result<()> {
+        self.upsert(lang, "list", data).await
+    }
+
+    async fn upsert_items(&self, lang: &str, data: &[ItemData]) -> anyhow::Result<()> {
+        self.upsert(lang, "items", data).await
+    }
+
+    async fn upsert_currencies(&self, lang: &str, data: &[CurrencyData]) -> anyhow::Result<()> {
+        self.upsert(lang, "currencies", data).await
+    }
+
+    async fn retain_boxes(&self, lang: &str, ids: &[u64]) -> anyhow::Result<()> {
+        self.retain::<LootBox>(lang, "list", ids.iter().copied())
+            .await
+    }
+
+    async fn retain_items(&self, lang: &str, ids: &[u64]) -> anyhow::Result<()> {
+        self.retain::<ItemData>(lang, "items", ids.iter().copied())
+            .await
+    }
+
+    async fn retain_currencies(&self, lang: &str, types: &[CurrencyType]) -> anyhow::Result<()> {
+        self.retain::<CurrencyData>(lang, "currencies", types.iter().map(|t| t.as_icon_name()))
+            .await
+    }
+
+    async fn save_user(&self, data: &UserData) -> anyhow::Result<bool> {
+        let data = data.clone();
+        self.access("meta", "users", move |users: &mut Docs<UserData>| {
+            let version = users.get(&data.user_id).map_or(0, |t| t.version);
+            if version != data.version {
+                return (false, false);
+            }
+            users.upsert(UserData {
+                version: data.version + 1,
+                ..data
+            });
+            (true, true)
+        })
+        .await
+    }
+
+    async fn upsert_history(&self, lang: &str, data: &LootBoxRevision) -> anyhow::Result<()> {
+        self.upsert_histories(lang, std::slice::from_ref(data))
+            .await
+    }
+
+    async fn upsert_histories(&self, lang: &str, data: &[LootBoxRevision]) -> anyhow::Result<()> {
+        self.upsert(lang, "history", data).await
+    }
+
+    async fn delete_user(&self, user_id: &str) -> anyhow::Result<()> {
+        let user_id = user_id.to_owned();
+        self.access("meta", "users", move |users: &mut Docs<UserData>| {
+            ((), users.retain(|t| t.user_id != user_id))
+        })
+        .await
+    }
+
+    async fn list_languages(&self) -> anyhow::Result<Vec<String>> {
+        self.access("meta", "languages", |docs: &mut Docs<Languages>| {
+            (
+                docs.list
+                    .first()
+                    .map(|t| t.langs.clone())
+                    .unwrap_or_default(),
+                false,
+            )
+        })
+        .await
+    }
+
+    async fn set_languages(&self, langs: &[&str]) -> anyhow::Result<()> {
+        let languages = Languages {
+            langs: langs.iter().map(|t| t.to_string()).collect(),
+        };
+        self.access("meta", "languages", |docs: &mut Docs<Languages>| {
+            *docs = Docs::new(vec![languages]);
+            ((), true)
+        })
+        .await
+    }
+}
+
+/// The store picked at runtime, see [`AnyStore::from_env`].
+#[derive(Debug)]
+pub enum AnyStore {
+    Mongo(MongoStore),
+    File(FileStore),
+}
+
+impl AnyStore {
+    /// See [`MongoStore::strict`], a [`FileStore`] always fails on a broken file.
+    pub fn strict(self) -> Self {
+        match self {
+            AnyStore::Mongo(t) => AnyStore::Mongo(t.strict()),
+            AnyStore::File(t) => AnyStore::File(t),
+        }
+    }
+
+    /// [`FileStore`] in `STORE_DIR` if set, otherwise [`MongoStore`] connected
+    /// to `MONGODB_CONN`.
+    pub async fn from_env() -> anyhow::Result<Self> {
+        match env::var("STORE_DIR") {
+            Ok(dir) => Ok(AnyStore::File(FileStore::new(dir))),
+            Err(_) => Ok(AnyStore::Mongo(
+                MongoStore::connect(&env::var("MONGODB_CONN")?).await?,
+            )),
+        }
+    }
+}
+
+/// Forward a call to the store in use.
+macro_rules! dispatch {
+    ($self:ident.$method:ident($($arg:expr),*)) => {
+        match $self {
+            AnyStore::Mongo(t) => t.$method($($arg),*).await,
+            AnyStore::File(t) => t.$method($($arg),*).await,
+        }
+    };
+}
+
+impl LootboxStore for AnyStore {
+    async fn get_box(&self, lang: &str, id: u64) -> anyhow::Result<Option<LootBox>> {
+        dispatch!(self.get_box(lang, id))
+    }
+
+    async fn list_boxes(&self, lang: &str) -> anyhow::Result<Vec<LootBox>> {
+        dispatch!(self.list_boxes(lang))
+    }
+
+    async fn get_currency(&self, lang: &str, name: &str) -> anyhow::Result<Option<CurrencyData>> {
+        dispatch!(self.get_currency(lang, name))
+    }
+
+    async fn list_currencies(&self, lang: &str) -> anyhow::Result<Vec<CurrencyData>> {
+        dispatch!(self.list_currencies(lang))
+    }
+
+    async fn get_item(&self, lang: &str, id: u64) -> anyhow::Result<Option<ItemData>> {
+        dispatch!(self.get_item(lang, id))
+    }
+
+    async fn list_items(&self, lang: &str) -> anyhow::Result<Vec<ItemData>> {
+        dispatch!(self.list_items(lang))
+    }
+
+    async fn get_price(&self, lang: &str, box_id: u64) -> anyhow::Result<Option<PriceData>> {
+        dispatch!(self.get_price(lang, box_id))
+    }
+
+    async fn get_user(&self, user_id: &str) -> anyhow::Result<Option<UserData>> {
+        dispatch!(self.get_user(user_id))
+    }
+
+    async fn list_history(&self, lang: &str, box_id: u64) -> anyhow::Result<Vec<LootBoxRevision>> {
+        dispatch!(self.list_history(lang, box_id))
+    }
+
+    async fn upsert_box(&self, lang: &str, data: &LootBox) -> anyhow::Result<()> {
+        dispatch!(self.upsert_box(lang, data))
+    }
+
+    async fn upsert_item(&self, lang: &str, data: &ItemData) -> anyhow::Result<()> {
+        dispatch!(self.upsert_item(lang, data))
+    }
+
+    async fn upsert_currency(&self, lang: &str, data: &CurrencyData) -> anyhow::Result<()> {
+        dispatch!(self.upsert_currency(lang, data))
+    }
+
+    async fn upsert_price(&self, lang: &str, data: &PriceData) -> anyhow::Result<()> {
+        dispatch!(self.upsert_price(lang, data))
+    }
+
+    async fn upsert_boxes(&self, lang: &str, data: &[LootBox]) -> anyhow::Result<()> {
+        dispatch!(self.upsert_boxes(lang, data))
+    }
+
+    async fn upsert_items(&self, lang: &str, data: &[ItemData]) -> anyhow::Result<()> {
+        dispatch!(self.upsert_items(lang, data))
+    }
+
+    async fn upsert_currencies(&self, lang: &str, data: &[CurrencyData]) -> anyhow::Result<()> {
+        dispatch!(self.upsert_currencies(lang, data))
+    }
+
+    async fn retain_boxes(&self, lang: &str, ids: &[u64]) -> anyhow::Result<()> {
+        dispatch!(self.retain_boxes(lang, ids))
+    }
+
+    async fn retain_items(&self, lang: &str, ids: &[u64]) -> anyhow::Result<()> {
+        dispatch!(self.retain_items(lang, ids))
+    }
+
+    async fn retain_currencies(&self, lang: &str, types: &[CurrencyType]) -> anyhow::Result<()> {
+        dispatch!(self.retain_currencies(lang, types))
+    }
+
+    async fn save_user(&self, data: &UserData) -> anyhow::Result<bool> {
+        dispatch!(self.save_user(data))
+    }
+
+    async fn upsert_history(&self, lang: &str, data: &LootBoxRevision) -> anyhow::Result<()> {
+        dispatch!(self.upsert_history(lang, data))
+    }
+
+    async fn upsert_histories(&self, lang: &str, data: &[LootBoxRevision]) -> anyhow::Result<()> {
+        dispatch!(self.upsert_histories(lang, data))
+    }
+
+    async fn delete_user(&self, user_id: &str) -> anyhow::Result<()> {
+        dispatch!(self.delete_user(user_id))
+    }
+
+    async fn list_languages(&self) -> anyhow::Result<Vec<String>> {
+        dispatch!(self.list_languages())
+    }
+
+    async fn set_languages(&self, langs: &[&str]) -> anyhow::Result<()> {
+        dispatch!(self.set_languages(langs))
+    }
+}
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+
+/// Simulated account of a chat user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct UserData {
+    /// Platform-prefixed id, e.g. `qq:12345`.
+    pub user_id: String,
+    /// Unique reward ids already dropped.
+    pub owned: Vec<u64>,
+    pub pity: Vec<UserBoxPity>,
+    /// Bumped on every save, see [`crate::store::LootboxStore::save_user`].
+    #[serde(default)]
+    pub version: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserBoxPity {
+    pub box_id: u64,
+    /// Guarantee counter.
+    pub current_try: u32,
+}
+
+impl UserData {
+    pub fn new(user_id: impl Into<String>) -> UserData {
+        UserData {
+            user_id: user_id.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn current_try(&self, box_id: u64) -> u32 {
+        self.pity
+            .iter()
+            .find(|t| t.box_id == box_id)
+            .map(|t| t.current_try)
+            .unwrap_or(0)
+    }
+
+    pub fn set_current_try(&mut self, box_id: u64, current_try: u32) {
+        match self.pity.iter_mut().find(|t| t.box_id == box_id) {
+            Some(pity) => pity.current_try = current_try,
+            None => self.pity.push(UserBoxPity {
+                box_id,
+                current_try,
+            }),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::{collections::HashSet, fmt, mem};
+
+use crate::lootbox::{LootBox, LootBoxRewardList, LootBoxRewardType};
+
+/// Largest accepted gap between a probability and the sum it should equal.
+pub const PROBABILITY_TOLERANCE: f64 = 0.01;
+
+/// Ship levels the game knows, 11 being superships.
+pub const SHIP_LEVELS: std::ops::RangeInclusive<u8> = 1..=11;
+
+/// A structural problem found by [`LootBox::validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum LootBoxIssue {
+    EmptySlot {
+        slot: usize,
+    },
+    EmptyList {
+        slot: usize,
+        list: String,
+    },
+    InvalidProbability {
+        slot: usize,
+        list: String,
+        probability: f64,
+    },
+    /// Reward lists of a slot do not sum up to 1.
+    SlotProbability {
+        slot: usize,
+        sum: f64,
+    },
+    /// Rewards of a list do not sum up to the list probability.
+    ListProbability {
+        slot: usize,
+        list: String,
+        sum: f64,
+        expected: f64,
+    },
+    /// A list has unique rewards, but nothing to give once they are all owned.
+    MissingFiller {
+        slot: usize,
+        list: String,
+    },
+    /// The filler is a signal or camouflage which no reward of the lootbox
+    /// describes, so the fetcher could not find its name and icon.
+    UnresolvedFiller {
+        filler: LootBoxRewardType,
+    },
+    UnknownShipLevel {
+        slot: usize,
+        list: String,
+        id: u64,
+        level: u8,
+    },
+    /// The same reward appears more than once in a slot.
+    DuplicateReward {
+        slot: usize,
+        id: u64,
+    },
+}
+
+impl LootBoxIssue {
+    /// Whether the lootbox cannot be opened or rendered correctly at all.
+    pub fn is_fatal(&self) -> bool {
+        !matches!(
+            self,
+            Self::UnknownShipLevel { .. } | Self::DuplicateReward { .. }
+        )
+    }
+}
+
+impl fmt::Display for LootBoxIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptySlot { slot } => write!(f, "slot {slot} has no reward list"),
+            Self::EmptyList { slot, list } => {
+                write!(f, "list `{list}` in slot {slot} has no reward")
+            }
+            Self::InvalidProbability {
+                slot,
+                list,
+                probability,
+            } => write!(
+                f,
+                "list `{list}` in slot {slot} has invalid probability {probability}"
+            ),
+            Self::SlotProbability { slot, sum } => {
+                write!(f, "lists in slot {slot} sum up to {sum} instead of 1")
+            }
+            Self::ListProbability {
+                slot,
+                list,
+                sum,
+                expected,
+            } => write!(
+                f,
+                "rewards in list `{list}` of slot {slot} sum up to {sum} instead of {expected}"
+            ),
+            Self::MissingFiller { slot, list } => write!(
+                f,
+                "list `{list}` in slot {slot} has unique rewards but there is no filler"
+            ),
+            Self::UnresolvedFiller { filler } => {
+                write!(f, "filler {filler:?} cannot be resolved")
+            }
+            Self::UnknownShipLevel {
+                slot,
+                list,
+                id,
+                level,
+            } => write!(
+                f,
+                "reward {id} in list `{list}` of slot {slot} has unknown ship level {level}"
+            ),
+            Self::DuplicateReward { slot, id } => {
+                write!(f, "reward {id} appears more than once in slot {slot}")
+            }
+        }
+    }
+}
+
+impl LootBox {
+    /// Report every structural problem of the lootbox, empty if there is none.
+    pub fn validate(&self) -> Vec<LootBoxIssue> {
+        let mut issues = vec![];
+
+        for (slot_index, slot) in self.slots.iter().enumerate() {
+            let lists: Vec<_> = slot.common.iter().chain(slot.valuable.iter()).collect();
+            if lists.is_empty() {
+                issues.push(LootBoxIssue::EmptySlot { slot: slot_index });
+                continue;
+            }
+
+            for list in lists.iter() {
+                issues.extend(self.validate_list(slot_index, list));
+            }
+
+            let sum: f64 = lists.iter().map(|t| t.probability).sum();
+            if (sum - 1.0).abs() > PROBABILITY_TOLERANCE {
+                issues.push(LootBoxIssue::SlotProbability {
+                    slot: slot_index,
+                    sum,
+                });
+            }
+
+            let mut seen = HashSet::new();
+            let mut reported = HashSet::new();
+            for reward in lists.iter().flat_map(|t| t.rewards.iter()) {
+                let Some(id) = reward.reward.get_id() else {
+                    continue;
+                };
+                let key = (mem::discriminant(&reward.reward), id);
+                if !seen.insert(key) && reported.insert(key) {
+                    issues.push(LootBoxIssue::DuplicateReward {
+                        slot: slot_index,
+                        id,
+                    });
+                }
+            }
+        }
+
+        // Other fillers are resolved from the items when rendered.
+        if let Some(filler) = &self.filler {
+            if let LootBoxRewardType::Signal { name, .. }
+            | LootBoxRewardType::Camouflage { name, .. } = &filler.filler
+            {
+                if name.is_empty() {
+                    issues.push(LootBoxIssue::UnresolvedFiller {
+                        filler: filler.filler.clone(),
+                    });
+                }
+            }
+        }
+
+        issues
+    }
+
+    fn validate_list(&self, slot: usize, list: &LootBoxRewardList) -> Vec<LootBoxIssue> {
+        let mut issues = vec![];
+        let name = || list.name.clone();
+
+        if list.rewards.is_empty() {
+            issues.push(LootBoxIssue::EmptyList { slot, list: name() });
+        }
+
+        let invalid = |t: f64| !t.is_finite() || t < 0.0;
+        if let Some(probability) = std::iter::once(list.probability)
+            .chain(list.rewards.iter().map(|t| t.probability))
+            .find(|&t| invalid(t))
+            .or(Some(list.probability).filter(|&t| t == 0.0))
+        {
+            issues.push(LootBoxIssue::InvalidProbability {
+                slot,
+                list: name(),
+                probability,
+            });
+        } else if !list.rewards.is_empty() {
+            let sum: f64 = list.rewards.iter().map(|t| t.probability).sum();
+            if (sum - list.probability).abs() > PROBABILITY_TOLERANCE {
+                issues.push(LootBoxIssue::ListProbability {
+                    slot,
+                    list: name(),
+                    sum,
+                    expected: list.probability,
+                });
+            }
+        }
+
+        if list.has_unique_rewards && self.filler.is_none() {
+            issues.push(LootBoxIssue::MissingFiller { slot, list: name() });
+        }
+
+        for reward in list.rewards.iter() {
+            use LootBoxRewardType::*;
+            let level = match &reward.reward {
+                Ship { ship_level, .. } => *ship_level,
+                Skin { ship, .. } | Permoflage { ship, .. } | Mskin { ship, .. } => ship.ship_level,
+                _ => continue,
+            };
+            if !SHIP_LEVELS.contains(&level) {
+                issues.push(LootBoxIssue::UnknownShipLevel {
+                    slot,
+                    list: name(),
+                    id: reward.reward.get_id().unwrap_or_default(),
+                    level,
+                });
+            }
+        }
+
+        issues
+    }
+}
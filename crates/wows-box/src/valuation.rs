@@ -0,0 +1,251 @@
+//! Doubloon-equivalent value of any reward.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{currencies::CurrencyType, item::ItemData, lootbox::LootBoxRewardType};
+
+/// Reward kinds valued as a whole when no better entry exists, named as the
+/// `type` tag of [`LootBoxRewardType`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RewardKind {
+    #[serde(rename = "camoboost")]
+    CamoBoost,
+    CollectionAlbum,
+    Signal,
+    Skin,
+    Camouflage,
+    Permoflage,
+    Mskin,
+    Style,
+    Crew,
+    Multiboost,
+    Ensign,
+    Lootbox,
+}
+
+impl RewardKind {
+    pub fn of(reward: &LootBoxRewardType) -> Option<Self> {
+        use LootBoxRewardType::*;
+        Some(match reward {
+            CamoBoost { .. } => Self::CamoBoost,
+            CollectionAlbum { .. } => Self::CollectionAlbum,
+            Signal { .. } => Self::Signal,
+            Skin { .. } => Self::Skin,
+            Camouflage { .. } => Self::Camouflage,
+            Permoflage { .. } => Self::Permoflage,
+            Mskin { .. } => Self::Mskin,
+            Style { .. } => Self::Style,
+            Crew { .. } => Self::Crew,
+            Multiboost { .. } => Self::Multiboost,
+            Ensign { .. } => Self::Ensign,
+            Lootbox { .. } => Self::Lootbox,
+            _ => return None,
+        })
+    }
+}
+
+/// Ship values by level, starting from level I. The last one is used for
+/// every higher level.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ShipValuation {
+    pub tech: Vec<f64>,
+    pub premium: Vec<f64>,
+    /// Falls back to `premium` if empty.
+    pub special: Vec<f64>,
+}
+
+impl ShipValuation {
+    pub fn value(&self, level: u8, is_premium: bool, is_special: bool) -> Option<f64> {
+        let values = match (is_special, is_premium) {
+            (true, _) if !self.special.is_empty() => &self.special,
+            (true, _) | (_, true) => &self.premium,
+            _ => &self.tech,
+        };
+        let idx = (level.max(1) as usize - 1).min(values.len().checked_sub(1)?);
+        values.get(idx).copied()
+    }
+
+    fn merge(&mut self, other: &ShipValuation) {
+        for (this, other) in [
+            (&mut self.tech, &other.tech),
+            (&mut self.premium, &other.premium),
+            (&mut self.special, &other.special),
+        ] {
+            if !other.is_empty() {
+                this.clone_from(other);
+            }
+        }
+    }
+}
+
+/// Doubloons a unit of every reward is worth.
+///
+/// Loaded from TOML or JSON, every entry of the file replaces the built-in
+/// one, e.g.
+///
+/// ```toml
+/// [currencies]
+/// credits = 0.0004
+///
+/// [kinds]
+/// signal = 30.0
+///
+/// [items]
+/// "4288654256" = 50.0
+///
+/// [languages.zh-sg.items]
+/// "特殊信号旗" = 60.0
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValuationTable {
+    #[serde(default)]
+    pub currencies: HashMap<CurrencyType, f64>,
+    #[serde(default)]
+    pub ships: ShipValuation,
+    /// By item id, or by the item name in [`ItemData`].
+    #[serde(default)]
+    pub items: HashMap<String, f64>,
+    #[serde(default)]
+    pub kinds: HashMap<RewardKind, f64>,
+    /// Merged over this table for the language.
+    #[serde(default)]
+    pub languages: HashMap<String, ValuationTable>,
+}
+
+impl Default for ValuationTable {
+    /// Rough values based on the premium shop.
+    fn default() -> Self {
+        ValuationTable {
+            currencies: HashMap::from([
+                (CurrencyType::Gold, 1.0),
+                (CurrencyType::Credits, 1.0 / 3000.0),
+                (CurrencyType::FreeXp, 1.0 / 25.0),
+                (CurrencyType::EliteXp, 1.0 / 25.0),
+                // A day of premium account, from the 30 days package.
+                (CurrencyType::WowsPremium, 2500.0 / 30.0),
+                (CurrencyType::Slots, 300.0),
+            ]),
+            ships: ShipValuation {
+                tech: vec![
+                    0.0, 100.0, 300.0, 500.0, 1000.0, 1500.0, 2000.0, 3000.0, 4000.0, 6000.0,
+                    10000.0,
+                ],
+                premium: vec![
+                    300.0, 500.0, 1000.0, 1500.0, 2500.0, 4000.0, 6000.0, 10000.0, 15000.0,
+                    20000.0, 30000.0,
+                ],
+                special: vec![],
+            },
+            items: HashMap::new(),
+            kinds: HashMap::from([
+                (RewardKind::Signal, 25.0),
+                (RewardKind::CamoBoost, 30.0),
+                (RewardKind::Camouflage, 300.0),
+                (RewardKind::Skin, 500.0),
+                (RewardKind::Permoflage, 1000.0),
+                (RewardKind::Mskin, 1000.0),
+                (RewardKind::Crew, 500.0),
+                (RewardKind::Multiboost, 50.0),
+                (RewardKind::Ensign, 100.0),
+            ]),
+            languages: HashMap::new(),
+        }
+    }
+}
+
+impl ValuationTable {
+    /// Built-in table with the entries of a `.toml` or `.json` file over it.
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let table: ValuationTable = match path.extension().and_then(|t| t.to_str()) {
+            Some("toml") => toml::from_str(&text)?,
+            _ => serde_json::from_str(&text)?,
+        };
+
+        let mut merged = ValuationTable::default();
+        merged.merge(&table);
+        Ok(merged)
+    }
+
+    /// Replace entries with the ones of `other`.
+    pub fn merge(&mut self, other: &ValuationTable) {
+        self.currencies
+            .extend(other.currencies.iter().map(|(k, v)| (*k, *v)));
+        self.ships.merge(&other.ships);
+        self.items
+            .extend(other.items.iter().map(|(k, v)| (k.clone(), *v)));
+        self.kinds.extend(other.kinds.iter().map(|(k, v)| (*k, *v)));
+        for (lang, table) in other.languages.iter() {
+            match self.languages.get_mut(lang) {
+                Some(t) => t.merge(table),
+                None => {
+                    self.languages.insert(lang.clone(), table.clone());
+                }
+            }
+        }
+    }
+
+    /// This table with the overrides of `lang` applied.
+    pub fn for_lang(&self, lang: &str) -> ValuationTable {
+        let mut table = self.clone();
+        table.languages.clear();
+        if let Some(overrides) = self.languages.get(lang) {
+            table.merge(overrides);
+        }
+        table
+    }
+
+    /// Value of one unit of `reward`. `item` is its item data, if it is an item.
+    pub fn value(&self, reward: &LootBoxRewardType, item: Option<&ItemData>) -> Option<f64> {
+        if let Some(currency) = reward.as_currency() {
+            return self.currencies.get(&currency).copied();
+        }
+        if let LootBoxRewardType::Ship {
+            ship_level,
+            is_premium,
+            is_special,
+            ..
+        } = reward
+        {
+            return self.ships.value(*ship_level, *is_premium, *is_special);
+        }
+
+        let by_id = reward
+            .get_id()
+            .and_then(|id| self.items.get(&id.to_string()));
+        let by_name = item.and_then(|t| self.items.get(&t.name));
+        let by_kind = RewardKind::of(reward).and_then(|t| self.kinds.get(&t));
+        by_id.or(by_name).or(by_kind).copied()
+    }
+
+    /// Total value of opening results, and the number of reward kinds
+    /// without a value.
+    pub fn total(
+        &self,
+        rewards: &HashMap<(LootBoxRewardType, bool), u32>,
+        items: &HashMap<u64, ItemData>,
+    ) -> (f64, u32) {
+        let mut value = 0.0;
+        let mut unvalued = HashSet::new();
+        for ((reward, _), amount) in rewards {
+            let item = reward.get_id().and_then(|t| items.get(&t));
+            match self.value(reward, item) {
+                Some(t) => value += t * *amount as f64,
+                None => {
+                    unvalued.insert(reward);
+                }
+            }
+        }
+        (value, unvalued.len() as u32)
+    }
+}
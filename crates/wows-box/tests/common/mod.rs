@@ -0,0 +1,95 @@
+#![allow(dead_code)]
+
+use wows_box::lootbox::{
+    LootBox, LootBoxFiller, LootBoxReward, LootBoxRewardList, LootBoxRewardType, LootBoxSlot,
+};
+
+pub fn ship_of(id: u64, ship_level: u8, is_premium: bool) -> LootBoxRewardType {
+    LootBoxRewardType::Ship {
+        crew_level: None,
+        ship_level,
+        id,
+        name: format!("Ship {id}"),
+        is_premium,
+        is_special: false,
+        icon: String::new(),
+    }
+}
+
+pub fn ship(id: u64) -> LootBoxRewardType {
+    ship_of(id, 8, true)
+}
+
+pub fn reward(probability: f64, amount: u32, reward: LootBoxRewardType) -> LootBoxReward {
+    LootBoxReward {
+        probability,
+        amount,
+        reward,
+    }
+}
+
+pub fn reward_list(
+    name: &str,
+    probability: f64,
+    rewards: Vec<LootBoxReward>,
+    unique: bool,
+) -> LootBoxRewardList {
+    LootBoxRewardList {
+        name: name.to_owned(),
+        short_name: String::new(),
+        probability,
+        rewards,
+        has_unique_rewards: unique,
+    }
+}
+
+/// A lootbox without any slot.
+pub fn lootbox(id: u64, name: &str) -> LootBox {
+    LootBox {
+        name: name.to_owned(),
+        short_name: String::new(),
+        wows_name_id: String::new(),
+        id,
+        is_premium: false,
+        icon: String::new(),
+        slots: vec![],
+        filler: None,
+        save_point: None,
+        retired: false,
+    }
+}
+
+/// One slot of credits and camouflages, or one of two unique ships.
+pub fn fixture() -> LootBox {
+    LootBox {
+        is_premium: true,
+        slots: vec![LootBoxSlot {
+            common: vec![reward_list(
+                "common",
+                0.9,
+                vec![
+                    reward(0.6, 1000, LootBoxRewardType::Credits),
+                    reward(0.3, 3, LootBoxRewardType::CamoBoost { id: 7 }),
+                ],
+                false,
+            )],
+            valuable: vec![reward_list(
+                "ships",
+                0.1,
+                vec![
+                    reward(0.05, 1, ship(11)),
+                    reward(0.05, 1, ship_of(12, 10, true)),
+                ],
+                true,
+            )],
+            name: String::new(),
+            continuous_rewards: false,
+        }],
+        filler: Some(LootBoxFiller {
+            filler: LootBoxRewardType::Gold,
+            amount: 500,
+        }),
+        save_point: Some(10),
+        ..lootbox(1, "Test")
+    }
+}
@@ -0,0 +1,73 @@
+use common::{fixture, reward, ship};
+use wows_box::{
+    diff::LootBoxChange,
+    lootbox::{LootBox, LootBoxRewardType},
+};
+
+mod common;
+
+#[test]
+fn test_diff_same() {
+    assert_eq!(fixture().diff(&fixture()), vec![]);
+
+    let mut icon = fixture();
+    icon.icon = "new.png".to_owned();
+    assert_eq!(fixture().diff(&icon), vec![LootBoxChange::Details]);
+}
+
+#[test]
+fn test_diff_probability() {
+    let mut new = fixture();
+    new.slots[0].common[0].probability = 0.8;
+    new.slots[0].common[0].rewards[0].probability = 0.8;
+    new.slots[0].common[0].rewards[0].amount = 2000;
+    new.slots[0].valuable[0].probability = 0.2;
+    new.slots[0].valuable[0].rewards[1] = reward(0.05, 1, ship(13));
+    new.save_point = None;
+
+    let changes = fixture().diff(&new);
+    let lines: Vec<_> = changes.iter().map(ToString::to_string).collect();
+    assert_eq!(
+        lines,
+        vec![
+            "list `common` of slot 0: 0.9 → 0.8",
+            "credits in list `common` of slot 0: 0.6 → 0.8",
+            "credits in list `common` of slot 0: amount 1000 → 2000",
+            "list `ships` of slot 0: 0.1 → 0.2",
+            "Ship 12 removed from list `ships` of slot 0",
+            "Ship 13 added to list `ships` of slot 0 at 0.05",
+            "guarantee: 10 → none",
+        ]
+    );
+}
+
+#[test]
+fn test_diff_retired() {
+    let retired = LootBox {
+        retired: true,
+        ..fixture()
+    };
+    assert_eq!(fixture().diff(&retired), vec![LootBoxChange::Retired]);
+    assert_eq!(retired.diff(&fixture()), vec![LootBoxChange::Restored]);
+    // Retiring does not change the contents seeds refer to.
+    assert_eq!(retired.revision(), fixture().revision());
+}
+
+#[test]
+fn test_diff_reward_details() {
+    let mut new = fixture();
+    if let LootBoxRewardType::Ship { name, .. } = &mut new.slots[0].valuable[0].rewards[0].reward {
+        *name = "Renamed".to_owned();
+    }
+
+    // Still the same ship, not one removed and another added.
+    let lines: Vec<_> = fixture()
+        .diff(&new)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(
+        lines,
+        ["Ship 11 in list `ships` of slot 0: details changed"]
+    );
+}
@@ -0,0 +1,64 @@
+use std::{env, fs};
+
+use wows_box::{
+    history::{box_at, date_text, parse_date, record},
+    snapshot::Snapshot,
+    store::{FileStore, LootboxStore},
+};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/snapshot.jsonl");
+
+#[test]
+fn test_parse_date() {
+    assert_eq!(parse_date("1970-01-01"), Some(86399));
+    assert_eq!(parse_date("2024-02"), parse_date("2024-02-29"));
+    assert_eq!(parse_date("2023-12"), parse_date("2023"));
+    assert_eq!(date_text(parse_date("2024-02-29").unwrap()), "2024-02-29");
+    assert_eq!(date_text(parse_date("2023").unwrap()), "2023-12-31");
+
+    assert_eq!(parse_date("2023-02-29"), None);
+    assert_eq!(parse_date("2023-13"), None);
+    assert_eq!(parse_date("2023-00-01"), None);
+    assert_eq!(parse_date("yesterday"), None);
+    // Out of range years, which would otherwise overflow.
+    assert_eq!(parse_date("1969"), None);
+    assert_eq!(parse_date("10000-01-01"), None);
+    assert_eq!(parse_date("9223372036854775807"), None);
+    assert_eq!(parse_date("-5"), None);
+    assert_eq!(parse_date("2023-01-9223372036854775807"), None);
+    assert_eq!(date_text(parse_date("9999").unwrap()), "9999-12-31");
+}
+
+#[tokio::test]
+async fn test_history_record() -> anyhow::Result<()> {
+    let dir = env::temp_dir().join(format!("history-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = FileStore::new(&dir);
+
+    let snapshot = Snapshot::load(FIXTURE)?;
+    let old = snapshot.langs["en"].boxes[0].clone();
+    let day = 24 * 60 * 60;
+    assert!(record(&store, "en", &old, 10 * day).await?);
+    assert!(!record(&store, "en", &old, 11 * day).await?);
+
+    let mut new = old.clone();
+    new.save_point = Some(20);
+    assert!(record(&store, "en", &new, 20 * day).await?);
+    let mut retired = new.clone();
+    retired.retired = true;
+    assert!(record(&store, "en", &retired, 30 * day).await?);
+    assert!(!record(&store, "en", &retired, 31 * day).await?);
+
+    let history = store.list_history("en", old.id).await?;
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].valid_until, Some(20 * day));
+    assert_eq!(history[1].valid_until, Some(30 * day));
+
+    assert_eq!(box_at(&store, "en", old.id, 5 * day).await?, None);
+    assert_eq!(box_at(&store, "en", old.id, 15 * day).await?, Some(old));
+    assert_eq!(box_at(&store, "en", new.id, 20 * day).await?, Some(new));
+    assert_eq!(box_at(&store, "en", retired.id, 30 * day).await?, None);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
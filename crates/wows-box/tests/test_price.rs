@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use wows_box::{
+    lootbox::LootBoxRewardType,
+    price::{PriceBundle, PriceData, RegionalPrice},
+    valuation::ValuationTable,
+};
+
+fn price() -> PriceData {
+    let cny = |price| RegionalPrice {
+        region: "cn".to_owned(),
+        currency: "CNY".to_owned(),
+        price,
+    };
+    PriceData {
+        box_id: 1,
+        bundles: vec![
+            PriceBundle {
+                amount: 1,
+                gold: Some(250),
+                real: vec![cny(25.0)],
+            },
+            PriceBundle {
+                amount: 5,
+                gold: Some(1100),
+                real: vec![],
+            },
+            PriceBundle {
+                amount: 10,
+                gold: None,
+                real: vec![cny(200.0)],
+            },
+        ],
+    }
+}
+
+#[test]
+fn test_cheapest_bundles() {
+    let price = price();
+    assert_eq!(price.gold_cost(0), Some(0));
+    assert_eq!(price.gold_cost(3), Some(750));
+    // 5 + 5 + 1 + 1
+    assert_eq!(price.gold_cost(12), Some(2700));
+    // Buying 5 is cheaper than 4 single ones.
+    assert_eq!(price.gold_cost(4), Some(1000));
+    assert_eq!(price.gold_cost(5), Some(1100));
+
+    // Large amounts are mostly bought in the bundle of 5.
+    assert_eq!(price.gold_cost(3_000_003), Some(660_000_750));
+    let brute = |times: u32| {
+        let mut cost = vec![0u32; times as usize + 1];
+        for n in 1..=times as usize {
+            cost[n] = [(1, 250), (5, 1100)]
+                .iter()
+                .map(|&(amount, gold)| cost[n.saturating_sub(amount)] + gold)
+                .min()
+                .unwrap();
+        }
+        cost[times as usize]
+    };
+    for times in 0..60 {
+        assert_eq!(price.gold_cost(times), Some(brute(times)));
+    }
+
+    let real = price.real_cost(12);
+    assert_eq!(real.len(), 1);
+    assert!((real[0].price - 250.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_cost_report() {
+    let rewards = HashMap::from([
+        ((LootBoxRewardType::Gold, false), 500),
+        ((LootBoxRewardType::Gold, true), 500),
+        ((LootBoxRewardType::Credits, false), 300000),
+        ((LootBoxRewardType::CollectionAlbum { id: 1 }, false), 10),
+    ]);
+    let report = price().cost_report(10, &rewards, &HashMap::new(), &ValuationTable::default());
+
+    assert_eq!(report.gold, Some(2200));
+    assert!((report.value - 1100.0).abs() < 1e-6);
+    assert_eq!(report.unvalued, 1);
+    assert!((report.ratio().unwrap() - 0.5).abs() < 1e-9);
+}
@@ -0,0 +1,56 @@
+use std::{env, fs};
+
+use wows_box::{
+    snapshot::{Snapshot, SNAPSHOT_VERSION},
+    store::{FileStore, LootboxStore},
+};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/snapshot.jsonl");
+
+#[tokio::test]
+async fn test_snapshot_round_trip() -> anyhow::Result<()> {
+    let dir = env::temp_dir().join(format!("snapshot-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+
+    let snapshot = Snapshot::load(FIXTURE)?;
+    assert_eq!(snapshot.manifest.version, SNAPSHOT_VERSION);
+    assert_eq!(snapshot.manifest.records, 4);
+    assert_eq!(snapshot.langs["en"].boxes[0].name, "Test Container");
+
+    let store = FileStore::new(dir.join("store"));
+    // Deleted, the snapshot does not have it.
+    let mut stale = snapshot.langs["en"].boxes[0].clone();
+    stale.id = 99;
+    store.upsert_box("en", &stale).await?;
+    snapshot.import(&store).await?;
+    assert!(store.get_box("en", 1).await?.is_some());
+    assert_eq!(store.get_box("en", 99).await?, None);
+    assert!(store.get_item("en", 7).await?.is_some());
+    assert!(store.get_currency("en", "gold").await?.is_some());
+
+    let exported = Snapshot::export(&store, &["en"], Some("8671650".to_owned())).await?;
+    assert_eq!(exported.manifest.records, 4);
+    let path = dir.join("exported.jsonl");
+    exported.save(&path)?;
+    let reloaded = Snapshot::load(&path)?;
+    assert_eq!(reloaded.manifest, exported.manifest);
+    assert_eq!(reloaded.langs["en"].boxes, snapshot.langs["en"].boxes);
+    assert_eq!(reloaded.langs["en"].items, snapshot.langs["en"].items);
+    assert_eq!(
+        reloaded.langs["en"].currencies,
+        snapshot.langs["en"].currencies
+    );
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn test_snapshot_truncated() {
+    let data = fs::read_to_string(FIXTURE).unwrap();
+    let truncated: Vec<_> = data.lines().take(3).collect();
+    assert!(Snapshot::read(truncated.join("\n").as_bytes()).is_err());
+
+    let newer = data.replacen("\"version\":1", "\"version\":99", 1);
+    assert!(Snapshot::read(newer.as_bytes()).is_err());
+}
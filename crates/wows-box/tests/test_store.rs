@@ -0,0 +1,101 @@
+use std::{env, fs};
+
+use common::lootbox;
+use wows_box::{
+    currencies::{CurrencyData, CurrencyType},
+    item::ItemData,
+    store::{FileStore, LootboxStore},
+    user::UserData,
+};
+
+mod common;
+
+#[tokio::test]
+async fn test_file_store() -> anyhow::Result<()> {
+    let dir = env::temp_dir().join(format!("file-store-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let store = FileStore::new(&dir);
+
+    assert_eq!(store.get_box("en", 1).await?, None);
+    assert!(store.list_boxes("en").await?.is_empty());
+
+    store.upsert_box("en", &lootbox(1, "First")).await?;
+    store.upsert_box("en", &lootbox(2, "Second")).await?;
+    store.upsert_box("en", &lootbox(1, "Renamed")).await?;
+    let boxes = store.list_boxes("en").await?;
+    assert_eq!(boxes.len(), 2);
+    assert_eq!(store.get_box("en", 1).await?.unwrap().name, "Renamed");
+    // Languages are kept apart.
+    assert_eq!(store.get_box("zh-sg", 1).await?, None);
+
+    let item = ItemData {
+        name: "Signal".to_owned(),
+        short_name: String::new(),
+        id: 7,
+        icon: "icon.png".to_owned(),
+    };
+    store.upsert_item("en", &item).await?;
+    assert_eq!(store.get_item("en", 7).await?, Some(item));
+
+    let currency = CurrencyData {
+        r#type: CurrencyType::Credits,
+        name: "Credits".to_owned(),
+        icon: "credits.png".to_owned(),
+    };
+    store.upsert_currency("en", &currency).await?;
+    assert_eq!(
+        store
+            .get_currency("en", CurrencyType::Credits.as_icon_name())
+            .await?
+            .map(|t| t.name),
+        Some(currency.name)
+    );
+
+    let mut user = UserData::new("qq:1");
+    user.owned = vec![11];
+    assert!(store.save_user(&user).await?);
+    let saved = store.get_user("qq:1").await?.unwrap();
+    assert_eq!(saved.owned, user.owned);
+    assert_eq!(saved.version, 1);
+    // A save based on the old version loses, a reloaded one wins.
+    user.owned.push(12);
+    assert!(!store.save_user(&user).await?);
+    let mut user = saved;
+    user.owned.push(12);
+    assert!(store.save_user(&user).await?);
+    assert_eq!(store.get_user("qq:1").await?.unwrap().owned, [11, 12]);
+    store.delete_user("qq:1").await?;
+    assert_eq!(store.get_user("qq:1").await?, None);
+
+    // Written at once, and read back after another store changed the file.
+    store
+        .upsert_boxes("en", &[lootbox(3, "Third"), lootbox(2, "Renamed")])
+        .await?;
+    let other = FileStore::new(&dir);
+    other.upsert_box("en", &lootbox(4, "Fourth")).await?;
+    let boxes = store.list_boxes("en").await?;
+    assert_eq!(boxes.len(), 4);
+    assert_eq!(store.get_box("en", 2).await?.unwrap().name, "Renamed");
+
+    // Lookups still find the documents moved by a delete.
+    store.retain_boxes("en", &[2, 4]).await?;
+    assert_eq!(store.get_box("en", 1).await?, None);
+    assert_eq!(store.get_box("en", 4).await?.unwrap().name, "Fourth");
+    store.upsert_box("en", &lootbox(4, "Renamed")).await?;
+    let names: Vec<_> = store
+        .list_boxes("en")
+        .await?
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    assert_eq!(names, ["Renamed", "Renamed"]);
+
+    // Languages cannot reach out of the directory.
+    assert!(store.list_boxes("../en").await.is_err());
+    assert!(store.get_box("/etc", 1).await.is_err());
+    store.set_languages(&["en", "zh-sg"]).await?;
+    assert_eq!(store.list_languages().await?, ["en", "zh-sg"]);
+
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use common::{fixture, reward, ship_of};
+use wows_box::{lootbox::LootBoxRewardType, validate::LootBoxIssue};
+
+mod common;
+
+#[test]
+fn test_validate_ok() {
+    assert_eq!(fixture().validate(), vec![]);
+
+    // Item fillers are fine as long as a reward describes them.
+    let mut lootbox = fixture();
+    lootbox.filler.as_mut().unwrap().filler = LootBoxRewardType::CamoBoost { id: 7 };
+    assert_eq!(lootbox.validate(), vec![]);
+}
+
+#[test]
+fn test_validate_probability() {
+    let mut lootbox = fixture();
+    lootbox.slots[0].common[0].rewards[0].probability = 0.4;
+    let issues = lootbox.validate();
+    assert!(matches!(
+        issues[..],
+        [LootBoxIssue::ListProbability { slot: 0, .. }]
+    ));
+
+    lootbox.slots[0].common[0].probability = 0.7;
+    let issues = lootbox.validate();
+    assert!(matches!(
+        issues[..],
+        [LootBoxIssue::SlotProbability { slot: 0, .. }]
+    ));
+    assert!(issues.iter().all(LootBoxIssue::is_fatal));
+}
+
+#[test]
+fn test_validate_filler() {
+    let mut lootbox = fixture();
+    lootbox.filler = None;
+    assert_eq!(
+        lootbox.validate(),
+        vec![LootBoxIssue::MissingFiller {
+            slot: 0,
+            list: "ships".to_owned()
+        }]
+    );
+
+    // Resolved from the items when rendered.
+    let mut lootbox = fixture();
+    lootbox.filler.as_mut().unwrap().filler = LootBoxRewardType::CamoBoost { id: 8 };
+    assert_eq!(lootbox.validate(), vec![]);
+
+    // What the fetcher leaves when it cannot describe the filler.
+    let unresolved = LootBoxRewardType::Signal {
+        id: 8,
+        name: String::new(),
+    };
+    let mut lootbox = fixture();
+    lootbox.filler.as_mut().unwrap().filler = unresolved.clone();
+    assert_eq!(
+        lootbox.validate(),
+        vec![LootBoxIssue::UnresolvedFiller { filler: unresolved }]
+    );
+}
+
+#[test]
+fn test_validate_warnings() {
+    let mut lootbox = fixture();
+    lootbox.slots[0].valuable[0].rewards = vec![
+        reward(0.05, 1, ship_of(11, 12, true)),
+        reward(0.05, 1, ship_of(11, 8, true)),
+    ];
+    let issues = lootbox.validate();
+    assert_eq!(
+        issues,
+        vec![
+            LootBoxIssue::UnknownShipLevel {
+                slot: 0,
+                list: "ships".to_owned(),
+                id: 11,
+                level: 12
+            },
+            LootBoxIssue::DuplicateReward { slot: 0, id: 11 },
+        ]
+    );
+    assert!(!issues.iter().any(LootBoxIssue::is_fatal));
+}
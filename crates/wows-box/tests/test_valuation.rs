@@ -0,0 +1,103 @@
+use std::{collections::HashMap, env, fs};
+
+use common::ship_of;
+use wows_box::{
+    currencies::CurrencyType, item::ItemData, lootbox::LootBoxRewardType, valuation::ValuationTable,
+};
+
+mod common;
+
+fn ship(ship_level: u8, is_premium: bool) -> LootBoxRewardType {
+    ship_of(1, ship_level, is_premium)
+}
+
+fn signal(id: u64, name: &str) -> (LootBoxRewardType, ItemData) {
+    let item = ItemData {
+        name: name.to_owned(),
+        short_name: String::new(),
+        id,
+        icon: String::new(),
+    };
+    (
+        LootBoxRewardType::Signal {
+            id,
+            name: String::new(),
+        },
+        item,
+    )
+}
+
+#[test]
+fn test_valuation_default() {
+    let table = ValuationTable::default();
+    assert_eq!(table.value(&LootBoxRewardType::Gold, None), Some(1.0));
+    assert_eq!(table.value(&LootBoxRewardType::Slots, None), Some(300.0));
+
+    // Levels past the table use its last entry.
+    assert_eq!(table.value(&ship(8, true), None), Some(10000.0));
+    assert_eq!(table.value(&ship(8, false), None), Some(3000.0));
+    assert_eq!(table.value(&ship(20, true), None), Some(30000.0));
+
+    let (reward, item) = signal(1, "Signal");
+    assert_eq!(table.value(&reward, Some(&item)), Some(25.0));
+    assert_eq!(
+        table.value(&LootBoxRewardType::CollectionAlbum { id: 1 }, None),
+        None
+    );
+}
+
+#[test]
+fn test_valuation_load_and_override() {
+    let toml = r#"
+[currencies]
+credits = 0.001
+
+[ships]
+premium = [100, 200]
+
+[items]
+"2" = 40
+"Special" = 50
+
+[languages.zh-sg.items]
+"Special" = 60
+"#;
+    let path = env::temp_dir().join(format!("valuation-{}.toml", std::process::id()));
+    fs::write(&path, toml).unwrap();
+    let table = ValuationTable::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // Entries not in the file keep their built-in values.
+    assert_eq!(table.currencies[&CurrencyType::Credits], 0.001);
+    assert_eq!(table.currencies[&CurrencyType::Gold], 1.0);
+    assert_eq!(table.value(&ship(5, true), None), Some(200.0));
+    assert_eq!(table.value(&ship(5, false), None), Some(1000.0));
+
+    let (by_id, by_id_item) = signal(2, "Other");
+    let (by_name, by_name_item) = signal(3, "Special");
+    assert_eq!(table.value(&by_id, Some(&by_id_item)), Some(40.0));
+    assert_eq!(table.value(&by_name, Some(&by_name_item)), Some(50.0));
+
+    let zh = table.for_lang("zh-sg");
+    assert_eq!(zh.value(&by_name, Some(&by_name_item)), Some(60.0));
+    assert_eq!(zh.value(&by_id, Some(&by_id_item)), Some(40.0));
+    assert_eq!(table.for_lang("en"), {
+        let mut t = table.clone();
+        t.languages.clear();
+        t
+    });
+
+    // JSON tables load the same way.
+    let json = serde_json::to_string(&table).unwrap();
+    let path = env::temp_dir().join(format!("valuation-{}.json", std::process::id()));
+    fs::write(&path, json).unwrap();
+    assert_eq!(ValuationTable::load(&path).unwrap(), table);
+    fs::remove_file(&path).unwrap();
+
+    let rewards = HashMap::from([
+        ((by_id.clone(), false), 2),
+        ((LootBoxRewardType::CollectionAlbum { id: 1 }, false), 3),
+    ]);
+    let items = HashMap::from([(2, by_id_item)]);
+    assert_eq!(table.total(&rewards, &items), (80.0, 1));
+}